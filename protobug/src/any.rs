@@ -0,0 +1,264 @@
+use std::{borrow::Cow, mem};
+
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use protobuf::{
+    MessageDyn,
+    reflect::{FileDescriptor, MessageDescriptor, ReflectValueRef, RuntimeFieldType, RuntimeType},
+};
+use serde_json::Value;
+
+use crate::{json, selection};
+
+const ANY_MESSAGE: &str = "google.protobuf.Any";
+
+/// Key holding the type URL of an expanded `Any` in JSON, as in the canonical proto3 mapping.
+pub(crate) const TYPE_KEY: &str = "@type";
+
+/// Every message type loaded from the schema, used to look up the types packed into
+/// `google.protobuf.Any` values by their type URL.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TypeRegistry {
+    files: Vec<FileDescriptor>,
+}
+
+impl TypeRegistry {
+    pub(crate) fn new(files: Vec<FileDescriptor>) -> Self {
+        Self { files }
+    }
+
+    /// The message type named after the last `/` of `type_url`, e.g.
+    /// `type.googleapis.com/acme.billing.v1.Invoice`.
+    pub(crate) fn resolve(&self, type_url: &str) -> Option<MessageDescriptor> {
+        let name = type_url.rsplit('/').next()?;
+        if name.is_empty() {
+            return None;
+        }
+
+        let full_name = format!(".{name}");
+        self.files
+            .iter()
+            .find_map(|fd| fd.message_by_full_name(&full_name))
+    }
+
+    /// The type URL of `message` and the message it packs, when `message` is an `Any` whose type
+    /// is loaded and whose value decodes as that type.
+    pub(crate) fn unpack(
+        &self,
+        descriptor: &MessageDescriptor,
+        message: &dyn MessageDyn,
+    ) -> Option<(String, Box<dyn MessageDyn>)> {
+        if !is_any(descriptor) {
+            return None;
+        }
+
+        let ReflectValueRef::String(type_url) = descriptor
+            .field_by_number(1)?
+            .get_singular_field_or_default(message)
+        else {
+            return None;
+        };
+        let ReflectValueRef::Bytes(value) = descriptor
+            .field_by_number(2)?
+            .get_singular_field_or_default(message)
+        else {
+            return None;
+        };
+        let packed = self.resolve(type_url)?.parse_from_bytes(value).ok()?;
+
+        Some((type_url.to_owned(), packed))
+    }
+
+    /// The type named by the type URL of `bytes`, an encoded `Any`.
+    pub(crate) fn packed_type(&self, bytes: &[u8]) -> Option<MessageDescriptor> {
+        let mut offset = 0;
+        let mut type_url = None;
+
+        while offset < bytes.len() {
+            let tag = selection::read_varint(bytes, &mut offset)?;
+            let wire_type = (tag & 0x07) as u8;
+
+            match selection::parse_field(bytes, &mut offset, wire_type)? {
+                selection::ParsedField::LengthDelimited { payload_range, .. } if tag >> 3 == 1 => {
+                    type_url = std::str::from_utf8(&bytes[payload_range]).ok();
+                }
+                _ => {}
+            }
+        }
+
+        self.resolve(type_url?)
+    }
+}
+
+pub(crate) fn is_any(descriptor: &MessageDescriptor) -> bool {
+    descriptor.full_name() == ANY_MESSAGE
+}
+
+/// Replaces every `Any` in `value`, the JSON of a `descriptor` message, with the message it packs
+/// alongside an `@type` key. Values whose type is not loaded keep their `typeUrl` and base64
+/// `value`.
+pub(crate) fn expand_json(
+    registry: &TypeRegistry,
+    descriptor: &MessageDescriptor,
+    value: &mut Value,
+) {
+    if is_any(descriptor) {
+        if let Some(expanded) = expand_any(registry, value) {
+            *value = expanded;
+        }
+        return;
+    }
+
+    let Value::Object(object) = value else {
+        return;
+    };
+
+    for field in descriptor.fields() {
+        let Some(field_value) = object.get_mut(field.json_name()) else {
+            continue;
+        };
+
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(child)) => {
+                expand_json(registry, &child, field_value);
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(child)) => {
+                if let Value::Array(items) = field_value {
+                    for item in items {
+                        expand_json(registry, &child, item);
+                    }
+                }
+            }
+            RuntimeFieldType::Map(_, RuntimeType::Message(child)) => {
+                if let Value::Object(entries) = field_value {
+                    for entry in entries.values_mut() {
+                        expand_json(registry, &child, entry);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn expand_any(registry: &TypeRegistry, value: &Value) -> Option<Value> {
+    let type_url = value.get("typeUrl")?.as_str()?;
+    let descriptor = registry.resolve(type_url)?;
+    let bytes = BASE64_STANDARD
+        .decode(
+            value
+                .get("value")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+        )
+        .ok()?;
+    let packed = descriptor.parse_from_bytes(&bytes).ok()?;
+    let printed =
+        protobuf_json_mapping::print_to_string_with_options(&*packed, &json::print_options())
+            .ok()?;
+    let mut packed_json = serde_json::from_str::<Value>(&printed).ok()?;
+    expand_json(registry, &descriptor, &mut packed_json);
+    let Value::Object(fields) = packed_json else {
+        return None;
+    };
+
+    let mut expanded = serde_json::Map::new();
+    expanded.insert(TYPE_KEY.to_owned(), Value::String(type_url.to_owned()));
+    expanded.extend(fields);
+    Some(Value::Object(expanded))
+}
+
+/// Packs every `Any` written with an `@type` key in `json`, a `descriptor` message, back into its
+/// `typeUrl` and base64 `value`, so that it parses with `protobuf_json_mapping`. JSON without
+/// `@type` keys is returned as is.
+pub(crate) fn pack_json<'a>(
+    registry: &TypeRegistry,
+    descriptor: &MessageDescriptor,
+    json: &'a str,
+) -> Result<Cow<'a, str>, String> {
+    if !json.contains(TYPE_KEY) {
+        return Ok(Cow::Borrowed(json));
+    }
+
+    let mut value = serde_json::from_str::<Value>(json).map_err(|error| error.to_string())?;
+    pack_value(registry, descriptor, &mut value)?;
+    Ok(Cow::Owned(value.to_string()))
+}
+
+fn pack_value(
+    registry: &TypeRegistry,
+    descriptor: &MessageDescriptor,
+    value: &mut Value,
+) -> Result<(), String> {
+    if is_any(descriptor) {
+        return pack_any(registry, value);
+    }
+
+    let Value::Object(object) = value else {
+        return Ok(());
+    };
+
+    for field in descriptor.fields() {
+        // JSON input may name fields by their proto name as well as their JSON name.
+        let key = if object.contains_key(field.json_name()) {
+            field.json_name()
+        } else {
+            field.name()
+        };
+        let Some(field_value) = object.get_mut(key) else {
+            continue;
+        };
+
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(child)) => {
+                pack_value(registry, &child, field_value)?;
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(child)) => {
+                if let Value::Array(items) = field_value {
+                    for item in items {
+                        pack_value(registry, &child, item)?;
+                    }
+                }
+            }
+            RuntimeFieldType::Map(_, RuntimeType::Message(child)) => {
+                if let Value::Object(entries) = field_value {
+                    for entry in entries.values_mut() {
+                        pack_value(registry, &child, entry)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn pack_any(registry: &TypeRegistry, value: &mut Value) -> Result<(), String> {
+    let Value::Object(object) = value else {
+        return Ok(());
+    };
+    let Some(type_url) = object.remove(TYPE_KEY) else {
+        return Ok(());
+    };
+    let Value::String(type_url) = type_url else {
+        return Err(format!("`{TYPE_KEY}` must be a type URL string"));
+    };
+    let descriptor = registry
+        .resolve(&type_url)
+        .ok_or_else(|| format!("No loaded message type matches `{TYPE_KEY}` {type_url}"))?;
+
+    let mut packed_json = Value::Object(mem::take(object));
+    pack_value(registry, &descriptor, &mut packed_json)?;
+
+    let packed = protobuf_json_mapping::parse_dyn_from_str(&descriptor, &packed_json.to_string())
+        .map_err(|error| format!("{type_url}: {error}"))?;
+    let bytes = packed
+        .write_to_bytes_dyn()
+        .map_err(|error| format!("{type_url}: {error}"))?;
+
+    *value = serde_json::json!({
+        "typeUrl": type_url,
+        "value": BASE64_STANDARD.encode(bytes),
+    });
+    Ok(())
+}
@@ -38,6 +38,12 @@ pub(crate) fn json_input_as_text(raw_input: &[u8]) -> std::result::Result<&str,
         .change_context(Inspect)
 }
 
+pub(crate) fn text_format_input_as_text(
+    raw_input: &[u8],
+) -> std::result::Result<&str, Report<Inspect>> {
+    input_as_text(raw_input, InputFormat::Text)
+}
+
 pub(crate) fn resolve_edit_input_format(
     raw_input: &[u8],
     requested: InputFormat,
@@ -57,6 +63,8 @@ pub(crate) fn resolve_edit_input_format(
                 InputFormat::Hex
             } else if decode_base64(raw_input).is_ok() {
                 InputFormat::Base64
+            } else if looks_like_text_format(raw_input) {
+                InputFormat::Text
             } else {
                 InputFormat::Binary
             }
@@ -79,6 +87,35 @@ pub(crate) fn validate_multiple_input_format(
     }
 }
 
+/// Resolves how a framed binary stream is encoded as a whole: raw, or as hex or base64 text.
+pub(crate) fn resolve_stream_format(
+    raw_input: &[u8],
+    requested: InputFormat,
+) -> std::result::Result<InputFormat, Report<Inspect>> {
+    match requested {
+        InputFormat::Json | InputFormat::Text => Err(Report::new(Inspect).attach(format!(
+            "Length-prefixed framing requires binary, hex, or base64 input, got `{}`",
+            requested.as_str()
+        ))),
+        InputFormat::Auto => {
+            if let Ok(text) = std::str::from_utf8(raw_input) {
+                let trimmed = text.trim();
+
+                if looks_like_hex(trimmed) && decode_hex(raw_input).is_ok() {
+                    return Ok(InputFormat::Hex);
+                }
+
+                if looks_like_base64(trimmed) && decode_base64(raw_input).is_ok() {
+                    return Ok(InputFormat::Base64);
+                }
+            }
+
+            Ok(InputFormat::Binary)
+        }
+        other => Ok(other),
+    }
+}
+
 pub(crate) fn decode_input(
     raw_input: &[u8],
     input_format: InputFormat,
@@ -86,6 +123,8 @@ pub(crate) fn decode_input(
     match input_format {
         InputFormat::Json => Err(Report::new(Inspect)
             .attach("Input format: json must be parsed through the JSON message loader")),
+        InputFormat::Text => Err(Report::new(Inspect)
+            .attach("Input format: text must be parsed through the text format message loader")),
         InputFormat::Binary => Ok(raw_input.to_vec()),
         InputFormat::Base64 => decode_base64(raw_input),
         InputFormat::Hex => decode_hex(raw_input),
@@ -162,3 +201,35 @@ fn looks_like_base64(text: &str) -> bool {
             byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'/' | b'=' | b'-' | b'_')
         })
 }
+
+/// Whether the input starts like a text format message: a field name or `[extension]` followed by
+/// `:`, `{` or `<`, after any `#` comments.
+fn looks_like_text_format(raw_input: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(raw_input) else {
+        return false;
+    };
+    let Some(first) = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+    else {
+        return false;
+    };
+
+    let rest = if let Some(extension) = first.strip_prefix('[') {
+        match extension.split_once(']') {
+            Some((_, rest)) => rest,
+            None => return false,
+        }
+    } else {
+        let name_len = first
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+            .unwrap_or(first.len());
+        if name_len == 0 || first.starts_with(|ch: char| ch.is_ascii_digit()) {
+            return false;
+        }
+        &first[name_len..]
+    };
+
+    rest.trim_start().starts_with([':', '{', '<'])
+}
@@ -0,0 +1,199 @@
+use std::cmp::Reverse;
+
+use protobuf::{
+    descriptor::field_descriptor_proto::Type,
+    reflect::{FieldDescriptor, MessageDescriptor, RuntimeFieldType, RuntimeType},
+};
+
+use crate::selection::{self, ParsedField};
+
+/// `--message` value that selects the message type by trial-parsing the payload.
+pub(crate) const AUTO_MESSAGE: &str = "auto";
+
+/// Nested messages are not scored past this depth.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MessageCandidate {
+    pub(crate) descriptor: MessageDescriptor,
+    pub(crate) score: MatchScore,
+}
+
+/// How well a payload fits a message type. Fields are ordered by how strongly they rule a
+/// candidate out, so comparing two scores compares their ranks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct MatchScore {
+    pub(crate) parse_failed: bool,
+    pub(crate) wire_type_mismatches: usize,
+    pub(crate) invalid_utf8_strings: usize,
+    pub(crate) unknown_fields: usize,
+    pub(crate) known_fields: Reverse<usize>,
+}
+
+impl MessageCandidate {
+    pub(crate) fn summary(&self) -> String {
+        let score = &self.score;
+        if score.parse_failed {
+            return format!("{} (does not parse)", self.descriptor.full_name());
+        }
+
+        format!(
+            "{} ({} known, {} unknown, {} wire type mismatches, {} invalid strings)",
+            self.descriptor.full_name(),
+            score.known_fields.0,
+            score.unknown_fields,
+            score.wire_type_mismatches,
+            score.invalid_utf8_strings,
+        )
+    }
+}
+
+/// Scores `bytes` against every candidate and returns them best match first.
+pub(crate) fn rank_messages(
+    candidates: impl IntoIterator<Item = MessageDescriptor>,
+    bytes: &[u8],
+) -> Vec<MessageCandidate> {
+    let mut ranked = candidates
+        .into_iter()
+        .map(|descriptor| {
+            let mut score = Walk::new(&descriptor, bytes).score;
+            score.parse_failed |= descriptor.parse_from_bytes(bytes).is_err();
+
+            MessageCandidate { descriptor, score }
+        })
+        .collect::<Vec<_>>();
+
+    ranked.sort_by(|left, right| {
+        left.score.cmp(&right.score).then_with(|| {
+            left.descriptor
+                .full_name()
+                .cmp(right.descriptor.full_name())
+        })
+    });
+    ranked
+}
+
+/// Where `bytes` stop being a well-formed `descriptor` message: the start of the first field,
+/// at any depth, that is truncated, has a wire type its field does not accept, or holds invalid
+/// UTF-8 in a string field.
+pub(crate) fn first_invalid_offset(descriptor: &MessageDescriptor, bytes: &[u8]) -> Option<usize> {
+    Walk::new(descriptor, bytes).first_invalid
+}
+
+/// What a walk over a payload's wire data finds out about it as a given message type.
+#[derive(Debug, Default)]
+struct Walk {
+    score: MatchScore,
+    /// See [`first_invalid_offset`].
+    first_invalid: Option<usize>,
+}
+
+impl Walk {
+    fn new(descriptor: &MessageDescriptor, bytes: &[u8]) -> Self {
+        let mut walk = Self::default();
+        if !walk.message(Some(descriptor), bytes, 0, 0) {
+            walk.score.parse_failed = true;
+        }
+        walk
+    }
+
+    fn invalid_at(&mut self, offset: usize) {
+        self.first_invalid.get_or_insert(offset);
+    }
+
+    /// Walks one level of wire data that starts `base` bytes into the payload, returning `false`
+    /// when it is not well-formed.
+    fn message(
+        &mut self,
+        descriptor: Option<&MessageDescriptor>,
+        bytes: &[u8],
+        base: usize,
+        depth: usize,
+    ) -> bool {
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let field_start = offset;
+            let Some(tag) = selection::read_varint(bytes, &mut offset) else {
+                self.invalid_at(base + field_start);
+                return false;
+            };
+            let field_number = (tag >> 3) as u32;
+            let wire_type = (tag & 0x07) as u8;
+            if field_number == 0 {
+                self.invalid_at(base + field_start);
+                return false;
+            }
+
+            let Some(parsed) = selection::parse_field(bytes, &mut offset, wire_type) else {
+                self.invalid_at(base + field_start);
+                return false;
+            };
+            let Some(descriptor) = descriptor else {
+                continue;
+            };
+            let Some(field) = descriptor.field_by_number(field_number) else {
+                self.score.unknown_fields += 1;
+                continue;
+            };
+
+            self.score.known_fields.0 += 1;
+            if !wire_type_matches(&field, wire_type) {
+                self.score.wire_type_mismatches += 1;
+                self.invalid_at(base + field_start);
+                continue;
+            }
+
+            let ParsedField::LengthDelimited { payload_range, .. } = parsed else {
+                continue;
+            };
+            let payload = &bytes[payload_range.clone()];
+
+            match field.proto().type_() {
+                Type::TYPE_STRING if std::str::from_utf8(payload).is_err() => {
+                    self.score.invalid_utf8_strings += 1;
+                    self.invalid_at(base + field_start);
+                }
+                Type::TYPE_MESSAGE if depth < MAX_DEPTH => {
+                    let child = match field.runtime_field_type() {
+                        RuntimeFieldType::Singular(RuntimeType::Message(child))
+                        | RuntimeFieldType::Repeated(RuntimeType::Message(child)) => Some(child),
+                        _ => None,
+                    };
+
+                    if !self.message(
+                        child.as_ref(),
+                        payload,
+                        base + payload_range.start,
+                        depth + 1,
+                    ) {
+                        self.score.wire_type_mismatches += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        true
+    }
+}
+
+fn wire_type_matches(field: &FieldDescriptor, wire_type: u8) -> bool {
+    let expected = match field.proto().type_() {
+        Type::TYPE_INT32
+        | Type::TYPE_INT64
+        | Type::TYPE_UINT32
+        | Type::TYPE_UINT64
+        | Type::TYPE_SINT32
+        | Type::TYPE_SINT64
+        | Type::TYPE_BOOL
+        | Type::TYPE_ENUM => 0,
+        Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 | Type::TYPE_DOUBLE => 1,
+        Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE => 2,
+        Type::TYPE_GROUP => 3,
+        Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 | Type::TYPE_FLOAT => 5,
+    };
+
+    // Repeated scalars may arrive packed into a single length-delimited field.
+    wire_type == expected || (field.is_repeated() && wire_type == 2 && expected != 3)
+}
@@ -0,0 +1,702 @@
+use std::cmp::Ordering;
+
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use camino::Utf8PathBuf;
+use error_stack::{Report, ResultExt as _};
+use protobuf::{
+    MessageDyn,
+    reflect::{
+        ReflectEq as _, ReflectEqMode, ReflectFieldRef, ReflectMapRef, ReflectRepeatedRef,
+        ReflectValueBox, ReflectValueRef,
+    },
+};
+
+use crate::{
+    decode,
+    error::Inspect,
+    json,
+    message::{InputFormat, Inspector},
+    schema::{EmbeddedMessage, MessageSelection, SchemaSource, load_message_selection},
+    selection::{self, FieldPath, FieldPathSegment},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub schema: SchemaSource,
+    pub message: Option<String>,
+    pub embedded: Vec<EmbeddedMessage>,
+    pub left: Utf8PathBuf,
+    pub right: Utf8PathBuf,
+    pub left_input_format: InputFormat,
+    pub right_input_format: InputFormat,
+}
+
+/// Field-level differences between two messages of the same type, in field order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageDiff {
+    changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FieldChange {
+    pub(crate) kind: ChangeKind,
+    /// Where the value sits in the left message, as used by `ProtobufLine`, or `None` for added
+    /// values.
+    pub(crate) left_path: Option<FieldPath>,
+    /// Where the value sits in the right message, or `None` for removed values.
+    pub(crate) right_path: Option<FieldPath>,
+    /// Path of the changed field including map keys, e.g. `labels["env"]`.
+    pub(crate) display_path: String,
+    pub(crate) before: Option<ReflectValueBox>,
+    pub(crate) after: Option<ReflectValueBox>,
+}
+
+/// Loads both payloads and compares them field by field.
+pub fn diff_payloads(options: DiffOptions) -> std::result::Result<MessageDiff, Report<Inspect>> {
+    // Both sides share one schema load so their descriptors compare equal.
+    let selection = load_message_selection(
+        &options.schema,
+        options.message.as_deref(),
+        &options.embedded,
+    )?;
+    let left = load_side(&selection, &options.left, options.left_input_format)?;
+    let right = load_side(&selection, &options.right, options.right_input_format)?;
+
+    let Some(changes) = left.diff(&right) else {
+        return Err(Report::new(Inspect)
+            .attach("Payloads were parsed as different message types")
+            .attach(format!(
+                "Left message type: {}",
+                left.descriptor().full_name()
+            ))
+            .attach(format!(
+                "Right message type: {}",
+                right.descriptor().full_name()
+            )));
+    };
+
+    Ok(MessageDiff { changes })
+}
+
+fn load_side(
+    selection: &MessageSelection,
+    path: &Utf8PathBuf,
+    input_format: InputFormat,
+) -> std::result::Result<Inspector, Report<Inspect>> {
+    let input = decode::read_input(Some(path))?;
+    let input_format = decode::resolve_edit_input_format(&input, input_format, false)?;
+
+    let inspector = selection
+        .parse(&input, input_format)
+        .attach_with(|| format!("Input file: {path}"))?;
+    if let Some(note) = inspector.embed_note() {
+        eprintln!("{path}: {note}");
+    }
+
+    Ok(inspector)
+}
+
+impl MessageDiff {
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// One line per change: `+` for added, `-` for removed and `~` for changed values.
+    pub fn to_text(&self) -> String {
+        self.changes
+            .iter()
+            .map(|change| {
+                let path = &change.display_path;
+                match (&change.before, &change.after) {
+                    (Some(before), Some(after)) => format!(
+                        "~ {path}: {} -> {}\n",
+                        display_value(before),
+                        display_value(after)
+                    ),
+                    (Some(before), None) => format!("- {path}: {}\n", display_value(before)),
+                    (None, Some(after)) => format!("+ {path}: {}\n", display_value(after)),
+                    (None, None) => String::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// The changes as a JSON array, with values in their canonical JSON form.
+    pub fn to_json(&self) -> std::result::Result<String, Report<Inspect>> {
+        let mut changes = Vec::new();
+
+        for change in &self.changes {
+            let mut entry = serde_json::Map::new();
+            entry.insert("path".to_owned(), change.display_path.clone().into());
+            entry.insert(
+                "change".to_owned(),
+                match change.kind {
+                    ChangeKind::Added => "added",
+                    ChangeKind::Removed => "removed",
+                    ChangeKind::Changed => "changed",
+                }
+                .into(),
+            );
+            if let Some(before) = &change.before {
+                entry.insert("before".to_owned(), json_value(before.as_value_ref())?);
+            }
+            if let Some(after) = &change.after {
+                entry.insert("after".to_owned(), json_value(after.as_value_ref())?);
+            }
+            changes.push(serde_json::Value::Object(entry));
+        }
+
+        serde_json::to_string_pretty(&changes).change_context(Inspect)
+    }
+}
+
+/// Compares two messages of the same type.
+pub(crate) fn diff_messages(left: &dyn MessageDyn, right: &dyn MessageDyn) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_message(left, right, &Location::default(), &mut changes);
+    changes
+}
+
+/// Where a value being compared sits in each message.
+#[derive(Debug, Clone, Default)]
+struct Location {
+    left: FieldPath,
+    right: FieldPath,
+    display: String,
+}
+
+impl Location {
+    fn field(&self, name: &str) -> Self {
+        let segment = FieldPathSegment::Field(name.to_owned());
+        Self {
+            left: self.left.iter().cloned().chain([segment.clone()]).collect(),
+            right: self.right.iter().cloned().chain([segment]).collect(),
+            display: if self.display.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{}.{name}", self.display)
+            },
+        }
+    }
+
+    /// Removed elements are displayed with their index on the left and everything else with its
+    /// index on the right.
+    fn element(&self, left_index: Option<usize>, right_index: Option<usize>) -> Self {
+        let displayed_index = right_index.or(left_index).unwrap_or_default();
+        Self {
+            left: self
+                .left
+                .iter()
+                .cloned()
+                .chain([FieldPathSegment::Index(left_index.unwrap_or_default())])
+                .collect(),
+            right: self
+                .right
+                .iter()
+                .cloned()
+                .chain([FieldPathSegment::Index(right_index.unwrap_or_default())])
+                .collect(),
+            display: format!("{}[{displayed_index}]", self.display),
+        }
+    }
+
+    fn map_entry(&self, key: ReflectValueRef<'_>) -> Self {
+        let key = selection::format_value(key);
+        let segment = FieldPathSegment::Key(key.clone());
+        Self {
+            left: self.left.iter().cloned().chain([segment.clone()]).collect(),
+            right: self.right.iter().cloned().chain([segment]).collect(),
+            display: format!("{}[{key}]", self.display),
+        }
+    }
+}
+
+fn diff_message(
+    left: &dyn MessageDyn,
+    right: &dyn MessageDyn,
+    location: &Location,
+    changes: &mut Vec<FieldChange>,
+) {
+    for field in left.descriptor_dyn().fields() {
+        let location = location.field(field.name());
+
+        match (field.get_reflect(left), field.get_reflect(right)) {
+            (ReflectFieldRef::Optional(left), ReflectFieldRef::Optional(right)) => {
+                diff_value(left.value(), right.value(), &location, changes)
+            }
+            (ReflectFieldRef::Repeated(left), ReflectFieldRef::Repeated(right)) => {
+                diff_repeated(&left, &right, &location, changes)
+            }
+            (ReflectFieldRef::Map(left), ReflectFieldRef::Map(right)) => {
+                diff_map(&left, &right, &location, changes)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn diff_value(
+    left: Option<ReflectValueRef<'_>>,
+    right: Option<ReflectValueRef<'_>>,
+    location: &Location,
+    changes: &mut Vec<FieldChange>,
+) {
+    let kind = match (&left, &right) {
+        (None, None) => return,
+        (Some(ReflectValueRef::Message(left)), Some(ReflectValueRef::Message(right))) => {
+            diff_message(&**left, &**right, location, changes);
+            return;
+        }
+        (Some(left), Some(right)) if left.reflect_eq(right, &ReflectEqMode::nan_equal()) => {
+            return;
+        }
+        (Some(_), Some(_)) => ChangeKind::Changed,
+        (Some(_), None) => ChangeKind::Removed,
+        (None, Some(_)) => ChangeKind::Added,
+    };
+
+    changes.push(FieldChange {
+        kind,
+        left_path: left.is_some().then(|| location.left.clone()),
+        right_path: right.is_some().then(|| location.right.clone()),
+        display_path: location.display.clone(),
+        before: left.map(|value| value.to_box()),
+        after: right.map(|value| value.to_box()),
+    });
+}
+
+/// Above this many cells in the alignment table, the elements between the common prefix and suffix
+/// are compared by index instead.
+const MAX_ALIGNMENT_CELLS: usize = 1 << 20;
+
+/// Above this many removed × added pairs, elements are paired by index instead, since costing a
+/// pair diffs both elements.
+const MAX_PAIRING_CELLS: usize = 1 << 12;
+
+/// Elements are aligned on their longest common subsequence so an insertion shows up as a single
+/// added element rather than every later element changing.
+fn diff_repeated(
+    left: &ReflectRepeatedRef<'_>,
+    right: &ReflectRepeatedRef<'_>,
+    location: &Location,
+    changes: &mut Vec<FieldChange>,
+) {
+    let left = (0..left.len())
+        .map(|index| left.get(index))
+        .collect::<Vec<_>>();
+    let right = (0..right.len())
+        .map(|index| right.get(index))
+        .collect::<Vec<_>>();
+
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let mut flush = |removed: &mut Vec<usize>, added: &mut Vec<usize>| {
+        for (left_index, right_index) in pair_elements(&left, removed, &right, added) {
+            diff_value(
+                left_index.map(|index| left[index].clone()),
+                right_index.map(|index| right[index].clone()),
+                &location.element(left_index, right_index),
+                changes,
+            );
+        }
+
+        removed.clear();
+        added.clear();
+    };
+
+    for step in align(&left, &right) {
+        match step {
+            Alignment::Same => flush(&mut removed, &mut added),
+            Alignment::Removed(index) => removed.push(index),
+            Alignment::Added(index) => added.push(index),
+        }
+    }
+    flush(&mut removed, &mut added);
+}
+
+/// Decides which of the elements removed and added between two common elements are edits of each
+/// other. Pairing costs one per changed leaf value and leaving an element unpaired costs one, so
+/// elements are only paired up when they have enough in common.
+fn pair_elements(
+    left: &[ReflectValueRef<'_>],
+    removed: &[usize],
+    right: &[ReflectValueRef<'_>],
+    added: &[usize],
+) -> Vec<(Option<usize>, Option<usize>)> {
+    if removed.len().saturating_mul(added.len()) > MAX_PAIRING_CELLS {
+        return (0..removed.len().max(added.len()))
+            .map(|index| (removed.get(index).copied(), added.get(index).copied()))
+            .collect();
+    }
+
+    let pair_costs = removed
+        .iter()
+        .map(|&removed| {
+            added
+                .iter()
+                .map(|&added| match (&left[removed], &right[added]) {
+                    (ReflectValueRef::Message(left), ReflectValueRef::Message(right)) => {
+                        diff_messages(&**left, &**right).len()
+                    }
+                    _ => 1,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // costs[i][j] is the cheapest way to match up removed[i..] with added[j..].
+    let mut costs = vec![vec![0_usize; added.len() + 1]; removed.len() + 1];
+    for i in (0..=removed.len()).rev() {
+        for j in (0..=added.len()).rev() {
+            costs[i][j] = match (i < removed.len(), j < added.len()) {
+                (true, true) => (pair_costs[i][j] + costs[i + 1][j + 1])
+                    .min(costs[i + 1][j] + 1)
+                    .min(costs[i][j + 1] + 1),
+                (true, false) => costs[i + 1][j] + 1,
+                (false, true) => costs[i][j + 1] + 1,
+                (false, false) => 0,
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < removed.len() || j < added.len() {
+        if i < removed.len()
+            && j < added.len()
+            && costs[i][j] == pair_costs[i][j] + costs[i + 1][j + 1]
+        {
+            pairs.push((Some(removed[i]), Some(added[j])));
+            i += 1;
+            j += 1;
+        } else if i < removed.len() && costs[i][j] == costs[i + 1][j] + 1 {
+            pairs.push((Some(removed[i]), None));
+            i += 1;
+        } else {
+            pairs.push((None, Some(added[j])));
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Same,
+    Removed(usize),
+    Added(usize),
+}
+
+fn align(left: &[ReflectValueRef<'_>], right: &[ReflectValueRef<'_>]) -> Vec<Alignment> {
+    let equal = |left: &ReflectValueRef<'_>, right: &ReflectValueRef<'_>| {
+        left.reflect_eq(right, &ReflectEqMode::nan_equal())
+    };
+
+    let prefix = left
+        .iter()
+        .zip(right)
+        .take_while(|(left, right)| equal(left, right))
+        .count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(left, right)| equal(left, right))
+        .count();
+    let (left_end, right_end) = (left.len() - suffix, right.len() - suffix);
+
+    let mut steps = vec![Alignment::Same; prefix];
+    let (mut i, mut j) = (prefix, prefix);
+    if (left_end - prefix).saturating_mul(right_end - prefix) > MAX_ALIGNMENT_CELLS {
+        while i < left_end && j < right_end {
+            if equal(&left[i], &right[j]) {
+                steps.push(Alignment::Same);
+            } else {
+                steps.push(Alignment::Removed(i));
+                steps.push(Alignment::Added(j));
+            }
+            i += 1;
+            j += 1;
+        }
+    } else {
+        // lengths[i][j] is the length of the longest common subsequence of the remaining
+        // left[prefix + i..left_end] and right[prefix + j..right_end].
+        let (rows, columns) = (left_end - prefix, right_end - prefix);
+        let mut lengths = vec![vec![0_usize; columns + 1]; rows + 1];
+        for row in (0..rows).rev() {
+            for column in (0..columns).rev() {
+                lengths[row][column] = if equal(&left[prefix + row], &right[prefix + column]) {
+                    lengths[row + 1][column + 1] + 1
+                } else {
+                    lengths[row + 1][column].max(lengths[row][column + 1])
+                };
+            }
+        }
+
+        while i < left_end && j < right_end {
+            let (row, column) = (i - prefix, j - prefix);
+            if equal(&left[i], &right[j]) {
+                steps.push(Alignment::Same);
+                i += 1;
+                j += 1;
+            } else if lengths[row + 1][column] >= lengths[row][column + 1] {
+                steps.push(Alignment::Removed(i));
+                i += 1;
+            } else {
+                steps.push(Alignment::Added(j));
+                j += 1;
+            }
+        }
+    }
+    steps.extend((i..left_end).map(Alignment::Removed));
+    steps.extend((j..right_end).map(Alignment::Added));
+    steps.extend(std::iter::repeat_n(Alignment::Same, suffix));
+    steps
+}
+
+fn diff_map(
+    left: &ReflectMapRef<'_>,
+    right: &ReflectMapRef<'_>,
+    location: &Location,
+    changes: &mut Vec<FieldChange>,
+) {
+    let mut keys = left
+        .into_iter()
+        .map(|(key, _)| key)
+        .chain(right.into_iter().map(|(key, _)| key))
+        .collect::<Vec<_>>();
+    keys.sort_by(compare_map_keys);
+    keys.dedup_by(|left, right| compare_map_keys(left, right) == Ordering::Equal);
+
+    for key in keys {
+        diff_value(
+            left.get(key.clone()),
+            right.get(key.clone()),
+            &location.map_entry(key),
+            changes,
+        );
+    }
+}
+
+fn compare_map_keys(left: &ReflectValueRef<'_>, right: &ReflectValueRef<'_>) -> Ordering {
+    fn integer(key: &ReflectValueRef<'_>) -> Option<i128> {
+        match *key {
+            ReflectValueRef::I32(value) => Some(value.into()),
+            ReflectValueRef::I64(value) => Some(value.into()),
+            ReflectValueRef::U32(value) => Some(value.into()),
+            ReflectValueRef::U64(value) => Some(value.into()),
+            ReflectValueRef::Bool(value) => Some(value.into()),
+            _ => None,
+        }
+    }
+
+    match (integer(left), integer(right)) {
+        (Some(left), Some(right)) => left.cmp(&right),
+        _ => selection::format_value(left.clone()).cmp(&selection::format_value(right.clone())),
+    }
+}
+
+fn display_value(value: &ReflectValueBox) -> String {
+    match value.as_value_ref() {
+        ReflectValueRef::Message(message) => {
+            format!("{{{}}}", protobuf::text_format::print_to_string(&*message))
+        }
+        value => selection::format_value(value),
+    }
+}
+
+/// Renders a value the way canonical JSON output renders it inside a message.
+fn json_value(
+    value: ReflectValueRef<'_>,
+) -> std::result::Result<serde_json::Value, Report<Inspect>> {
+    Ok(match value {
+        ReflectValueRef::U32(value) => value.into(),
+        ReflectValueRef::I32(value) => value.into(),
+        ReflectValueRef::U64(value) => value.to_string().into(),
+        ReflectValueRef::I64(value) => value.to_string().into(),
+        ReflectValueRef::F32(value) => float_json_value(value.into()),
+        ReflectValueRef::F64(value) => float_json_value(value),
+        ReflectValueRef::Bool(value) => value.into(),
+        ReflectValueRef::String(value) => value.into(),
+        ReflectValueRef::Bytes(value) => BASE64_STANDARD.encode(value).into(),
+        ReflectValueRef::Enum(descriptor, number) => match descriptor.value_by_number(number) {
+            Some(value) => value.name().into(),
+            None => number.into(),
+        },
+        ReflectValueRef::Message(message) => {
+            let json = protobuf_json_mapping::print_to_string_with_options(
+                &*message,
+                &json::print_options(),
+            )
+            .change_context(Inspect)?;
+            serde_json::from_str(&json).change_context(Inspect)?
+        }
+    })
+}
+
+fn float_json_value(value: f64) -> serde_json::Value {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn trace_bundle_schema() -> SchemaSource {
+        SchemaSource::Proto {
+            paths: vec![Utf8PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../protogen/proto/trace-bundle.proto"
+            ))],
+            includes: Vec::new(),
+        }
+    }
+
+    fn sample_path(name: &str) -> Utf8PathBuf {
+        Utf8PathBuf::from(format!(
+            "{}/../protogen/samples/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+    }
+
+    fn diff_json(left: &str, right: &str) -> MessageDiff {
+        let dir = tempdir().unwrap();
+        let left_path = Utf8PathBuf::from_path_buf(dir.path().join("left.json")).unwrap();
+        let right_path = Utf8PathBuf::from_path_buf(dir.path().join("right.json")).unwrap();
+        fs::write(&left_path, left).unwrap();
+        fs::write(&right_path, right).unwrap();
+
+        diff_payloads(DiffOptions {
+            schema: trace_bundle_schema(),
+            message: Some("TraceBundle".to_owned()),
+            embedded: Vec::new(),
+            left: left_path,
+            right: right_path,
+            left_input_format: InputFormat::Auto,
+            right_input_format: InputFormat::Json,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn identical_payloads_in_different_encodings_have_no_changes() {
+        let diff = diff_payloads(DiffOptions {
+            schema: trace_bundle_schema(),
+            message: Some("TraceBundle".to_owned()),
+            embedded: Vec::new(),
+            left: sample_path("trace-bundle-http.bin"),
+            right: sample_path("trace-bundle-http.hex"),
+            left_input_format: InputFormat::Binary,
+            right_input_format: InputFormat::Auto,
+        })
+        .unwrap();
+
+        assert!(!diff.has_changes());
+        assert_eq!(diff.to_text(), "");
+    }
+
+    #[test]
+    fn diff_reports_scalar_repeated_and_map_changes() {
+        let diff = diff_json(
+            r#"{
+                "exportId": "a",
+                "environment": "Staging",
+                "labels": {"env": "prod", "team": "core"},
+                "services": [
+                    {"name": "api", "version": "1"},
+                    {"name": "db", "version": "2"}
+                ],
+                "alerts": [{"code": "E1", "relatedSpanIds": ["s1", "s2", "s3"]}]
+            }"#,
+            r#"{
+                "exportId": "b",
+                "labels": {"env": "dev", "region": "eu"},
+                "services": [
+                    {"name": "api", "version": "1"},
+                    {"name": "cache", "version": "1"},
+                    {"name": "db", "version": "3"}
+                ],
+                "alerts": [{"code": "E1", "relatedSpanIds": ["s1", "s3"]}],
+                "user": {}
+            }"#,
+        );
+
+        assert!(diff.has_changes());
+        assert_snapshot!(diff.to_text());
+
+        let env = diff
+            .changes
+            .iter()
+            .find(|change| change.display_path == r#"labels["env"]"#)
+            .unwrap();
+        let path = vec![
+            FieldPathSegment::Field("labels".to_owned()),
+            FieldPathSegment::Key(r#""env""#.to_owned()),
+        ];
+        assert_eq!(env.left_path.as_ref(), Some(&path));
+        assert_eq!(env.right_path.as_ref(), Some(&path));
+    }
+
+    #[test]
+    fn align_trims_common_ends_and_falls_back_to_index_pairing() {
+        let values =
+            |range: std::ops::Range<u32>| range.map(ReflectValueRef::U32).collect::<Vec<_>>();
+
+        let left = values(0..2000);
+        let mut right = left.clone();
+        right[1000] = ReflectValueRef::U32(5000);
+        let steps = align(&left, &right);
+        assert_eq!(steps.len(), 2001);
+        assert_eq!(
+            steps[999..1002],
+            [
+                Alignment::Same,
+                Alignment::Removed(1000),
+                Alignment::Added(1000)
+            ]
+        );
+        assert!(steps[1002..].iter().all(|step| *step == Alignment::Same));
+
+        let steps = align(&values(0..2000), &values(2000..4001));
+        assert_eq!(steps.len(), 4001);
+        assert_eq!(
+            steps[..4],
+            [
+                Alignment::Removed(0),
+                Alignment::Added(0),
+                Alignment::Removed(1),
+                Alignment::Added(1)
+            ]
+        );
+        assert_eq!(steps[4000], Alignment::Added(2000));
+    }
+
+    #[test]
+    fn diff_json_lists_changes_with_canonical_values() {
+        let diff = diff_json(
+            r#"{"exportId": "a", "rawEnvelope": "AAE=", "services": [{"name": "api"}]}"#,
+            r#"{"exportId": "a", "rawEnvelope": "AAI=", "services": []}"#,
+        );
+
+        assert_snapshot!(diff.to_json().unwrap());
+    }
+}
@@ -8,53 +8,94 @@ use error_stack::{Report, ResultExt as _};
 use crate::{
     decode,
     error::Inspect,
+    framing::{self, EncodedMessage, StreamLayout},
     json,
-    message::{InputFormat, Inspector},
-    schema::load_inspector,
+    message::{Framing, InputFormat, Inspector},
+    schema::{EmbeddedMessage, SchemaSource, load_inspector, load_message_selection},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EditOptions {
-    pub schema: Utf8PathBuf,
+    pub schema: SchemaSource,
     pub message: Option<String>,
+    pub embedded: Vec<EmbeddedMessage>,
     pub file: Option<Utf8PathBuf>,
     pub input_format: InputFormat,
     pub filter: Option<String>,
     pub multiple: bool,
+    pub framing: Framing,
 }
 
 struct EditedMessage {
     inspector: Inspector,
     source_format: InputFormat,
+    /// gRPC-Web trailer frames after a single framed message. A stream keeps its trailer in
+    /// its layout instead.
+    trailer: Vec<u8>,
+}
+
+struct EditedMessages {
+    messages: Vec<EditedMessage>,
+    layout: StreamLayout,
 }
 
 pub fn edit_to_json(options: EditOptions) -> std::result::Result<String, Report<Inspect>> {
     edit(options)?.inspector.canonical_json()
 }
 
-pub fn edit_to_bytes(options: EditOptions) -> std::result::Result<Vec<u8>, Report<Inspect>> {
-    edit(options)?.inspector.bytes()
+pub fn edit_to_text(options: EditOptions) -> std::result::Result<String, Report<Inspect>> {
+    Ok(edit(options)?.inspector.text_format())
+}
+
+/// The edited message, along with any gRPC-Web trailer frames that followed it in the input.
+pub fn edit_to_bytes(options: EditOptions) -> std::result::Result<EncodedMessage, Report<Inspect>> {
+    let edited = edit(options)?;
+
+    Ok(EncodedMessage {
+        bytes: edited.inspector.bytes()?,
+        trailer: edited.trailer,
+    })
 }
 
 pub fn edit_to_json_lines(options: EditOptions) -> std::result::Result<String, Report<Inspect>> {
-    let (edited, had_trailing_newline) = edit_multiple(options)?;
+    let edited = edit_multiple(options)?;
     let lines = edited
-        .into_iter()
+        .messages
+        .iter()
         .map(|edited| json::compact_json(&edited.inspector))
         .collect::<std::result::Result<Vec<_>, _>>()?;
-    Ok(json::join_lines(lines, had_trailing_newline))
+    // Binary streams have no trailing newline of their own to mirror.
+    let trailing_newline =
+        edited.layout.had_trailing_newline || edited.layout.framing != Framing::Lines;
+    Ok(json::join_lines(lines, trailing_newline))
 }
 
 pub fn edit_to_encoded_lines(
     options: EditOptions,
     output_format: InputFormat,
 ) -> std::result::Result<String, Report<Inspect>> {
-    let (edited, had_trailing_newline) = edit_multiple(options)?;
+    let edited = edit_multiple(options)?;
+    if edited.layout.framing != Framing::Lines
+        && !matches!(output_format, InputFormat::Json | InputFormat::Text)
+    {
+        return Err(Report::new(Inspect)
+            .attach("length-prefixed framing is written as one stream, not as separate lines"));
+    }
+
     let lines = edited
-        .into_iter()
+        .messages
+        .iter()
         .map(|edited| json::encode_line_output(&edited.inspector, output_format))
         .collect::<std::result::Result<Vec<_>, _>>()?;
-    Ok(json::join_lines(lines, had_trailing_newline))
+    // Binary streams have no trailing newline of their own to mirror.
+    let trailing_newline =
+        edited.layout.had_trailing_newline || edited.layout.framing != Framing::Lines;
+    Ok(json::join_lines(lines, trailing_newline))
+}
+
+/// Edits every message of a length-prefixed stream and returns the re-framed binary stream.
+pub fn edit_to_framed_bytes(options: EditOptions) -> std::result::Result<Vec<u8>, Report<Inspect>> {
+    frame_messages(&edit_multiple(options)?)
 }
 
 pub fn edit_in_place(options: EditOptions) -> std::result::Result<(), Report<Inspect>> {
@@ -68,6 +109,19 @@ pub fn edit_in_place(options: EditOptions) -> std::result::Result<(), Report<Ins
             .attach("`edit --in-place` does not support stdin; pass a file path instead"));
     }
 
+    if options.framing != Framing::Lines {
+        let edited = edit_multiple(options)?;
+        let output = framing::encode_messages(
+            edited.messages.iter().map(|edited| &edited.inspector),
+            &edited.layout,
+            None,
+        )?;
+
+        return std::fs::write(&path, output)
+            .attach_with(|| format!("Output file: {path}"))
+            .change_context(Inspect);
+    }
+
     if options.multiple {
         let output_format = match options.input_format {
             InputFormat::Base64 => InputFormat::Base64,
@@ -93,6 +147,7 @@ pub fn edit_in_place(options: EditOptions) -> std::result::Result<(), Report<Ins
     let edited = edit(options)?;
     let bytes = match edited.source_format {
         InputFormat::Json => edited.inspector.canonical_json()?.into_bytes(),
+        InputFormat::Text => edited.inspector.text_format().into_bytes(),
         InputFormat::Base64 => BASE64_STANDARD
             .encode(edited.inspector.bytes()?)
             .into_bytes(),
@@ -114,13 +169,19 @@ fn edit(options: EditOptions) -> std::result::Result<EditedMessage, Report<Inspe
     }
 
     let input = decode::read_input(options.file.as_deref())?;
-    let source_format = decode::resolve_edit_input_format(&input, options.input_format, false)?;
+    let single = framing::unframe_single(&input, options.framing, options.input_format)?;
+    let source_format =
+        decode::resolve_edit_input_format(&single.payload, single.input_format, false)?;
     let mut inspector = load_inspector(
-        options.schema.as_ref(),
+        &options.schema,
         options.message.as_deref(),
-        &input,
+        &options.embedded,
+        &single.payload,
         source_format,
     )?;
+    if let Some(note) = inspector.embed_note() {
+        eprintln!("{note}");
+    }
 
     if let Some(filter) = options.filter.as_deref() {
         let filtered = json::apply_json_filter(&inspector.canonical_json()?, filter)?;
@@ -132,34 +193,32 @@ fn edit(options: EditOptions) -> std::result::Result<EditedMessage, Report<Inspe
     Ok(EditedMessage {
         inspector,
         source_format,
+        trailer: single.trailer,
     })
 }
 
-fn edit_multiple(
-    options: EditOptions,
-) -> std::result::Result<(Vec<EditedMessage>, bool), Report<Inspect>> {
-    decode::validate_multiple_input_format(options.input_format)?;
+fn edit_multiple(options: EditOptions) -> std::result::Result<EditedMessages, Report<Inspect>> {
     let input = decode::read_input(options.file.as_deref())?;
-    let text = std::str::from_utf8(&input)
-        .attach("Input format: line-based text")
-        .change_context(Inspect)?;
-    let had_trailing_newline = text.ends_with('\n');
-    let source_format = decode::resolve_edit_input_format(&input, options.input_format, true)?;
-    let mut edited = Vec::new();
-
-    for line in text.lines() {
-        let line = line.trim_end_matches('\r');
-
-        if line.is_empty() {
-            return Err(Report::new(Inspect)
-                .attach("`edit --multiple` does not support empty lines in the input file"));
+    let multiple = framing::split_input(&input, options.framing, options.input_format, "edit")?;
+    if !options.multiple && multiple.payloads.len() != 1 {
+        return Err(Report::new(Inspect).attach(format!(
+            "Expected exactly one framed message but found {}; pass `--multiple` to edit each one \
+             separately",
+            multiple.payloads.len()
+        )));
+    }
+    let selection = load_message_selection(
+        &options.schema,
+        options.message.as_deref(),
+        &options.embedded,
+    )?;
+    let mut messages = Vec::new();
+
+    for (index, payload) in multiple.payloads.iter().enumerate() {
+        let mut inspector = selection.parse(payload, multiple.payload_format)?;
+        if let Some(note) = inspector.embed_note() {
+            eprintln!("Message {}: {note}", index + 1);
         }
-        let mut inspector = load_inspector(
-            options.schema.as_ref(),
-            options.message.as_deref(),
-            line.as_bytes(),
-            source_format,
-        )?;
 
         if let Some(filter) = options.filter.as_deref() {
             let filtered = json::apply_json_filter(&inspector.canonical_json()?, filter)?;
@@ -168,11 +227,35 @@ fn edit_multiple(
                 .map_err(|error| Report::new(Inspect).attach(error))?;
         }
 
-        edited.push(EditedMessage {
+        messages.push(EditedMessage {
             inspector,
-            source_format,
+            source_format: multiple.payload_format,
+            trailer: Vec::new(),
         });
     }
 
-    Ok((edited, had_trailing_newline))
+    Ok(EditedMessages {
+        messages,
+        layout: multiple.layout(options.framing),
+    })
+}
+
+fn frame_messages(edited: &EditedMessages) -> std::result::Result<Vec<u8>, Report<Inspect>> {
+    let payloads = edited
+        .messages
+        .iter()
+        .map(|edited| edited.inspector.bytes())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    match edited.layout.framing {
+        Framing::Lines => {
+            Err(Report::new(Inspect)
+                .attach("line framing is written with the line-based edit helpers"))
+        }
+        framing => {
+            let mut stream = framing::join_frames(payloads.iter().map(Vec::as_slice), framing);
+            stream.extend_from_slice(&edited.layout.trailer);
+            Ok(stream)
+        }
+    }
 }
@@ -17,11 +17,14 @@ use tempfile::tempdir;
 use super::*;
 use crate::{json, schema::load_inspector};
 
-fn schema_path() -> Utf8PathBuf {
-    Utf8PathBuf::from(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/../protogen/proto/system-event.proto"
-    ))
+fn schema() -> SchemaSource {
+    SchemaSource::Proto {
+        paths: vec![Utf8PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../protogen/proto/system-event.proto"
+        ))],
+        includes: Vec::new(),
+    }
 }
 
 fn sample_bytes() -> Vec<u8> {
@@ -46,8 +49,9 @@ fn sample_bytes() -> Vec<u8> {
 
 fn sample_json() -> String {
     load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -73,8 +77,9 @@ fn filtered_edit_bytes_match_snapshot() {
     let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.json")).unwrap();
     fs::write(&input_path, sample_json()).unwrap();
     let bytes = edit_to_bytes(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path),
         input_format: InputFormat::Json,
         filter: Some(
@@ -82,8 +87,10 @@ fn filtered_edit_bytes_match_snapshot() {
                 .to_owned(),
         ),
         multiple: false,
+        framing: Framing::Lines,
     })
-    .unwrap();
+    .unwrap()
+    .bytes;
 
     assert_snapshot!(hex::encode(&bytes));
 }
@@ -94,14 +101,17 @@ fn edit_binary_input_round_trips_sample_bytes() {
     let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.bin")).unwrap();
     fs::write(&input_path, sample_bytes()).unwrap();
     let bytes = edit_to_bytes(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path),
         input_format: InputFormat::Binary,
         filter: None,
         multiple: false,
+        framing: Framing::Lines,
     })
-    .unwrap();
+    .unwrap()
+    .bytes;
 
     assert_eq!(bytes, sample_bytes());
 }
@@ -112,14 +122,17 @@ fn edit_hex_input_round_trips_sample_bytes() {
     let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.hex")).unwrap();
     fs::write(&input_path, hex::encode(sample_bytes())).unwrap();
     let bytes = edit_to_bytes(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path),
         input_format: InputFormat::Hex,
         filter: None,
         multiple: false,
+        framing: Framing::Lines,
     })
-    .unwrap();
+    .unwrap()
+    .bytes;
 
     assert_eq!(bytes, sample_bytes());
 }
@@ -130,14 +143,17 @@ fn edit_base64_input_round_trips_sample_bytes() {
     let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.base64")).unwrap();
     fs::write(&input_path, BASE64_STANDARD.encode(sample_bytes())).unwrap();
     let bytes = edit_to_bytes(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path),
         input_format: InputFormat::Base64,
         filter: None,
         multiple: false,
+        framing: Framing::Lines,
     })
-    .unwrap();
+    .unwrap()
+    .bytes;
 
     assert_eq!(bytes, sample_bytes());
 }
@@ -149,12 +165,14 @@ fn edit_in_place_preserves_hex_encoding() {
     fs::write(&input_path, hex::encode(sample_bytes())).unwrap();
 
     edit_in_place(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path.clone()),
         input_format: InputFormat::Hex,
         filter: Some(r#".click.x = 100 | .click.y = 42"#.to_owned()),
         multiple: false,
+        framing: Framing::Lines,
     })
     .unwrap();
 
@@ -169,12 +187,14 @@ fn edit_in_place_preserves_json_encoding() {
     fs::write(&input_path, sample_json()).unwrap();
 
     edit_in_place(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path.clone()),
         input_format: InputFormat::Json,
         filter: Some(r#".click.x = 100 | .click.y = 42"#.to_owned()),
         multiple: false,
+        framing: Framing::Lines,
     })
     .unwrap();
 
@@ -196,12 +216,14 @@ fn edit_multiple_hex_lines_match_snapshot() {
 
     let output = edit_to_encoded_lines(
         EditOptions {
-            schema: schema_path(),
+            schema: schema(),
             message: Some("SystemEvent".to_owned()),
+            embedded: Vec::new(),
             file: Some(input_path),
             input_format: InputFormat::Hex,
             filter: Some(r#".click.x = 100 | .click.y = 42"#.to_owned()),
             multiple: true,
+            framing: Framing::Lines,
         },
         InputFormat::Hex,
     )
@@ -227,12 +249,14 @@ fn edit_multiple_base64_to_json_lines_match_snapshot() {
     .unwrap();
 
     let output = edit_to_json_lines(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path),
         input_format: InputFormat::Base64,
         filter: Some(r#".click.x = 100 | .click.y = 42"#.to_owned()),
         multiple: true,
+        framing: Framing::Lines,
     })
     .unwrap();
 
@@ -256,15 +280,303 @@ fn edit_in_place_preserves_multiple_base64_encoding() {
     .unwrap();
 
     edit_in_place(EditOptions {
-        schema: schema_path(),
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path.clone()),
         input_format: InputFormat::Base64,
         filter: Some(r#".click.x = 100 | .click.y = 42"#.to_owned()),
         multiple: true,
+        framing: Framing::Lines,
     })
     .unwrap();
 
     let written = fs::read_to_string(&input_path).unwrap();
     assert_snapshot!(written);
 }
+
+fn sample_with_unknown_fields() -> SystemEvent {
+    let mut event = SystemEvent::parse_from_bytes(&sample_bytes()).unwrap();
+    event.mut_unknown_fields().add_varint(99, 7);
+    event
+        .mut_click()
+        .mut_unknown_fields()
+        .add_length_delimited(50, b"future".to_vec());
+    event
+}
+
+#[test]
+fn edit_in_place_preserves_unknown_fields() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.bin")).unwrap();
+    fs::write(
+        &input_path,
+        sample_with_unknown_fields().write_to_bytes().unwrap(),
+    )
+    .unwrap();
+
+    edit_in_place(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path.clone()),
+        input_format: InputFormat::Binary,
+        filter: Some(r#".click.x = 7"#.to_owned()),
+        multiple: false,
+        framing: Framing::Lines,
+    })
+    .unwrap();
+
+    let mut expected = sample_with_unknown_fields();
+    expected.mut_click().x = 7;
+    let written = SystemEvent::parse_from_bytes(&fs::read(&input_path).unwrap()).unwrap();
+    assert_eq!(written, expected);
+}
+
+fn delimited_stream(messages: &[Vec<u8>]) -> Vec<u8> {
+    crate::framing::join_delimited(messages.iter().map(Vec::as_slice))
+}
+
+#[test]
+fn edit_in_place_rewrites_delimited_binary_stream() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.bin")).unwrap();
+    fs::write(
+        &input_path,
+        delimited_stream(&[sample_bytes(), sample_bytes()]),
+    )
+    .unwrap();
+
+    edit_in_place(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path.clone()),
+        input_format: InputFormat::Binary,
+        filter: Some(r#".reason = "a much longer reason than before""#.to_owned()),
+        multiple: true,
+        framing: Framing::Delimited,
+    })
+    .unwrap();
+
+    let payloads = crate::framing::split_delimited(&fs::read(&input_path).unwrap()).unwrap();
+    assert_eq!(payloads.len(), 2);
+    for payload in payloads {
+        let event = SystemEvent::parse_from_bytes(&payload).unwrap();
+        assert_eq!(
+            event.reason.as_deref(),
+            Some("a much longer reason than before")
+        );
+    }
+}
+
+#[test]
+fn edit_delimited_stream_preserves_hex_text_encoding() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.hex")).unwrap();
+    fs::write(
+        &input_path,
+        format!("{}\n", hex::encode(delimited_stream(&[sample_bytes()]))),
+    )
+    .unwrap();
+
+    edit_in_place(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path.clone()),
+        input_format: InputFormat::Auto,
+        filter: Some(r#".click.x = 7"#.to_owned()),
+        multiple: true,
+        framing: Framing::Delimited,
+    })
+    .unwrap();
+
+    let mut expected = SystemEvent::parse_from_bytes(&sample_bytes()).unwrap();
+    expected.mut_click().x = 7;
+    assert_eq!(
+        fs::read_to_string(&input_path).unwrap(),
+        format!(
+            "{}\n",
+            hex::encode(delimited_stream(&[expected.write_to_bytes().unwrap()]))
+        )
+    );
+}
+
+#[test]
+fn edit_delimited_stream_prints_json_lines() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.bin")).unwrap();
+    fs::write(
+        &input_path,
+        delimited_stream(&[sample_bytes(), sample_bytes()]),
+    )
+    .unwrap();
+
+    let output = edit_to_json_lines(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Binary,
+        filter: Some(".click.x = 1".to_owned()),
+        multiple: true,
+        framing: Framing::Delimited,
+    })
+    .unwrap();
+
+    assert_eq!(output.lines().count(), 2);
+    assert!(output.ends_with('\n'));
+    assert!(output.lines().all(|line| line.contains(r#""x":1"#)));
+}
+
+#[test]
+fn edit_grpc_body_reframes_each_message() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("body.base64")).unwrap();
+    let body = crate::framing::join_grpc([sample_bytes().as_slice(), sample_bytes().as_slice()]);
+    fs::write(&input_path, BASE64_STANDARD.encode(body)).unwrap();
+
+    let output = edit_to_framed_bytes(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Base64,
+        filter: Some(r#".click.x = 7"#.to_owned()),
+        multiple: true,
+        framing: Framing::Grpc,
+    })
+    .unwrap();
+
+    let mut expected = SystemEvent::parse_from_bytes(&sample_bytes()).unwrap();
+    expected.mut_click().x = 7;
+    let expected = expected.write_to_bytes().unwrap();
+    assert_eq!(
+        output,
+        crate::framing::join_grpc([expected.as_slice(), expected.as_slice()])
+    );
+}
+
+#[test]
+fn edit_grpc_web_body_in_place_keeps_trailers() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("body.bin")).unwrap();
+    let trailer = b"\x80\x00\x00\x00\x0fgrpc-status:0\r\n";
+    let mut body = crate::framing::join_grpc([sample_bytes().as_slice()]);
+    body.extend_from_slice(trailer);
+    fs::write(&input_path, body).unwrap();
+
+    edit_in_place(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path.clone()),
+        input_format: InputFormat::Binary,
+        filter: Some(r#".click.x = 7"#.to_owned()),
+        multiple: true,
+        framing: Framing::Grpc,
+    })
+    .unwrap();
+
+    let mut expected = SystemEvent::parse_from_bytes(&sample_bytes()).unwrap();
+    expected.mut_click().x = 7;
+    let mut expected = crate::framing::join_grpc([expected.write_to_bytes().unwrap().as_slice()]);
+    expected.extend_from_slice(trailer);
+    assert_eq!(fs::read(&input_path).unwrap(), expected);
+}
+
+#[test]
+fn edit_single_grpc_frame_requires_multiple_for_several_frames() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("body.bin")).unwrap();
+    fs::write(
+        &input_path,
+        crate::framing::join_grpc([sample_bytes().as_slice(), sample_bytes().as_slice()]),
+    )
+    .unwrap();
+
+    let error = edit_to_bytes(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Binary,
+        filter: None,
+        multiple: false,
+        framing: Framing::Grpc,
+    })
+    .err()
+    .unwrap();
+
+    assert!(format!("{error:?}").contains("Expected exactly one framed message but found 2"));
+}
+
+#[test]
+fn edit_in_place_preserves_text_format_encoding() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.textproto")).unwrap();
+    fs::write(
+        &input_path,
+        indoc::indoc! {r#"
+            # Captured from the staging cluster.
+            timestamp {
+              seconds: 1234567
+              nanos: 123
+            }
+            reason: "user clicked"
+            click {
+              x: 42
+              y: 100
+            }
+        "#},
+    )
+    .unwrap();
+
+    edit_in_place(EditOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path.clone()),
+        input_format: InputFormat::Auto,
+        filter: Some(r#".click.x = 100 | .click.y = 42"#.to_owned()),
+        multiple: false,
+        framing: Framing::Lines,
+    })
+    .unwrap();
+
+    let written = fs::read_to_string(&input_path).unwrap();
+    assert_snapshot!(written);
+}
+
+#[test]
+fn edit_multiple_prints_text_format_lines() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.hex")).unwrap();
+    fs::write(
+        &input_path,
+        format!(
+            "{}\n{}\n",
+            hex::encode(sample_bytes()),
+            hex::encode(sample_bytes())
+        ),
+    )
+    .unwrap();
+
+    let output = edit_to_encoded_lines(
+        EditOptions {
+            schema: schema(),
+            message: Some("SystemEvent".to_owned()),
+            embedded: Vec::new(),
+            file: Some(input_path),
+            input_format: InputFormat::Hex,
+            filter: None,
+            multiple: true,
+            framing: Framing::Lines,
+        },
+        InputFormat::Text,
+    )
+    .unwrap();
+
+    assert_snapshot!(output);
+}
@@ -1,8 +1,8 @@
 use protobuf::{
     MessageDyn,
     reflect::{
-        MessageDescriptor, ReflectFieldRef, ReflectValueBox, ReflectValueRef, RuntimeFieldType,
-        RuntimeType,
+        MessageDescriptor, ReflectFieldRef, ReflectMapRef, ReflectValueBox, ReflectValueRef,
+        RuntimeFieldType, RuntimeType,
     },
 };
 
@@ -75,7 +75,17 @@ pub(crate) fn omitted_default_enum_hint(
 
             omitted_default_enum_hint(&*nested, &nested.descriptor_dyn(), nested_path)
         }
-        ReflectFieldRef::Map(_) => None,
+        ReflectFieldRef::Map(map) => {
+            let (selection::FieldPathSegment::Key(key), nested_path) = rest.split_first()? else {
+                return None;
+            };
+            let ReflectValueRef::Message(nested) = map.get(entry_key(&map, key)?.as_value_ref())?
+            else {
+                return None;
+            };
+
+            omitted_default_enum_hint(&*nested, &nested.descriptor_dyn(), nested_path)
+        }
     }
 }
 
@@ -127,7 +137,21 @@ pub(crate) fn enum_selection(
                 };
                 enum_selection(&*nested, &nested.descriptor_dyn(), nested_path)
             }
-            ReflectFieldRef::Map(_) => None,
+            ReflectFieldRef::Map(map) => {
+                let (selection::FieldPathSegment::Key(key), nested_path) = rest.split_first()?
+                else {
+                    return None;
+                };
+                match (map.get(entry_key(&map, key)?.as_value_ref())?, nested_path) {
+                    (ReflectValueRef::Enum(enum_descriptor, current_number), []) => {
+                        enum_selection_for_number(&enum_descriptor, current_number)
+                    }
+                    (ReflectValueRef::Message(nested), _) => {
+                        enum_selection(&*nested, &nested.descriptor_dyn(), nested_path)
+                    }
+                    _ => None,
+                }
+            }
         },
     }
 }
@@ -213,11 +237,48 @@ pub(crate) fn cycle_enum_variant(
                 repeated.set(*index, nested);
                 Some(next_variant)
             }
+            RuntimeFieldType::Map(_, value_type) => {
+                let (selection::FieldPathSegment::Key(key), nested_path) = rest.split_first()?
+                else {
+                    return None;
+                };
+
+                let map = field.get_map(message);
+                let key = entry_key(&map, key)?;
+                let mut value = map.get(key.as_value_ref())?.to_box();
+                let next_variant = match (value_type, &mut value, nested_path) {
+                    (RuntimeType::Enum(enum_descriptor), ReflectValueBox::Enum(_, number), []) => {
+                        let next_variant = cycle_enum_descriptor(&enum_descriptor, *number, delta)?;
+                        *number = next_variant.value();
+                        next_variant.name().to_owned()
+                    }
+                    (
+                        RuntimeType::Message(message_descriptor),
+                        ReflectValueBox::Message(nested_message),
+                        _,
+                    ) => cycle_enum_variant(
+                        &mut **nested_message,
+                        &message_descriptor,
+                        nested_path,
+                        delta,
+                    )?,
+                    _ => return None,
+                };
+                field.mut_map(message).insert(key, value);
+                Some(next_variant)
+            }
             _ => None,
         },
     }
 }
 
+/// The key of the entry of `map` that a [`selection::FieldPathSegment::Key`] names.
+fn entry_key(map: &ReflectMapRef<'_>, key: &str) -> Option<ReflectValueBox> {
+    map.into_iter()
+        .find(|(entry_key, _)| selection::format_value(entry_key.clone()) == key)
+        .map(|(entry_key, _)| entry_key.to_box())
+}
+
 fn cycle_enum_descriptor(
     enum_descriptor: &protobuf::reflect::EnumDescriptor,
     current_number: i32,
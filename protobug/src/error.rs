@@ -13,5 +13,13 @@ pub struct InvalidSchema;
 pub struct NoTopLevelMessages;
 
 #[derive(Debug, Display, Error)]
-#[display("Schemas with multiple top-level messages are not currently supported")]
+#[display("Schema has multiple top-level messages; pass `--message <name>` or `--message auto`")]
 pub struct MultipleTopLevelMessages;
+
+#[derive(Debug, Display, Error)]
+#[display("Message name matches messages in multiple schema files")]
+pub struct AmbiguousMessageName;
+
+#[derive(Debug, Display, Error)]
+#[display("Payload does not parse as any message in the schema")]
+pub struct NoMatchingMessage;
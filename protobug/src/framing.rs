@@ -0,0 +1,422 @@
+use std::borrow::Cow;
+
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use error_stack::{Report, ResultExt as _};
+
+use crate::{
+    decode,
+    error::Inspect,
+    json,
+    message::{Framing, InputFormat, Inspector},
+    selection,
+};
+
+/// The payloads of a `--multiple` input, split according to its framing.
+pub(crate) struct MultipleInput {
+    /// Message payloads, each still encoded as `payload_format`.
+    pub(crate) payloads: Vec<Vec<u8>>,
+    pub(crate) payload_format: InputFormat,
+    /// Encoding of the input as a whole, used to write it back out.
+    pub(crate) stream_format: InputFormat,
+    pub(crate) had_trailing_newline: bool,
+    /// gRPC-Web trailer frames after the messages, kept as they were.
+    pub(crate) trailer: Vec<u8>,
+}
+
+/// How a `--multiple` input was framed and encoded, so its messages can be written back the same
+/// way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StreamLayout {
+    pub(crate) framing: Framing,
+    pub(crate) payload_format: InputFormat,
+    pub(crate) stream_format: InputFormat,
+    pub(crate) had_trailing_newline: bool,
+    /// gRPC-Web trailer frames, written back unchanged after the messages.
+    pub(crate) trailer: Vec<u8>,
+}
+
+impl MultipleInput {
+    pub(crate) fn layout(&self, framing: Framing) -> StreamLayout {
+        StreamLayout {
+            framing,
+            payload_format: self.payload_format,
+            stream_format: self.stream_format,
+            had_trailing_newline: self.had_trailing_newline,
+            trailer: self.trailer.clone(),
+        }
+    }
+}
+
+/// Writes messages back out in `layout`. `format` replaces the encoding of each line, or of the
+/// whole stream for length-prefixed framing; `None` keeps the input's.
+pub(crate) fn encode_messages<'a>(
+    inspectors: impl IntoIterator<Item = &'a Inspector>,
+    layout: &StreamLayout,
+    format: Option<InputFormat>,
+) -> std::result::Result<Vec<u8>, Report<Inspect>> {
+    let inspectors = inspectors.into_iter();
+    let format = format.unwrap_or(if layout.framing == Framing::Lines {
+        layout.payload_format
+    } else {
+        layout.stream_format
+    });
+
+    let mut output = if layout.framing == Framing::Lines {
+        let lines = inspectors
+            .map(|inspector| json::encode_line_output(inspector, format))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        lines.join("\n").into_bytes()
+    } else {
+        let payloads = inspectors
+            .map(Inspector::bytes)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut stream = join_frames(payloads.iter().map(Vec::as_slice), layout.framing);
+        stream.extend_from_slice(&layout.trailer);
+        match format {
+            InputFormat::Base64 => BASE64_STANDARD.encode(stream).into_bytes(),
+            InputFormat::Hex => hex::encode(stream).into_bytes(),
+            InputFormat::Binary => stream,
+            other => {
+                return Err(Report::new(Inspect).attach(format!(
+                    "Length-prefixed framing is written as binary, hex, or base64, not `{}`",
+                    other.as_str()
+                )));
+            }
+        }
+    };
+
+    // A raw binary stream has no trailing newline, whatever the input had.
+    if layout.had_trailing_newline && format != InputFormat::Binary {
+        output.push(b'\n');
+    }
+
+    Ok(output)
+}
+
+pub(crate) fn split_input(
+    raw_input: &[u8],
+    framing: Framing,
+    input_format: InputFormat,
+    command: &str,
+) -> std::result::Result<MultipleInput, Report<Inspect>> {
+    if framing == Framing::Lines {
+        return split_lines(raw_input, input_format, command);
+    }
+
+    let stream_format = decode::resolve_stream_format(raw_input, input_format)?;
+    let bytes = decode::decode_input(raw_input, stream_format)?;
+    let Frames { payloads, trailer } = split_frames(&bytes, framing)?;
+
+    Ok(MultipleInput {
+        payloads,
+        payload_format: InputFormat::Binary,
+        stream_format,
+        had_trailing_newline: stream_format != InputFormat::Binary && raw_input.ends_with(b"\n"),
+        trailer,
+    })
+}
+
+/// The one message of a single-payload input, unwrapped from its framing.
+pub(crate) struct SinglePayload<'a> {
+    pub(crate) payload: Cow<'a, [u8]>,
+    pub(crate) input_format: InputFormat,
+    /// gRPC-Web trailer frames after the message, to write back after it.
+    pub(crate) trailer: Vec<u8>,
+}
+
+/// An encoded message and the gRPC-Web trailer frames that followed it in the input, which go
+/// after the message once it is framed again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedMessage {
+    pub bytes: Vec<u8>,
+    pub trailer: Vec<u8>,
+}
+
+/// Unwraps the one message of a framed single-payload input. Unframed input is returned as is.
+pub(crate) fn unframe_single(
+    raw_input: &[u8],
+    framing: Framing,
+    input_format: InputFormat,
+) -> std::result::Result<SinglePayload<'_>, Report<Inspect>> {
+    if framing == Framing::Lines {
+        return Ok(SinglePayload {
+            payload: Cow::Borrowed(raw_input),
+            input_format,
+            trailer: Vec::new(),
+        });
+    }
+
+    let stream_format = decode::resolve_stream_format(raw_input, input_format)?;
+    let bytes = decode::decode_input(raw_input, stream_format)?;
+    let Frames {
+        mut payloads,
+        trailer,
+    } = split_frames(&bytes, framing)?;
+
+    if payloads.len() != 1 {
+        return Err(Report::new(Inspect).attach(format!(
+            "Expected exactly one framed message but found {}; pass `--multiple` to handle each \
+             one separately",
+            payloads.len()
+        )));
+    }
+
+    Ok(SinglePayload {
+        payload: Cow::Owned(payloads.remove(0)),
+        input_format: InputFormat::Binary,
+        trailer,
+    })
+}
+
+/// Joins binary payloads with the given framing. [`Framing::Lines`] adds nothing, so it is only
+/// meaningful for a single payload.
+pub fn join_frames<'a>(payloads: impl IntoIterator<Item = &'a [u8]>, framing: Framing) -> Vec<u8> {
+    match framing {
+        Framing::Lines => payloads.into_iter().flatten().copied().collect(),
+        Framing::Delimited => join_delimited(payloads),
+        Framing::Grpc => join_grpc(payloads),
+    }
+}
+
+/// The message payloads of a framed stream and the bytes after the last of them, which only
+/// gRPC-Web trailer frames leave.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Frames {
+    pub(crate) payloads: Vec<Vec<u8>>,
+    pub(crate) trailer: Vec<u8>,
+}
+
+fn split_frames(bytes: &[u8], framing: Framing) -> std::result::Result<Frames, Report<Inspect>> {
+    let payloads = match framing {
+        Framing::Lines => vec![bytes.to_vec()],
+        Framing::Delimited => split_delimited(bytes)?,
+        Framing::Grpc => return split_grpc(bytes),
+    };
+
+    Ok(Frames {
+        payloads,
+        trailer: Vec::new(),
+    })
+}
+
+fn split_lines(
+    raw_input: &[u8],
+    input_format: InputFormat,
+    command: &str,
+) -> std::result::Result<MultipleInput, Report<Inspect>> {
+    decode::validate_multiple_input_format(input_format)?;
+    let text = std::str::from_utf8(raw_input)
+        .attach("Input format: line-based text")
+        .change_context(Inspect)?;
+    let mut payloads = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            return Err(Report::new(Inspect).attach(format!(
+                "`{command} --multiple` does not support empty lines in the input file"
+            )));
+        }
+
+        payloads.push(line.as_bytes().to_vec());
+    }
+
+    Ok(MultipleInput {
+        payloads,
+        payload_format: input_format,
+        stream_format: input_format,
+        had_trailing_newline: text.ends_with('\n'),
+        trailer: Vec::new(),
+    })
+}
+
+/// Splits a stream of varint length-prefixed messages into their payloads.
+pub(crate) fn split_delimited(bytes: &[u8]) -> std::result::Result<Vec<Vec<u8>>, Report<Inspect>> {
+    let mut payloads = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let frame_start = offset;
+        let length = selection::read_varint(bytes, &mut offset)
+            .and_then(|length| usize::try_from(length).ok())
+            .ok_or_else(|| {
+                Report::new(Inspect).attach(format!(
+                    "Truncated length prefix for message {} at byte offset {frame_start}",
+                    payloads.len() + 1
+                ))
+            })?;
+        let end = offset
+            .checked_add(length)
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| {
+                Report::new(Inspect).attach(format!(
+                    "Message {} at byte offset {frame_start} declares {length} bytes but only {} remain",
+                    payloads.len() + 1,
+                    bytes.len() - offset
+                ))
+            })?;
+
+        payloads.push(bytes[offset..end].to_vec());
+        offset = end;
+    }
+
+    Ok(payloads)
+}
+
+/// Joins payloads into a stream of varint length-prefixed messages.
+pub(crate) fn join_delimited<'a>(payloads: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut stream = Vec::new();
+
+    for payload in payloads {
+        let mut length = payload.len() as u64;
+        while length >= 0x80 {
+            stream.push((length as u8 & 0x7f) | 0x80);
+            length >>= 7;
+        }
+        stream.push(length as u8);
+        stream.extend_from_slice(payload);
+    }
+
+    stream
+}
+
+/// gRPC frames are a compressed flag byte followed by a 4-byte big-endian payload length.
+const GRPC_HEADER_LEN: usize = 5;
+
+/// Splits a gRPC or gRPC-Web message body into its frame payloads and the gRPC-Web trailer
+/// frames that end it, which are returned as is.
+pub(crate) fn split_grpc(bytes: &[u8]) -> std::result::Result<Frames, Report<Inspect>> {
+    let mut payloads = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let frame_start = offset;
+        let header = bytes.get(offset..offset + GRPC_HEADER_LEN).ok_or_else(|| {
+            Report::new(Inspect).attach(format!(
+                "Truncated gRPC frame header for message {} at byte offset {frame_start}",
+                payloads.len() + 1
+            ))
+        })?;
+        offset += GRPC_HEADER_LEN;
+
+        match header[0] {
+            0 => {}
+            // gRPC-Web sends trailers as a frame with the most significant flag bit set.
+            flag if flag & 0x80 != 0 => {
+                return Ok(Frames {
+                    payloads,
+                    trailer: bytes[frame_start..].to_vec(),
+                });
+            }
+            1 => {
+                return Err(Report::new(Inspect).attach(format!(
+                    "gRPC frame {} at byte offset {frame_start} is compressed, which is not \
+                     supported; capture the traffic with compression disabled",
+                    payloads.len() + 1
+                )));
+            }
+            flag => {
+                return Err(Report::new(Inspect).attach(format!(
+                    "gRPC frame {} at byte offset {frame_start} has an invalid flag byte {flag:#04x}",
+                    payloads.len() + 1
+                )));
+            }
+        }
+
+        let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let end = offset
+            .checked_add(length)
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| {
+                Report::new(Inspect).attach(format!(
+                    "gRPC frame {} at byte offset {frame_start} declares {length} bytes but only {} remain",
+                    payloads.len() + 1,
+                    bytes.len() - offset
+                ))
+            })?;
+
+        payloads.push(bytes[offset..end].to_vec());
+        offset = end;
+    }
+
+    Ok(Frames {
+        payloads,
+        trailer: Vec::new(),
+    })
+}
+
+/// Joins payloads into uncompressed gRPC frames.
+pub(crate) fn join_grpc<'a>(payloads: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut stream = Vec::new();
+
+    for payload in payloads {
+        stream.push(0);
+        stream.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        stream.extend_from_slice(payload);
+    }
+
+    stream
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn delimited_stream_round_trips() {
+        let payloads = vec![vec![0x08, 0x01], Vec::new(), vec![0xaa; 300]];
+        let stream = join_delimited(payloads.iter().map(Vec::as_slice));
+
+        assert_eq!(&stream[..4], &[0x02, 0x08, 0x01, 0x00]);
+        assert_eq!(&stream[4..6], &[0xac, 0x02]);
+        assert_eq!(split_delimited(&stream).unwrap(), payloads);
+    }
+
+    #[test]
+    fn delimited_stream_reports_truncated_message() {
+        let error = split_delimited(&[0x02, 0x08, 0x01, 0x05, 0x08])
+            .err()
+            .unwrap();
+
+        assert!(
+            format!("{error:?}")
+                .contains("Message 2 at byte offset 3 declares 5 bytes but only 1 remain")
+        );
+    }
+
+    #[test]
+    fn grpc_frames_round_trip_and_keep_web_trailers() {
+        let payloads = vec![vec![0x08, 0x01], vec![0x10, 0x02, 0x18, 0x03]];
+        let mut body = join_grpc(payloads.iter().map(Vec::as_slice));
+
+        assert_eq!(&body[..7], &[0x00, 0x00, 0x00, 0x00, 0x02, 0x08, 0x01]);
+        assert_eq!(
+            split_grpc(&body).unwrap(),
+            Frames {
+                payloads: payloads.clone(),
+                trailer: Vec::new(),
+            }
+        );
+
+        let trailer = [0x80, 0x00, 0x00, 0x00, 0x02, b'o', b'k'];
+        body.extend_from_slice(&trailer);
+        assert_eq!(
+            split_grpc(&body).unwrap(),
+            Frames {
+                payloads,
+                trailer: trailer.to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn grpc_frames_reject_compressed_payloads() {
+        let error = split_grpc(&[0x01, 0x00, 0x00, 0x00, 0x01, 0x00])
+            .err()
+            .unwrap();
+
+        assert!(format!("{error:?}").contains("gRPC frame 1 at byte offset 0 is compressed"));
+    }
+}
@@ -7,52 +7,118 @@ use error_stack::{Report, ResultExt as _};
 use crate::{
     decode,
     error::Inspect,
-    message::{DisplayOptions, InputFormat, Inspector, SaveTargets},
-    schema::load_inspector,
+    framing::{self, EncodedMessage, StreamLayout},
+    message::{DisplayOptions, Framing, InputFormat, Inspector, SaveTargets},
+    schema::{EmbeddedMessage, SchemaSource, load_inspector, load_message_selection},
     tui,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InspectOptions {
-    pub schema: Utf8PathBuf,
+    pub schema: SchemaSource,
     pub message: Option<String>,
+    pub embedded: Vec<EmbeddedMessage>,
     pub file: Option<Utf8PathBuf>,
     pub input_format: InputFormat,
     pub multiple: bool,
+    pub framing: Framing,
     pub display_options: DisplayOptions,
     pub save_targets: SaveTargets,
 }
 
 pub fn run_inspect(options: InspectOptions) -> std::result::Result<(), Report<Inspect>> {
     let save_targets = options.save_targets.clone();
-    let display_options = options.display_options;
-    let inspectors = inspect(options)?;
+    let display_options = options.display_options.clone();
+    let (inspectors, layout) = inspect(options)?;
 
     let mut terminal = tui::Session::new().change_context(Inspect)?;
     let mut app =
         tui::App::new(inspectors, save_targets, display_options).change_context(Inspect)?;
+    if let Some(layout) = layout {
+        app = app.with_stream_layout(layout);
+    }
     app.run(terminal.terminal_mut()).change_context(Inspect)?;
 
     Ok(())
 }
 
 pub fn inspect_to_json(options: InspectOptions) -> std::result::Result<String, Report<Inspect>> {
-    inspect_one(options)?.canonical_json()
+    inspect_printed(options)?.0.canonical_json()
+}
+
+pub fn inspect_to_text(options: InspectOptions) -> std::result::Result<String, Report<Inspect>> {
+    Ok(inspect_printed(options)?.0.text_format())
+}
+
+/// The message as shown in the Protobuf pane, with well-known types such as timestamps and
+/// durations in their readable forms.
+pub fn inspect_to_human(options: InspectOptions) -> std::result::Result<String, Report<Inspect>> {
+    let local_time = options.display_options.local_time;
+    let lines = inspect_printed(options)?.0.protobuf_lines(local_time);
+
+    Ok(lines
+        .into_iter()
+        .map(|line| format!("{}\n", line.text))
+        .collect())
+}
+
+/// The encoded message, along with any gRPC-Web trailer frames that followed it in the input.
+pub fn inspect_to_bytes(
+    options: InspectOptions,
+) -> std::result::Result<EncodedMessage, Report<Inspect>> {
+    let (inspector, trailer) = inspect_printed(options)?;
+
+    Ok(EncodedMessage {
+        bytes: inspector.bytes()?,
+        trailer,
+    })
+}
+
+/// Loads the payload of a `--print-format` run. The type `--message auto` picked, how the other
+/// candidates scored, and `--embed` fields left as bytes go to stderr so stdout holds only the
+/// message.
+fn inspect_printed(
+    options: InspectOptions,
+) -> std::result::Result<(Inspector, Vec<u8>), Report<Inspect>> {
+    let (inspector, trailer) = inspect_one(options)?;
+    if let Some(ranking) = detection_ranking(&inspector) {
+        eprint!("{ranking}");
+    }
+    if let Some(note) = inspector.embed_note() {
+        eprintln!("{note}");
+    }
+
+    Ok((inspector, trailer))
 }
 
-pub fn inspect_to_bytes(options: InspectOptions) -> std::result::Result<Vec<u8>, Report<Inspect>> {
-    inspect_one(options)?.bytes()
+/// Names the message type `--message auto` picked, followed by every candidate that parsed and
+/// its score, best match first.
+fn detection_ranking(inspector: &Inspector) -> Option<String> {
+    let detected = inspector.detected_type()?;
+    let mut ranking = format!("Detected message type: {detected}\n");
+    for (rank, candidate) in inspector.detected_candidates().iter().enumerate() {
+        ranking.push_str(&format!("  {}. {}\n", rank + 1, candidate.summary()));
+    }
+
+    Some(ranking)
 }
 
-fn inspect(options: InspectOptions) -> std::result::Result<Vec<Inspector>, Report<Inspect>> {
+/// Loads the messages to inspect, along with how they were framed when there are several.
+fn inspect(
+    options: InspectOptions,
+) -> std::result::Result<(Vec<Inspector>, Option<StreamLayout>), Report<Inspect>> {
     if options.multiple {
-        return inspect_multiple(options);
+        let (inspectors, layout) = inspect_multiple(options)?;
+        return Ok((inspectors, Some(layout)));
     }
 
-    Ok(vec![inspect_one(options)?])
+    Ok((vec![inspect_one(options)?.0], None))
 }
 
-fn inspect_one(options: InspectOptions) -> std::result::Result<Inspector, Report<Inspect>> {
+/// Loads the single payload, along with any gRPC-Web trailer frames after it.
+fn inspect_one(
+    options: InspectOptions,
+) -> std::result::Result<(Inspector, Vec<u8>), Report<Inspect>> {
     if options.multiple {
         return Err(Report::new(Inspect).attach(
             "expected a single payload; multiple payloads require the multi-message inspector path",
@@ -60,43 +126,39 @@ fn inspect_one(options: InspectOptions) -> std::result::Result<Inspector, Report
     }
 
     let input = decode::read_input(options.file.as_deref())?;
-    load_inspector(
-        options.schema.as_ref(),
+    let single = framing::unframe_single(&input, options.framing, options.input_format)?;
+    let inspector = load_inspector(
+        &options.schema,
         options.message.as_deref(),
-        &input,
-        options.input_format,
-    )
+        &options.embedded,
+        &single.payload,
+        single.input_format,
+    )?;
+
+    Ok((inspector, single.trailer))
 }
 
 pub(crate) fn inspect_multiple(
     options: InspectOptions,
-) -> std::result::Result<Vec<Inspector>, Report<Inspect>> {
-    decode::validate_multiple_input_format(options.input_format)?;
+) -> std::result::Result<(Vec<Inspector>, StreamLayout), Report<Inspect>> {
     let input = decode::read_input(options.file.as_deref())?;
-    let text = std::str::from_utf8(&input)
-        .attach("Input format: line-based text")
-        .change_context(Inspect)?;
-    let mut inspectors = Vec::new();
-
-    for line in text.lines() {
-        let line = line.trim_end_matches('\r');
-        if line.is_empty() {
-            return Err(Report::new(Inspect)
-                .attach("`inspect --multiple` does not support empty lines in the input file"));
-        }
-
-        inspectors.push(load_inspector(
-            options.schema.as_ref(),
-            options.message.as_deref(),
-            line.as_bytes(),
-            options.input_format,
-        )?);
-    }
+    let multiple = framing::split_input(&input, options.framing, options.input_format, "inspect")?;
+    let selection = load_message_selection(
+        &options.schema,
+        options.message.as_deref(),
+        &options.embedded,
+    )?;
 
-    if inspectors.is_empty() {
+    if multiple.payloads.is_empty() {
         return Err(Report::new(Inspect)
-            .attach("`inspect --multiple` did not find any payload lines in the input file"));
+            .attach("`inspect --multiple` did not find any payloads in the input file"));
     }
 
-    Ok(inspectors)
+    let inspectors = multiple
+        .payloads
+        .iter()
+        .map(|payload| selection.parse(payload, multiple.payload_format))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok((inspectors, multiple.layout(options.framing)))
 }
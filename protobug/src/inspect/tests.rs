@@ -9,17 +9,24 @@ use protobuf::{
     EnumOrUnknown, Message as _, MessageField, SpecialFields,
     well_known_types::timestamp::Timestamp,
 };
-use protogen::system_event::{
-    SystemEvent,
-    system_event::{Event as SystemEventVariant, MouseButton, MouseDown},
+use protogen::{
+    system_event::{
+        SystemEvent,
+        system_event::{Event as SystemEventVariant, MouseButton, MouseDown},
+    },
+    trace_bundle::{
+        TraceBundle, TraceReplayRequest,
+        trace_bundle::{Header, HttpRequest, Service},
+        trace_replay_request::Source as ReplaySource,
+    },
 };
 use tempfile::tempdir;
 
 use super::*;
 use crate::{
-    decode,
+    decode, json,
     message::EnumSelection,
-    schema::{available_message_names, load_file_descriptor, select_message},
+    schema::{EmbeddedMessage, available_message_names, load_schema, select_message},
     selection,
 };
 
@@ -30,6 +37,13 @@ fn schema_path() -> Utf8PathBuf {
     ))
 }
 
+fn schema() -> SchemaSource {
+    SchemaSource::Proto {
+        paths: vec![schema_path()],
+        includes: Vec::new(),
+    }
+}
+
 fn sample_bytes() -> Vec<u8> {
     SystemEvent {
         timestamp: MessageField::some(Timestamp {
@@ -52,8 +66,9 @@ fn sample_bytes() -> Vec<u8> {
 
 fn sample_json() -> String {
     load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -63,71 +78,1092 @@ fn sample_json() -> String {
 }
 
 #[test]
-fn auto_detects_base64_and_hex_input() {
-    let bytes = sample_bytes();
-    let base64 = BASE64_STANDARD.encode(&bytes);
-    let hex = hex::encode(&bytes);
+fn auto_detects_base64_and_hex_input() {
+    let bytes = sample_bytes();
+    let base64 = BASE64_STANDARD.encode(&bytes);
+    let hex = hex::encode(&bytes);
+
+    assert_eq!(
+        decode::decode_input(base64.as_bytes(), InputFormat::Auto).unwrap(),
+        bytes,
+    );
+    assert_eq!(
+        decode::decode_input(hex.as_bytes(), InputFormat::Auto).unwrap(),
+        bytes
+    );
+}
+
+#[test]
+fn read_input_requires_explicit_stdin_marker() {
+    let error = decode::read_input(None).unwrap_err();
+    let message = format!("{error:?}");
+
+    assert!(message.contains("No input file was provided."));
+    assert!(message.contains("Pass --file <path> or --file - to read from stdin."));
+}
+
+#[test]
+fn explicit_hex_format_ignores_whitespace() {
+    let bytes = sample_bytes();
+    let spaced_hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    assert_eq!(
+        decode::decode_input(spaced_hex.as_bytes(), InputFormat::Hex).unwrap(),
+        bytes,
+    );
+}
+
+#[test]
+fn selecting_message_is_required_when_schema_has_multiple_messages() {
+    let dir = tempdir().unwrap();
+    let schema_path = Utf8PathBuf::from_path_buf(dir.path().join("multi.proto")).unwrap();
+    fs::write(
+        &schema_path,
+        indoc! {r#"
+            syntax = "proto3";
+
+            message Alpha {}
+            message Beta {}
+        "#},
+    )
+    .unwrap();
+
+    let schema = load_schema(&SchemaSource::Proto {
+        paths: vec![schema_path],
+        includes: Vec::new(),
+    })
+    .unwrap();
+    assert_eq!(available_message_names(&schema), vec!["Alpha", "Beta"]);
+    let error = format!("{:?}", select_message(&schema, None).unwrap_err());
+    assert!(error.contains(
+        "Schema has multiple top-level messages; pass `--message <name>` or `--message auto`"
+    ));
+    assert!(error.contains("Available messages: Alpha, Beta"));
+    assert_eq!(
+        select_message(&schema, Some("Beta")).unwrap().name(),
+        "Beta"
+    );
+}
+
+fn trace_bundle_schema() -> SchemaSource {
+    SchemaSource::Proto {
+        paths: vec![Utf8PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../protogen/proto/trace-bundle.proto"
+        ))],
+        includes: Vec::new(),
+    }
+}
+
+fn sample_file(name: &str) -> Vec<u8> {
+    fs::read(format!(
+        "{}/../protogen/samples/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap()
+}
+
+#[test]
+fn auto_message_detects_payload_type() {
+    let bundle = load_inspector(
+        &trace_bundle_schema(),
+        Some("auto"),
+        &[],
+        &sample_file("trace-bundle-http.bin"),
+        InputFormat::Binary,
+    )
+    .unwrap();
+    let replay = load_inspector(
+        &trace_bundle_schema(),
+        Some("auto"),
+        &[],
+        &sample_file("trace-replay-request.hex"),
+        InputFormat::Hex,
+    )
+    .unwrap();
+
+    assert!(
+        bundle
+            .canonical_json()
+            .unwrap()
+            .contains(r#""exportId": "exp-http-20260326""#)
+    );
+    assert!(replay.canonical_json().unwrap().contains(r#""checksum""#));
+}
+
+#[test]
+fn auto_message_detects_type_of_each_line() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.hex")).unwrap();
+    fs::write(
+        &input_path,
+        format!(
+            "{}\n{}\n",
+            hex::encode(sample_file("trace-replay-request.bin")),
+            hex::encode(sample_file("trace-bundle-queue.bin")),
+        ),
+    )
+    .unwrap();
+
+    let (inspectors, _) = inspect_multiple(InspectOptions {
+        schema: trace_bundle_schema(),
+        message: Some("auto".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Hex,
+        multiple: true,
+        framing: Framing::Lines,
+        display_options: DisplayOptions::default(),
+        save_targets: SaveTargets::default(),
+    })
+    .unwrap();
+
+    assert_eq!(inspectors.len(), 2);
+    assert!(
+        inspectors[0]
+            .canonical_json()
+            .unwrap()
+            .contains(r#""checksum""#)
+    );
+    assert!(
+        inspectors[1]
+            .canonical_json()
+            .unwrap()
+            .contains(r#""exportId""#)
+    );
+}
+
+#[test]
+fn auto_message_ranks_the_candidates_that_parse() {
+    let replay = load_inspector(
+        &trace_bundle_schema(),
+        Some("auto"),
+        &[],
+        &sample_file("trace-replay-request.hex"),
+        InputFormat::Hex,
+    )
+    .unwrap();
+    let named = load_inspector(
+        &trace_bundle_schema(),
+        Some("TraceReplayRequest"),
+        &[],
+        &sample_file("trace-replay-request.hex"),
+        InputFormat::Hex,
+    )
+    .unwrap();
+
+    assert_eq!(replay.detected_type(), Some("TraceReplayRequest"));
+    assert_snapshot!(detection_ranking(&replay).unwrap());
+    assert_eq!(named.detected_type(), None);
+    assert_eq!(detection_ranking(&named), None);
+}
+
+#[test]
+fn auto_message_lists_candidates_when_nothing_parses() {
+    let error = load_inspector(
+        &trace_bundle_schema(),
+        Some("auto"),
+        &[],
+        &[0xff, 0xff],
+        InputFormat::Binary,
+    )
+    .err()
+    .unwrap();
+    let rendered = format!("{error:?}");
+
+    assert!(rendered.contains("Payload does not parse as any message in the schema"));
+    assert!(rendered.contains("Candidate: TraceReplayRequest (does not parse)"));
+}
+
+#[test]
+fn auto_message_rejects_json_input() {
+    let error = load_inspector(
+        &trace_bundle_schema(),
+        Some("auto"),
+        &[],
+        b"{}",
+        InputFormat::Json,
+    )
+    .err()
+    .unwrap();
+
+    assert!(format!("{error:?}").contains("`--message auto` requires a binary, hex, or base64"));
+}
+
+fn http_envelope_bundle(method: &str) -> TraceBundle {
+    TraceBundle {
+        export_id: "exp-embedded".to_owned(),
+        raw_envelope: HttpRequest {
+            method: method.to_owned(),
+            path: "/v1/traces".to_owned(),
+            ..Default::default()
+        }
+        .write_to_bytes()
+        .unwrap(),
+        ..Default::default()
+    }
+}
+
+fn embed(field_path: &str, message: &str) -> EmbeddedMessage {
+    format!("{field_path}={message}").parse().unwrap()
+}
+
+#[test]
+fn embedded_message_decodes_bytes_field_and_reencodes_edits() {
+    let bytes = http_envelope_bundle("POST").write_to_bytes().unwrap();
+    let opaque = load_inspector(
+        &trace_bundle_schema(),
+        Some("TraceBundle"),
+        &[],
+        &bytes,
+        InputFormat::Binary,
+    )
+    .unwrap();
+    let mut inspector = load_inspector(
+        &trace_bundle_schema(),
+        Some("TraceBundle"),
+        &[embed("rawEnvelope", "TraceBundle.HttpRequest")],
+        &bytes,
+        InputFormat::Binary,
+    )
+    .unwrap();
+
+    assert!(
+        opaque
+            .protobuf_lines(false)
+            .iter()
+            .any(|line| line.text == r#"raw_envelope: "\n\x04POST\x12\n/v1/traces""#)
+    );
+    assert!(
+        inspector
+            .protobuf_lines(false)
+            .iter()
+            .any(|line| line.text == r#"  method: "POST""#)
+    );
+    assert_eq!(inspector.bytes().unwrap(), bytes);
+
+    let edited = inspector
+        .canonical_json()
+        .unwrap()
+        .replace(r#""method": "POST""#, r#""method": "DELETE""#);
+    inspector.apply_json(&edited).unwrap();
+
+    assert_eq!(
+        inspector.bytes().unwrap(),
+        http_envelope_bundle("DELETE").write_to_bytes().unwrap()
+    );
+}
+
+#[test]
+fn embedded_message_paths_go_through_message_fields() {
+    let request = TraceReplayRequest {
+        source: Some(ReplaySource::TraceBundle(http_envelope_bundle("GET"))),
+        checksum: Header {
+            name: "sha256".to_owned(),
+            value: "9f7d".to_owned(),
+            ..Default::default()
+        }
+        .write_to_bytes()
+        .unwrap(),
+        ..Default::default()
+    };
+    let inspector = load_inspector(
+        &trace_bundle_schema(),
+        Some("TraceReplayRequest"),
+        &[
+            embed("trace_bundle.raw_envelope", "TraceBundle.HttpRequest"),
+            embed("checksum", "TraceBundle.Header"),
+        ],
+        &request.write_to_bytes().unwrap(),
+        InputFormat::Binary,
+    )
+    .unwrap();
+    let json = inspector.canonical_json().unwrap();
+
+    assert!(json.contains(r#""method": "GET""#));
+    assert!(json.contains(r#""name": "sha256""#));
+    assert_eq!(
+        TraceReplayRequest::parse_from_bytes(&inspector.bytes().unwrap()).unwrap(),
+        request
+    );
+}
+
+#[test]
+fn embedded_message_keeps_bytes_that_do_not_decode() {
+    let opaque = TraceBundle {
+        export_id: "exp-opaque".to_owned(),
+        raw_envelope: vec![0xff, 0xff],
+        ..Default::default()
+    };
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.hex")).unwrap();
+    fs::write(
+        &input_path,
+        format!(
+            "{}\n{}\n",
+            hex::encode(http_envelope_bundle("POST").write_to_bytes().unwrap()),
+            hex::encode(opaque.write_to_bytes().unwrap()),
+        ),
+    )
+    .unwrap();
+
+    let (inspectors, _) = inspect_multiple(InspectOptions {
+        schema: trace_bundle_schema(),
+        message: Some("TraceBundle".to_owned()),
+        embedded: vec![embed("raw_envelope", "TraceBundle.HttpRequest")],
+        file: Some(input_path),
+        input_format: InputFormat::Hex,
+        multiple: true,
+        framing: Framing::Lines,
+        display_options: DisplayOptions::default(),
+        save_targets: SaveTargets::default(),
+    })
+    .unwrap();
+
+    assert_eq!(inspectors[0].embed_note(), None);
+    assert!(
+        inspectors[0]
+            .canonical_json()
+            .unwrap()
+            .contains(r#""method": "POST""#)
+    );
+    assert_eq!(
+        inspectors[1].embed_note(),
+        Some(
+            "Kept the `--embed raw_envelope=TraceBundle.HttpRequest` fields as bytes because \
+             they do not decode as their messages"
+        )
+    );
+    assert!(
+        inspectors[1]
+            .canonical_json()
+            .unwrap()
+            .contains(r#""rawEnvelope": "//8=""#)
+    );
+    assert_eq!(
+        inspectors[1].bytes().unwrap(),
+        opaque.write_to_bytes().unwrap()
+    );
+}
+
+#[test]
+fn embedded_message_rejects_fields_that_are_not_bytes() {
+    let rendered = |embedded: EmbeddedMessage, message: &str| {
+        let error = load_message_selection(&trace_bundle_schema(), Some(message), &[embedded])
+            .err()
+            .unwrap();
+        format!("{error:?}")
+    };
+
+    assert!(
+        rendered(embed("export_id", "TraceBundle.Header"), "TraceBundle")
+            .contains("Only bytes fields can hold an embedded message")
+    );
+    assert!(
+        rendered(embed("services.nope", "TraceBundle.Header"), "TraceBundle")
+            .contains("Message TraceBundle.Service has no field named nope")
+    );
+    assert!(
+        rendered(embed("export_id.raw", "TraceBundle.Header"), "TraceBundle")
+            .contains("TraceBundle.export_id is not a message field")
+    );
+    assert!(
+        rendered(embed("raw_envelope", "TraceBundle.Header"), "auto")
+            .contains("`--embed` requires `--message`")
+    );
+    assert!("raw_envelope".parse::<EmbeddedMessage>().is_err());
+}
+
+/// An envelope holding `Any` payloads, loaded alongside the trace bundle schema that defines the
+/// packed types.
+fn any_schema(root: &camino::Utf8Path) -> SchemaSource {
+    let schema_path = root.join("envelope.proto");
+    fs::write(
+        &schema_path,
+        indoc! {r#"
+            syntax = "proto3";
+
+            package acme;
+
+            import "google/protobuf/any.proto";
+
+            message Envelope {
+                string id = 1;
+                google.protobuf.Any payload = 2;
+                repeated google.protobuf.Any extras = 3;
+            }
+        "#},
+    )
+    .unwrap();
+
+    SchemaSource::Proto {
+        paths: vec![
+            schema_path,
+            Utf8PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../protogen/proto/trace-bundle.proto"
+            )),
+        ],
+        includes: Vec::new(),
+    }
+}
+
+fn http_request_bytes(method: &str) -> Vec<u8> {
+    HttpRequest {
+        method: method.to_owned(),
+        path: "/v1/traces".to_owned(),
+        ..Default::default()
+    }
+    .write_to_bytes()
+    .unwrap()
+}
+
+/// An envelope whose payload packs an `HttpRequest`, with an extra of a type the schema lacks.
+fn envelope_json(method: &str) -> String {
+    serde_json::json!({
+        "id": "env-1",
+        "payload": {
+            "typeUrl": "type.googleapis.com/TraceBundle.HttpRequest",
+            "value": BASE64_STANDARD.encode(http_request_bytes(method)),
+        },
+        "extras": [{"typeUrl": "type.googleapis.com/acme.Missing", "value": "AAE="}],
+    })
+    .to_string()
+}
+
+fn well_known_schema(root: &camino::Utf8Path) -> SchemaSource {
+    let schema_path = root.join("job.proto");
+    fs::write(
+        &schema_path,
+        indoc! {r#"
+            syntax = "proto3";
+
+            package acme;
+
+            import "google/protobuf/duration.proto";
+            import "google/protobuf/struct.proto";
+            import "google/protobuf/timestamp.proto";
+            import "google/protobuf/wrappers.proto";
+
+            message Job {
+                google.protobuf.Timestamp started_at = 1;
+                google.protobuf.Duration elapsed = 2;
+                google.protobuf.StringValue owner = 3;
+                google.protobuf.UInt32Value retries = 4;
+                google.protobuf.Struct labels = 5;
+                repeated google.protobuf.Value results = 6;
+                map<string, google.protobuf.Duration> stages = 7;
+            }
+        "#},
+    )
+    .unwrap();
+
+    SchemaSource::Proto {
+        paths: vec![schema_path],
+        includes: Vec::new(),
+    }
+}
+
+/// A job whose well-known types are written out field by field, as the dynamic schema parses them.
+fn job_json() -> String {
+    serde_json::json!({
+        "startedAt": {"seconds": "1711465600", "nanos": 987654321},
+        "elapsed": {"seconds": "1", "nanos": 500000000},
+        "owner": {"value": "ops"},
+        "retries": {"value": 3},
+        "labels": {"fields": {
+            "env": {"stringValue": "prod"},
+            "canary": {"boolValue": true},
+        }},
+        "results": [
+            {"numberValue": 0.5},
+            {"nullValue": "NULL_VALUE"},
+            {"listValue": {"values": [{"stringValue": "a"}, {"numberValue": 2}]}},
+        ],
+        "stages": {"build": {"seconds": "-2", "nanos": -250000000}},
+    })
+    .to_string()
+}
+
+#[test]
+fn well_known_types_print_on_one_line_in_the_human_format() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let input_path = root.join("job.json");
+    fs::write(&input_path, job_json()).unwrap();
+
+    let human = inspect_to_human(InspectOptions {
+        schema: well_known_schema(&root),
+        message: Some("acme.Job".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Json,
+        multiple: false,
+        framing: Framing::Lines,
+        display_options: DisplayOptions::default(),
+        save_targets: SaveTargets::default(),
+    })
+    .unwrap();
+
+    assert_eq!(
+        human,
+        indoc! {r#"
+            started_at: 2024-03-26T15:06:40.987654321Z
+            elapsed: 1.5s
+            owner: "ops"
+            retries: 3
+            labels: {"canary":true,"env":"prod"}
+            results: 0.5
+            results: null
+            results: ["a",2.0]
+            stages {
+              key: "build"
+              value: -2.25s
+            }
+        "#}
+    );
+}
+
+#[test]
+fn well_known_lines_keep_their_field_paths_and_show_local_time_on_request() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let inspector = load_inspector(
+        &well_known_schema(&root),
+        Some("acme.Job"),
+        &[],
+        job_json().as_bytes(),
+        InputFormat::Json,
+    )
+    .unwrap();
+
+    let lines = inspector.protobuf_lines(true);
+    assert_eq!(
+        lines[0].path,
+        vec![selection::FieldPathSegment::Field("started_at".to_owned())]
+    );
+    assert_eq!(
+        lines[6].path,
+        vec![
+            selection::FieldPathSegment::Field("results".to_owned()),
+            selection::FieldPathSegment::Index(1),
+        ]
+    );
+
+    // Whatever the local offset, the timestamp is the same instant.
+    let local = lines[0].text.strip_prefix("started_at: ").unwrap();
+    assert_eq!(
+        local.parse::<jiff::Timestamp>().unwrap(),
+        jiff::Timestamp::new(1_711_465_600, 987_654_321).unwrap()
+    );
+}
+
+#[test]
+fn any_values_expand_in_json_and_pack_again_when_edited() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let schema = any_schema(&root);
+    let mut inspector = load_inspector(
+        &schema,
+        Some("acme.Envelope"),
+        &[],
+        envelope_json("GET").as_bytes(),
+        InputFormat::Json,
+    )
+    .unwrap();
+    let json = inspector.canonical_json().unwrap();
+    let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+    assert_eq!(
+        value["payload"],
+        serde_json::json!({
+            "@type": "type.googleapis.com/TraceBundle.HttpRequest",
+            "body": "",
+            "headers": [],
+            "method": "GET",
+            "path": "/v1/traces",
+        })
+    );
+    assert_eq!(
+        value["extras"][0]["typeUrl"],
+        "type.googleapis.com/acme.Missing"
+    );
+
+    let original = inspector.bytes().unwrap();
+    let reloaded = load_inspector(
+        &schema,
+        Some("acme.Envelope"),
+        &[],
+        json.as_bytes(),
+        InputFormat::Json,
+    )
+    .unwrap();
+    assert_eq!(reloaded.bytes().unwrap(), original);
+
+    inspector
+        .apply_json(&json.replace(r#""method": "GET""#, r#""method": "POST""#))
+        .unwrap();
+    let expected = load_inspector(
+        &schema,
+        Some("acme.Envelope"),
+        &[],
+        envelope_json("POST").as_bytes(),
+        InputFormat::Json,
+    )
+    .unwrap();
+    assert_eq!(inspector.bytes().unwrap(), expected.bytes().unwrap());
+
+    let error = inspector
+        .apply_json(&json.replace("TraceBundle.HttpRequest", "acme.Missing"))
+        .unwrap_err();
+    assert!(
+        error.contains("No loaded message type matches `@type` type.googleapis.com/acme.Missing")
+    );
+}
+
+#[test]
+fn any_values_are_shown_selected_and_highlighted_as_their_packed_message() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let schema = any_schema(&root);
+    let inspector = load_inspector(
+        &schema,
+        Some("acme.Envelope"),
+        &[],
+        envelope_json("GET").as_bytes(),
+        InputFormat::Json,
+    )
+    .unwrap();
+    let json = inspector.canonical_json().unwrap();
+    let bytes = inspector.bytes().unwrap();
+
+    let lines = inspector
+        .protobuf_lines(false)
+        .into_iter()
+        .map(|line| line.text)
+        .collect::<Vec<_>>();
+    assert!(lines.contains(&"  [type.googleapis.com/TraceBundle.HttpRequest] {".to_owned()));
+    assert!(lines.contains(&r#"    method: "GET""#.to_owned()));
+    assert!(lines.contains(&r#"  type_url: "type.googleapis.com/acme.Missing""#.to_owned()));
+
+    let (row, column) = json
+        .lines()
+        .enumerate()
+        .find_map(|(row, line)| line.find(r#""method""#).map(|column| (row, column)))
+        .unwrap();
+    let path = inspector
+        .selected_path_for_json_cursor(&json, (row, column))
+        .unwrap();
+    assert_eq!(selection::display_path(&path), "payload.value.method");
+    assert_eq!(
+        inspector.json_cursor_for_path(&json, &path),
+        Some((row, column))
+    );
+
+    let method = [&[0x0a, 0x03][..], b"GET"].concat();
+    let method_start = bytes
+        .windows(method.len())
+        .position(|window| window == method)
+        .unwrap();
+    assert_eq!(
+        inspector.highlighted_byte_indices(&path).unwrap(),
+        (method_start..method_start + method.len()).collect()
+    );
+    assert_eq!(
+        inspector.path_for_byte(method_start + 2).unwrap(),
+        Some(path)
+    );
+}
+
+#[test]
+fn map_entries_are_selected_highlighted_and_shown_by_key() {
+    let inspector = load_inspector(
+        &trace_bundle_schema(),
+        Some("TraceBundle"),
+        &[],
+        &sample_file("trace-bundle-http.bin"),
+        InputFormat::Binary,
+    )
+    .unwrap();
+    let json = inspector.canonical_json().unwrap();
+    let bytes = inspector.bytes().unwrap();
+
+    let (row, column) = json
+        .lines()
+        .enumerate()
+        .find_map(|(row, line)| line.find(r#""cluster""#).map(|column| (row, column)))
+        .unwrap();
+    let path = inspector
+        .selected_path_for_json_cursor(&json, (row, column))
+        .unwrap();
+    assert_eq!(selection::display_path(&path), r#"labels["cluster"]"#);
+    assert_eq!(
+        inspector.json_cursor_for_path(&json, &path),
+        Some((row, column))
+    );
+
+    // Only the selected entry is highlighted, from its tag to the end of its value.
+    let entry = [&[0x0a, 0x07][..], b"cluster", &[0x12, 0x09], b"prod-eu-1"].concat();
+    let entry_start = bytes
+        .windows(entry.len())
+        .position(|window| window == entry)
+        .unwrap();
+    assert_eq!(
+        inspector.highlighted_byte_indices(&path).unwrap(),
+        (entry_start - 2..entry_start + entry.len()).collect()
+    );
+    assert_eq!(
+        inspector.path_for_byte(entry_start + 3).unwrap(),
+        Some(path.clone())
+    );
+
+    let lines = inspector
+        .protobuf_lines(false)
+        .into_iter()
+        .filter(|line| line.path == path)
+        .map(|line| line.text)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "labels {",
+            r#"  key: "cluster""#,
+            r#"  value: "prod-eu-1""#,
+            "}"
+        ]
+    );
+}
+
+fn rollout_schema(root: &camino::Utf8Path) -> SchemaSource {
+    let schema_path = root.join("rollout.proto");
+    fs::write(
+        &schema_path,
+        indoc! {r#"
+            syntax = "proto3";
+
+            package acme;
+
+            enum Stage {
+                STAGE_UNSPECIFIED = 0;
+                CANARY = 1;
+                GENERAL = 2;
+            }
+
+            message Check {
+                Stage stage = 1;
+            }
+
+            message Rollout {
+                map<string, Stage> stages = 1;
+                map<int32, Check> checks = 2;
+            }
+        "#},
+    )
+    .unwrap();
+
+    SchemaSource::Proto {
+        paths: vec![schema_path],
+        includes: Vec::new(),
+    }
+}
+
+#[test]
+fn enums_in_map_values_are_listed_and_cycled_by_key() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let mut inspector = load_inspector(
+        &rollout_schema(&root),
+        Some("acme.Rollout"),
+        &[],
+        br#"{"stages": {"eu": "CANARY", "us": "GENERAL"}, "checks": {"7": {"stage": "GENERAL"}, "8": {}}}"#,
+        InputFormat::Json,
+    )
+    .unwrap();
 
+    let eu = [
+        selection::FieldPathSegment::Field("stages".to_owned()),
+        selection::FieldPathSegment::Key(r#""eu""#.to_owned()),
+    ];
     assert_eq!(
-        decode::decode_input(base64.as_bytes(), InputFormat::Auto).unwrap(),
-        bytes,
+        inspector.enum_selection(&eu),
+        Some(EnumSelection {
+            variants: vec![
+                "STAGE_UNSPECIFIED".to_owned(),
+                "CANARY".to_owned(),
+                "GENERAL".to_owned(),
+            ],
+            current: 1,
+        }),
     );
     assert_eq!(
-        decode::decode_input(hex.as_bytes(), InputFormat::Auto).unwrap(),
-        bytes
+        inspector.cycle_enum_variant(&eu, 1),
+        Some("GENERAL".to_owned())
+    );
+
+    let json = inspector.canonical_json().unwrap();
+    let (row, column) = json
+        .lines()
+        .enumerate()
+        .find_map(|(row, line)| line.find(r#""stage""#).map(|column| (row, column)))
+        .unwrap();
+    let stage = inspector
+        .selected_path_for_json_cursor(&json, (row, column))
+        .unwrap();
+    assert_eq!(selection::display_path(&stage), "checks[7].stage");
+    assert_eq!(
+        inspector.cycle_enum_variant(&stage, 1),
+        Some("STAGE_UNSPECIFIED".to_owned())
+    );
+    assert_eq!(
+        inspector
+            .omitted_default_enum_hint(&[
+                selection::FieldPathSegment::Field("checks".to_owned()),
+                selection::FieldPathSegment::Key("8".to_owned()),
+                selection::FieldPathSegment::Field("stage".to_owned()),
+            ])
+            .as_deref(),
+        Some("Default enum STAGE_UNSPECIFIED is omitted on the wire"),
+    );
+
+    let json = inspector.canonical_json().unwrap();
+    let rollout = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    assert_eq!(
+        rollout,
+        serde_json::json!({
+            "stages": {"eu": "GENERAL", "us": "GENERAL"},
+            "checks": {
+                "7": {"stage": "STAGE_UNSPECIFIED"},
+                "8": {"stage": "STAGE_UNSPECIFIED"},
+            },
+        })
     );
 }
 
-#[test]
-fn read_input_requires_explicit_stdin_marker() {
-    let error = decode::read_input(None).unwrap_err();
-    let message = format!("{error:?}");
+fn write_schema_tree(root: &camino::Utf8Path) -> Utf8PathBuf {
+    fs::create_dir_all(root.join("proto/common/v1")).unwrap();
+    fs::create_dir_all(root.join("proto/acme/v1")).unwrap();
+    fs::write(
+        root.join("proto/common/v1/types.proto"),
+        indoc! {r#"
+            syntax = "proto3";
 
-    assert!(message.contains("No input file was provided."));
-    assert!(message.contains("Pass --file <path> or --file - to read from stdin."));
+            package common.v1;
+
+            message Money {
+                string currency = 1;
+                int64 units = 2;
+            }
+        "#},
+    )
+    .unwrap();
+
+    let schema_path = root.join("proto/acme/v1/invoice.proto");
+    fs::write(
+        &schema_path,
+        indoc! {r#"
+            syntax = "proto3";
+
+            package acme.v1;
+
+            import "common/v1/types.proto";
+
+            message Invoice {
+                common.v1.Money total = 1;
+            }
+        "#},
+    )
+    .unwrap();
+
+    schema_path
 }
 
 #[test]
-fn explicit_hex_format_ignores_whitespace() {
-    let bytes = sample_bytes();
-    let spaced_hex = bytes
-        .iter()
-        .map(|byte| format!("{byte:02x}"))
-        .collect::<Vec<_>>()
-        .join(" ");
+fn include_paths_resolve_imports_from_sibling_trees() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let schema_path = write_schema_tree(&root);
 
+    let schema = load_schema(&SchemaSource::Proto {
+        paths: vec![schema_path],
+        includes: vec![root.join("proto")],
+    })
+    .unwrap();
+
+    assert_eq!(available_message_names(&schema), vec!["acme.v1.Invoice"]);
     assert_eq!(
-        decode::decode_input(spaced_hex.as_bytes(), InputFormat::Hex).unwrap(),
-        bytes,
+        select_message(&schema, Some("Invoice"))
+            .unwrap()
+            .full_name(),
+        "acme.v1.Invoice"
     );
 }
 
 #[test]
-fn selecting_message_is_required_when_schema_has_multiple_messages() {
+fn missing_import_reports_import_and_search_paths() {
     let dir = tempdir().unwrap();
-    let schema_path = Utf8PathBuf::from_path_buf(dir.path().join("multi.proto")).unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let schema_path = write_schema_tree(&root);
+
+    let error = load_schema(&SchemaSource::Proto {
+        paths: vec![schema_path],
+        includes: Vec::new(),
+    })
+    .unwrap_err();
+    let message = format!("{error:?}");
+
+    assert!(message.contains("common/v1/types.proto"));
+    assert!(message.contains(&format!(
+        "Import search paths: {}",
+        root.join("proto/acme/v1")
+    )));
+}
+
+#[test]
+fn schema_directory_loads_every_file_and_selects_by_full_name() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    write_schema_tree(&root);
+    fs::create_dir_all(root.join("proto/acme/legacy")).unwrap();
     fs::write(
-        &schema_path,
+        root.join("proto/acme/legacy/invoice.proto"),
         indoc! {r#"
             syntax = "proto3";
 
-            message Alpha {}
-            message Beta {}
+            package acme.legacy;
+
+            message Invoice {
+                string total = 1;
+            }
         "#},
     )
     .unwrap();
 
-    let fd = load_file_descriptor(schema_path.as_ref()).unwrap();
-    assert_eq!(available_message_names(&fd), vec!["Alpha", "Beta"]);
-    assert!(select_message(&fd, None).is_err());
-    assert_eq!(select_message(&fd, Some("Beta")).unwrap().name(), "Beta");
+    let schema = load_schema(&SchemaSource::Proto {
+        paths: vec![root.join("proto")],
+        includes: Vec::new(),
+    })
+    .unwrap();
+
+    assert_eq!(
+        available_message_names(&schema),
+        vec!["acme.legacy.Invoice", "acme.v1.Invoice", "common.v1.Money"],
+    );
+    assert_eq!(
+        select_message(&schema, Some("acme.legacy.Invoice"))
+            .unwrap()
+            .full_name(),
+        "acme.legacy.Invoice",
+    );
+    assert_eq!(
+        select_message(&schema, Some(".common.v1.Money"))
+            .unwrap()
+            .full_name(),
+        "common.v1.Money",
+    );
+
+    let error = select_message(&schema, Some("Invoice")).unwrap_err();
+    let message = format!("{error:?}");
+    assert!(message.contains("Matching messages: acme.legacy.Invoice, acme.v1.Invoice"));
+}
+
+#[test]
+fn multiple_schema_files_resolve_imported_messages_by_full_name() {
+    let dir = tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    let invoice_path = write_schema_tree(&root);
+
+    let schema = load_schema(&SchemaSource::Proto {
+        paths: vec![invoice_path, schema().to_string().into()],
+        includes: vec![root.join("proto")],
+    })
+    .unwrap();
+
+    assert_eq!(
+        available_message_names(&schema),
+        vec![
+            "SystemEvent",
+            "SystemEvent.MouseDown",
+            "SystemEvent.MouseMove",
+            "acme.v1.Invoice",
+        ],
+    );
+    assert_eq!(
+        select_message(&schema, Some("common.v1.Money"))
+            .unwrap()
+            .full_name(),
+        "common.v1.Money",
+    );
+    assert_eq!(
+        select_message(&schema, Some("SystemEvent.MouseDown"))
+            .unwrap()
+            .full_name(),
+        "SystemEvent.MouseDown",
+    );
+}
+
+fn write_descriptor_set(path: &camino::Utf8Path, include_imports: bool) {
+    let schema_path = schema_path();
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .includes(schema_path.parent())
+        .input(&schema_path)
+        .parse_and_typecheck()
+        .unwrap();
+    let file = parsed
+        .file_descriptors
+        .into_iter()
+        .filter(|fd| include_imports || fd.name() == "system-event.proto")
+        .collect();
+    let set = protobuf::descriptor::FileDescriptorSet {
+        file,
+        ..Default::default()
+    };
+
+    fs::write(path, set.write_to_bytes().unwrap()).unwrap();
+}
+
+#[test]
+fn descriptor_set_loads_message_without_proto_sources() {
+    let dir = tempdir().unwrap();
+    let set_path = Utf8PathBuf::from_path_buf(dir.path().join("descriptor.pb")).unwrap();
+    write_descriptor_set(&set_path, true);
+
+    let inspector = load_inspector(
+        &SchemaSource::DescriptorSet(set_path),
+        Some("SystemEvent"),
+        &[],
+        &sample_bytes(),
+        InputFormat::Binary,
+    )
+    .unwrap();
+
+    assert_eq!(inspector.bytes().unwrap(), sample_bytes());
+    assert_eq!(inspector.canonical_json().unwrap(), sample_json());
+}
+
+#[test]
+fn descriptor_set_without_imports_reports_hint() {
+    let dir = tempdir().unwrap();
+    let set_path = Utf8PathBuf::from_path_buf(dir.path().join("descriptor.pb")).unwrap();
+    write_descriptor_set(&set_path, false);
+
+    let error = load_schema(&SchemaSource::DescriptorSet(set_path)).unwrap_err();
+    let message = format!("{error:?}");
+
+    assert!(message.contains("Could not resolve descriptor dependencies for: system-event.proto"));
+    assert!(message.contains("--include_imports"));
 }
 
 #[test]
 fn inspector_tracks_parse_errors_without_losing_last_valid_message() {
     let mut inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -151,11 +1187,59 @@ fn inspector_tracks_parse_errors_without_losing_last_valid_message() {
     assert!(inspector.canonical_json().unwrap().contains("\"updated\""));
 }
 
+#[test]
+fn inspector_keeps_edited_bytes_that_do_not_decode() {
+    let mut inspector = load_inspector(
+        &schema(),
+        Some("SystemEvent"),
+        &[],
+        &sample_bytes(),
+        InputFormat::Binary,
+    )
+    .unwrap();
+    let json = inspector.canonical_json().unwrap();
+
+    let mut truncated = sample_bytes();
+    let reason_tag = truncated.iter().position(|&byte| byte == 0x12).unwrap();
+    truncated.truncate(reason_tag + 4);
+    let error = inspector.apply_bytes(truncated.clone()).unwrap_err();
+
+    assert!(
+        error.starts_with(&format!("Bytes do not decode at offset {reason_tag}: ")),
+        "{error}"
+    );
+    assert_eq!(inspector.decode_error(), Some(error.as_str()));
+    assert_eq!(inspector.bytes().unwrap(), truncated);
+    assert_eq!(inspector.canonical_json().unwrap(), json);
+    assert!(json::encode_line_output(&inspector, InputFormat::Json).is_err());
+    assert_eq!(
+        json::encode_line_output(&inspector, InputFormat::Hex).unwrap(),
+        hex::encode(&truncated)
+    );
+
+    let mut edited = sample_bytes();
+    let x_value = edited
+        .windows(2)
+        .position(|pair| pair == [0x10, 0x2a])
+        .unwrap()
+        + 1;
+    edited[x_value] = 0x2b;
+    inspector.apply_bytes(edited.clone()).unwrap();
+
+    assert_eq!(inspector.decode_error(), None);
+    assert_eq!(inspector.bytes().unwrap(), edited);
+    assert!(inspector.canonical_json().unwrap().contains("\"x\": 43"));
+
+    inspector.apply_json(&json).unwrap();
+    assert_eq!(inspector.bytes().unwrap(), sample_bytes());
+}
+
 #[test]
 fn canonical_json_matches_snapshot() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -167,8 +1251,9 @@ fn canonical_json_matches_snapshot() {
 #[test]
 fn json_input_round_trips_sample_bytes() {
     let bytes = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         sample_json().as_bytes(),
         InputFormat::Json,
     )
@@ -187,8 +1272,9 @@ fn inspect_multiple_hex_lines_loads_each_message() {
     second["click"]["x"] = serde_json::Value::from(100);
     second["click"]["y"] = serde_json::Value::from(42);
     let second_bytes = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         serde_json::to_string_pretty(&second).unwrap().as_bytes(),
         InputFormat::Json,
     )
@@ -205,12 +1291,14 @@ fn inspect_multiple_hex_lines_loads_each_message() {
     )
     .unwrap();
 
-    let inspectors = inspect_multiple(InspectOptions {
-        schema: schema_path(),
+    let (inspectors, _) = inspect_multiple(InspectOptions {
+        schema: schema(),
         message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
         file: Some(input_path),
         input_format: InputFormat::Hex,
         multiple: true,
+        framing: Framing::Lines,
         display_options: DisplayOptions::default(),
         save_targets: SaveTargets::default(),
     })
@@ -236,8 +1324,9 @@ fn inspector_saves_all_configured_output_formats() {
     let dir = tempdir().unwrap();
     let bytes = sample_bytes();
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &bytes,
         InputFormat::Binary,
     )
@@ -248,6 +1337,7 @@ fn inspector_saves_all_configured_output_formats() {
         base64: Some(Utf8PathBuf::from_path_buf(dir.path().join("message.base64")).unwrap()),
         hex: Some(Utf8PathBuf::from_path_buf(dir.path().join("message.hex")).unwrap()),
         binary: Some(Utf8PathBuf::from_path_buf(dir.path().join("message.bin")).unwrap()),
+        ..SaveTargets::default()
     };
 
     let saved = inspector.save(&targets).unwrap();
@@ -272,8 +1362,9 @@ fn inspector_saves_all_configured_output_formats() {
 #[test]
 fn inspector_reports_omitted_default_enum_hint() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -293,8 +1384,9 @@ fn inspector_reports_omitted_default_enum_hint() {
 #[test]
 fn inspector_lists_and_cycles_enum_variants() {
     let mut inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -332,8 +1424,9 @@ fn inspector_lists_and_cycles_enum_variants() {
 #[test]
 fn inspector_skips_omitted_default_enum_hint_for_other_fields() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -347,3 +1440,215 @@ fn inspector_skips_omitted_default_enum_hint_for_other_fields() {
         None,
     );
 }
+
+#[test]
+fn applying_json_keeps_unknown_fields_across_edits() {
+    let mut event = SystemEvent::parse_from_bytes(&sample_bytes()).unwrap();
+    event.mut_unknown_fields().add_fixed32(98, 5);
+    event.mut_click().mut_unknown_fields().add_varint(40, 1);
+    let mut inspector = load_inspector(
+        &schema(),
+        Some("SystemEvent"),
+        &[],
+        &event.write_to_bytes().unwrap(),
+        InputFormat::Binary,
+    )
+    .unwrap();
+
+    for (x, reason) in [(1, "first"), (2, "second")] {
+        let mut json =
+            serde_json::from_str::<serde_json::Value>(&inspector.canonical_json().unwrap())
+                .unwrap();
+        json["click"]["x"] = serde_json::Value::from(x);
+        json["reason"] = serde_json::Value::from(reason);
+        inspector.apply_json(&json.to_string()).unwrap();
+    }
+
+    event.mut_click().x = 2;
+    event.reason = Some("second".to_owned());
+    assert_eq!(
+        SystemEvent::parse_from_bytes(&inspector.bytes().unwrap()).unwrap(),
+        event
+    );
+}
+
+#[test]
+fn applying_json_keeps_unknown_fields_with_their_repeated_element() {
+    let service = |name: &str, unknown: Option<u64>| {
+        let mut service = Service {
+            name: name.to_owned(),
+            ..Default::default()
+        };
+        if let Some(value) = unknown {
+            service.mut_unknown_fields().add_varint(90, value);
+        }
+        service
+    };
+    let bundle = TraceBundle {
+        services: vec![service("frontend", Some(1)), service("worker", None)],
+        ..Default::default()
+    };
+    let load = || {
+        load_inspector(
+            &trace_bundle_schema(),
+            Some("TraceBundle"),
+            &[],
+            &bundle.write_to_bytes().unwrap(),
+            InputFormat::Binary,
+        )
+        .unwrap()
+    };
+    let edited_services = |inspector: &Inspector, edit: fn(&mut Vec<serde_json::Value>)| {
+        let mut json =
+            serde_json::from_str::<serde_json::Value>(&inspector.canonical_json().unwrap())
+                .unwrap();
+        edit(json["services"].as_array_mut().unwrap());
+        json.to_string()
+    };
+
+    let mut deleted = load();
+    deleted
+        .apply_json(&edited_services(&deleted, |services| {
+            services.remove(0);
+        }))
+        .unwrap();
+    assert_eq!(
+        TraceBundle::parse_from_bytes(&deleted.bytes().unwrap())
+            .unwrap()
+            .services,
+        [service("worker", None)]
+    );
+
+    let mut reordered = load();
+    reordered
+        .apply_json(&edited_services(&reordered, |services| services.reverse()))
+        .unwrap();
+    assert_eq!(
+        TraceBundle::parse_from_bytes(&reordered.bytes().unwrap())
+            .unwrap()
+            .services,
+        [service("worker", None), service("frontend", Some(1))]
+    );
+
+    let mut renamed = load();
+    renamed
+        .apply_json(&edited_services(&renamed, |services| {
+            services[0]["name"] = serde_json::Value::from("checkout");
+        }))
+        .unwrap();
+    assert_eq!(
+        TraceBundle::parse_from_bytes(&renamed.bytes().unwrap())
+            .unwrap()
+            .services,
+        [service("checkout", Some(1)), service("worker", None)]
+    );
+}
+
+#[test]
+fn inspect_multiple_splits_delimited_stream() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("input.bin")).unwrap();
+    let mut second = SystemEvent::parse_from_bytes(&sample_bytes()).unwrap();
+    second.mut_click().x = 100;
+    fs::write(
+        &input_path,
+        crate::framing::join_delimited([
+            sample_bytes().as_slice(),
+            second.write_to_bytes().unwrap().as_slice(),
+        ]),
+    )
+    .unwrap();
+
+    let (inspectors, _) = inspect_multiple(InspectOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Auto,
+        multiple: true,
+        framing: Framing::Delimited,
+        display_options: DisplayOptions::default(),
+        save_targets: SaveTargets::default(),
+    })
+    .unwrap();
+
+    assert_eq!(inspectors.len(), 2);
+    assert!(
+        inspectors[1]
+            .canonical_json()
+            .unwrap()
+            .contains(r#""x": 100"#)
+    );
+}
+
+#[test]
+fn inspect_unwraps_single_grpc_frame() {
+    let dir = tempdir().unwrap();
+    let input_path = Utf8PathBuf::from_path_buf(dir.path().join("body.hex")).unwrap();
+    fs::write(
+        &input_path,
+        hex::encode(crate::framing::join_grpc([sample_bytes().as_slice()])),
+    )
+    .unwrap();
+
+    let bytes = inspect_to_bytes(InspectOptions {
+        schema: schema(),
+        message: Some("SystemEvent".to_owned()),
+        embedded: Vec::new(),
+        file: Some(input_path),
+        input_format: InputFormat::Auto,
+        multiple: false,
+        framing: Framing::Grpc,
+        display_options: DisplayOptions::default(),
+        save_targets: SaveTargets::default(),
+    })
+    .unwrap()
+    .bytes;
+
+    assert_eq!(bytes, sample_bytes());
+}
+
+#[test]
+fn text_format_input_round_trips_sample_bytes() {
+    let inspector = load_inspector(
+        &schema(),
+        Some("SystemEvent"),
+        &[],
+        &sample_bytes(),
+        InputFormat::Binary,
+    )
+    .unwrap();
+    let text = inspector.text_format();
+
+    let reparsed = load_inspector(
+        &schema(),
+        Some("SystemEvent"),
+        &[],
+        text.as_bytes(),
+        InputFormat::Text,
+    )
+    .unwrap();
+
+    assert_eq!(reparsed.bytes().unwrap(), sample_bytes());
+    assert_eq!(
+        decode::resolve_edit_input_format(text.as_bytes(), InputFormat::Auto, false).unwrap(),
+        InputFormat::Text
+    );
+}
+
+#[test]
+fn text_format_input_reports_parse_location() {
+    let error = load_inspector(
+        &schema(),
+        Some("SystemEvent"),
+        &[],
+        b"reason: \"ok\"\nclick { x: nope }\n",
+        InputFormat::Text,
+    )
+    .err()
+    .unwrap();
+    let rendered = format!("{error:?}");
+
+    assert!(rendered.contains("Input format: text"));
+    assert!(rendered.contains("Message type: SystemEvent"));
+}
@@ -1,7 +1,7 @@
 use base64::Engine as _;
 use error_stack::{Report, ResultExt as _};
 use jaq_core::{
-    Ctx, Vars, data,
+    Ctx, ValT as _, Vars, data,
     load::{Arena, File, Loader},
     unwrap_valr,
 };
@@ -12,6 +12,17 @@ use crate::{
     message::{InputFormat, Inspector},
 };
 
+/// Options for printing canonical JSON: enum names, JSON field names, and every field including
+/// those holding default values.
+pub(crate) fn print_options() -> protobuf_json_mapping::PrintOptions {
+    protobuf_json_mapping::PrintOptions {
+        enum_values_int: false,
+        proto_field_name: false,
+        always_output_default_values: true,
+        ..Default::default()
+    }
+}
+
 pub(crate) fn compact_json(inspector: &Inspector) -> std::result::Result<String, Report<Inspect>> {
     let value = serde_json::from_str::<serde_json::Value>(&inspector.canonical_json()?)
         .change_context(Inspect)?;
@@ -30,26 +41,32 @@ pub(crate) fn encode_line_output(
     inspector: &Inspector,
     output_format: InputFormat,
 ) -> std::result::Result<String, Report<Inspect>> {
+    if matches!(output_format, InputFormat::Json | InputFormat::Text)
+        && let Some(error) = inspector.decode_error()
+    {
+        return Err(Report::new(Inspect).attach(format!(
+            "Cannot write {} while the bytes do not decode. {error}",
+            output_format.as_str()
+        )));
+    }
+
     let bytes = inspector.bytes()?;
     match output_format {
         InputFormat::Base64 => Ok(base64::prelude::BASE64_STANDARD.encode(bytes)),
         InputFormat::Hex => Ok(hex::encode(bytes)),
         InputFormat::Json => compact_json(inspector),
+        InputFormat::Text => Ok(inspector.compact_text_format()),
         InputFormat::Auto | InputFormat::Binary => Err(Report::new(Inspect)
-            .attach("line-based editing only supports json, hex, or base64 output")),
+            .attach("line-based editing only supports json, text, hex, or base64 output")),
     }
 }
 
-pub(crate) fn apply_json_filter(
-    json: &str,
+/// A compiled `jaq` filter, ready to run against many JSON values.
+pub(crate) type JsonFilter = jaq_core::Filter<data::JustLut<JaqVal>>;
+
+pub(crate) fn compile_json_filter(
     filter: &str,
-) -> std::result::Result<String, Report<Inspect>> {
-    let input = serde_json::from_str::<serde_json::Value>(json)
-        .attach("Input format: json")
-        .change_context(Inspect)?;
-    let input = serde_json::from_value::<JaqVal>(input)
-        .attach("Input format: json")
-        .change_context(Inspect)?;
+) -> std::result::Result<JsonFilter, Report<Inspect>> {
     let program = File {
         code: filter,
         path: (),
@@ -63,14 +80,48 @@ pub(crate) fn apply_json_filter(
     let modules = loader
         .load(&arena, program)
         .map_err(|errors| Report::new(Inspect).attach(format!("Invalid filter: {errors:?}")))?;
-    let filter = jaq_core::Compiler::default()
+    jaq_core::Compiler::default()
         .with_funs(
             jaq_core::funs()
                 .chain(jaq_std::funs())
                 .chain(jaq_json::funs()),
         )
         .compile(modules)
-        .map_err(|errors| Report::new(Inspect).attach(format!("Invalid filter: {errors:?}")))?;
+        .map_err(|errors| Report::new(Inspect).attach(format!("Invalid filter: {errors:?}")))
+}
+
+fn jaq_input(json: &str) -> std::result::Result<JaqVal, Report<Inspect>> {
+    let input = serde_json::from_str::<serde_json::Value>(json)
+        .attach("Input format: json")
+        .change_context(Inspect)?;
+    serde_json::from_value::<JaqVal>(input)
+        .attach("Input format: json")
+        .change_context(Inspect)
+}
+
+/// Whether `filter` yields a truthy value for `json`; like jq, everything except `false` and
+/// `null` is truthy. A filter that yields nothing, such as a failed `select`, or that fails on
+/// this input before yielding a truthy value, does not match.
+pub(crate) fn json_filter_matches(filter: &JsonFilter, json: &str) -> bool {
+    let Ok(input) = jaq_input(json) else {
+        return false;
+    };
+    let ctx = Ctx::<data::JustLut<JaqVal>>::new(&filter.lut, Vars::new([]));
+
+    filter
+        .id
+        .run((ctx, input))
+        .map(unwrap_valr)
+        .map_while(Result::ok)
+        .any(|value| value.as_bool())
+}
+
+pub(crate) fn apply_json_filter(
+    json: &str,
+    filter: &str,
+) -> std::result::Result<String, Report<Inspect>> {
+    let input = jaq_input(json)?;
+    let filter = compile_json_filter(filter)?;
     let ctx = Ctx::<data::JustLut<JaqVal>>::new(&filter.lut, Vars::new([]));
     let mut output = filter.id.run((ctx, input)).map(unwrap_valr);
     let first = output
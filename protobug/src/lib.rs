@@ -1,22 +1,36 @@
+mod any;
 mod decode;
+mod detect;
+mod diff;
 mod edit;
 mod enum_edit;
 mod error;
+mod framing;
 mod inspect;
 mod json;
 mod line_wrap;
 mod message;
+mod raw;
 mod schema;
 mod selection;
 mod tui;
+mod unknown_fields;
 mod validate;
+mod well_known;
 
 pub use self::{
+    diff::{DiffOptions, MessageDiff, diff_payloads},
     edit::{
-        EditOptions, edit_in_place, edit_to_bytes, edit_to_encoded_lines, edit_to_json,
-        edit_to_json_lines,
+        EditOptions, edit_in_place, edit_to_bytes, edit_to_encoded_lines, edit_to_framed_bytes,
+        edit_to_json, edit_to_json_lines, edit_to_text,
     },
-    inspect::{InspectOptions, inspect_to_bytes, inspect_to_json, run_inspect},
-    message::{DisplayOptions, InputFormat, SaveTargets},
+    framing::{EncodedMessage, join_frames},
+    inspect::{
+        InspectOptions, inspect_to_bytes, inspect_to_human, inspect_to_json, inspect_to_text,
+        run_inspect,
+    },
+    message::{DisplayOptions, Framing, InputFormat, SaveTargets},
+    raw::{RawOptions, inspect_raw},
+    schema::{EmbeddedMessage, SchemaSource},
     validate::validate_schema,
 };
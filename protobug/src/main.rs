@@ -1,6 +1,6 @@
 //! Protobuf Debugging Suite.
 
-use std::io::Write as _;
+use std::{io::Write as _, process::ExitCode};
 
 use base64::Engine as _;
 use camino::Utf8PathBuf;
@@ -8,9 +8,11 @@ use clap::{Parser, Subcommand, ValueEnum};
 use derive_more::derive::{Display, Error};
 use error_stack::{Report, ResultExt as _};
 use protobug::{
-    DisplayOptions, EditOptions, InputFormat, InspectOptions, SaveTargets, edit_in_place,
-    edit_to_bytes, edit_to_encoded_lines, edit_to_json, edit_to_json_lines, inspect_to_bytes,
-    inspect_to_json, run_inspect, validate_schema,
+    DiffOptions, DisplayOptions, EditOptions, EmbeddedMessage, EncodedMessage, Framing,
+    InputFormat, InspectOptions, RawOptions, SaveTargets, SchemaSource, diff_payloads,
+    edit_in_place, edit_to_bytes, edit_to_encoded_lines, edit_to_framed_bytes, edit_to_json,
+    edit_to_json_lines, edit_to_text, inspect_raw, inspect_to_bytes, inspect_to_human,
+    inspect_to_json, inspect_to_text, join_frames, run_inspect, validate_schema,
 };
 
 #[derive(Debug, Parser)]
@@ -27,17 +29,42 @@ enum Commands {
     Validate {
         #[arg(long)]
         schema: Utf8PathBuf,
+
+        /// Additional directory to search for imported schema files. May be repeated.
+        #[arg(long = "include", short = 'I', value_name = "DIR")]
+        includes: Vec<Utf8PathBuf>,
     },
 
     /// Inspects a protobuf payload using a schema.
     Inspect {
-        #[arg(long)]
-        schema: Utf8PathBuf,
+        /// Schema file, or directory of schema files, to load message types from. May be repeated.
+        #[arg(
+            long = "schema",
+            value_name = "PATH",
+            required_unless_present_any = ["descriptor_set", "raw"]
+        )]
+        schemas: Vec<Utf8PathBuf>,
+
+        /// Additional directory to search for imported schema files. May be repeated.
+        #[arg(long = "include", short = 'I', value_name = "DIR")]
+        includes: Vec<Utf8PathBuf>,
 
-        /// Message name relative to the package in the schema.
+        /// Load message types from a compiled `FileDescriptorSet` instead of a .proto schema.
+        #[arg(long, conflicts_with_all = ["schemas", "includes"])]
+        descriptor_set: Option<Utf8PathBuf>,
+
+        /// Fully qualified message name, or a name relative to its schema's package. Pass "auto" to
+        /// pick the message type that best matches each payload.
         #[arg(long)]
         message: Option<String>,
 
+        /// Decode a bytes field as an embedded message, given as `FIELD_PATH=MESSAGE`, e.g.
+        /// `raw_envelope=TraceBundle.HttpRequest`. The path starts at the top-level message and
+        /// names a field of the message type it ends in, which is decoded wherever that type
+        /// appears. May be repeated.
+        #[arg(long = "embed", value_name = "FIELD_PATH=MESSAGE")]
+        embedded: Vec<EmbeddedMessage>,
+
         /// Input file path. Pass "-" to read from stdin.
         #[arg(long)]
         file: Option<Utf8PathBuf>,
@@ -46,14 +73,28 @@ enum Commands {
         #[arg(long, value_enum, default_value_t = InputFormatArg::Auto)]
         input_format: InputFormatArg,
 
-        /// Treat the input as one hex/base64 payload per line and inspect one message at a time.
+        /// Treat the input as several messages split by `--framing` and inspect one at a time.
         #[arg(long)]
         multiple: bool,
 
+        /// How messages are framed in the input. Without `--multiple` the input must hold exactly
+        /// one frame.
+        #[arg(long, value_enum, default_value_t = FramingArg::Lines)]
+        framing: FramingArg,
+
         /// Bytes per row shared by the hex and ASCII panes.
         #[arg(long, value_parser = parse_width)]
         columns: Option<usize>,
 
+        /// jaq filter that summarizes each message in the message list, e.g. `.exportId`.
+        #[arg(long, value_name = "FILTER")]
+        summary: Option<String>,
+
+        /// Show timestamps with the local UTC offset rather than in UTC, in the Protobuf pane and
+        /// with `--print-format human`.
+        #[arg(long)]
+        local_time: bool,
+
         /// Save the current message as pretty JSON when Ctrl-S is pressed.
         #[arg(long)]
         save_json: Option<Utf8PathBuf>,
@@ -70,24 +111,73 @@ enum Commands {
         #[arg(long)]
         save_base64: Option<Utf8PathBuf>,
 
+        /// Save every message to this file when Ctrl-W is pressed, framed and encoded like the
+        /// input.
+        #[arg(long, value_name = "PATH", requires = "multiple")]
+        save_all: Option<Utf8PathBuf>,
+
+        /// Save every message back to the input file when Ctrl-W is pressed.
+        #[arg(long, requires_all = ["multiple", "file"], conflicts_with = "save_all")]
+        save_all_in_place: bool,
+
+        /// Encoding used by Ctrl-W for each line, or for the whole stream with length-prefixed
+        /// framing. Defaults to the input's encoding.
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        save_all_format: Option<OutputFormatArg>,
+
         /// Print the decoded message in the selected format and exit.
         #[arg(
             long,
             value_enum,
-            conflicts_with_all = ["columns", "save_json", "save_bin", "save_hex", "save_base64"]
+            conflicts_with_all = [
+                "columns", "summary", "save_json", "save_bin", "save_hex", "save_base64",
+                "save_all", "save_all_in_place", "save_all_format",
+            ]
         )]
-        print_format: Option<OutputFormatArg>,
+        print_format: Option<InspectPrintFormatArg>,
+
+        /// Print the field numbers and wire values of the payload without a schema and exit.
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "schemas", "includes", "descriptor_set", "message", "embedded", "columns",
+                "summary", "local_time", "save_json", "save_bin", "save_hex", "save_base64", "save_all",
+                "save_all_in_place", "save_all_format", "print_format",
+            ]
+        )]
+        raw: bool,
     },
 
     /// Edits protobuf payloads by applying a jaq filter to their JSON representation.
     Edit {
-        #[arg(long)]
-        schema: Utf8PathBuf,
+        /// Schema file, or directory of schema files, to load message types from. May be repeated.
+        #[arg(
+            long = "schema",
+            value_name = "PATH",
+            required_unless_present = "descriptor_set"
+        )]
+        schemas: Vec<Utf8PathBuf>,
 
-        /// Message name relative to the package in the schema.
+        /// Additional directory to search for imported schema files. May be repeated.
+        #[arg(long = "include", short = 'I', value_name = "DIR")]
+        includes: Vec<Utf8PathBuf>,
+
+        /// Load message types from a compiled `FileDescriptorSet` instead of a .proto schema.
+        #[arg(long, conflicts_with_all = ["schemas", "includes"])]
+        descriptor_set: Option<Utf8PathBuf>,
+
+        /// Fully qualified message name, or a name relative to its schema's package. Pass "auto" to
+        /// pick the message type that best matches each payload.
         #[arg(long)]
         message: Option<String>,
 
+        /// Decode a bytes field as an embedded message, given as `FIELD_PATH=MESSAGE`, e.g.
+        /// `raw_envelope=TraceBundle.HttpRequest`. The path starts at the top-level message and
+        /// names a field of the message type it ends in, which is decoded wherever that type
+        /// appears. May be repeated.
+        #[arg(long = "embed", value_name = "FIELD_PATH=MESSAGE")]
+        embedded: Vec<EmbeddedMessage>,
+
         /// Input file path. Pass "-" to read from stdin.
         #[arg(long)]
         file: Option<Utf8PathBuf>,
@@ -100,10 +190,15 @@ enum Commands {
         #[arg(long)]
         filter: Option<String>,
 
-        /// Treat the input as one hex/base64 payload per line and edit each line independently.
+        /// Treat the input as several messages split by `--framing` and edit each independently.
         #[arg(long)]
         multiple: bool,
 
+        /// How messages are framed in the input and output. Without `--multiple` the input must
+        /// hold exactly one frame.
+        #[arg(long, value_enum, default_value_t = FramingArg::Lines)]
+        framing: FramingArg,
+
         /// Overwrite the input file using the input file's encoding.
         #[arg(long, conflicts_with = "print_format")]
         in_place: bool,
@@ -112,6 +207,55 @@ enum Commands {
         #[arg(long, value_enum)]
         print_format: Option<OutputFormatArg>,
     },
+
+    /// Compares two protobuf payloads field by field. Exits with status 1 when they differ.
+    Diff {
+        /// Schema file, or directory of schema files, to load message types from. May be repeated.
+        #[arg(
+            long = "schema",
+            value_name = "PATH",
+            required_unless_present = "descriptor_set"
+        )]
+        schemas: Vec<Utf8PathBuf>,
+
+        /// Additional directory to search for imported schema files. May be repeated.
+        #[arg(long = "include", short = 'I', value_name = "DIR")]
+        includes: Vec<Utf8PathBuf>,
+
+        /// Load message types from a compiled `FileDescriptorSet` instead of a .proto schema.
+        #[arg(long, conflicts_with_all = ["schemas", "includes"])]
+        descriptor_set: Option<Utf8PathBuf>,
+
+        /// Fully qualified message name, or a name relative to its schema's package. Pass "auto" to
+        /// pick the message type that best matches each payload.
+        #[arg(long)]
+        message: Option<String>,
+
+        /// Decode a bytes field as an embedded message, given as `FIELD_PATH=MESSAGE`, e.g.
+        /// `raw_envelope=TraceBundle.HttpRequest`. The path starts at the top-level message and
+        /// names a field of the message type it ends in, which is decoded wherever that type
+        /// appears. May be repeated.
+        #[arg(long = "embed", value_name = "FIELD_PATH=MESSAGE")]
+        embedded: Vec<EmbeddedMessage>,
+
+        /// The original payload. Pass "-" to read from stdin.
+        left: Utf8PathBuf,
+
+        /// The payload to compare against the original. Pass "-" to read from stdin.
+        right: Utf8PathBuf,
+
+        /// How to decode the left payload.
+        #[arg(long, value_enum, default_value_t = EditInputFormatArg::Auto)]
+        left_format: EditInputFormatArg,
+
+        /// How to decode the right payload.
+        #[arg(long, value_enum, default_value_t = EditInputFormatArg::Auto)]
+        right_format: EditInputFormatArg,
+
+        /// Print the changes as a JSON array.
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -120,6 +264,7 @@ enum InputFormatArg {
     Base64,
     Hex,
     Binary,
+    Text,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -128,6 +273,28 @@ enum OutputFormatArg {
     Binary,
     Base64,
     Hex,
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum InspectPrintFormatArg {
+    Json,
+    Binary,
+    Base64,
+    Hex,
+    Text,
+    /// The Protobuf pane's view, with timestamps, durations, wrappers, and structs shown readably.
+    Human,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FramingArg {
+    /// One hex or base64 payload per line with `--multiple`, and no framing otherwise.
+    Lines,
+    /// Varint length-prefixed messages, as written by `writeDelimitedTo`.
+    Delimited,
+    /// gRPC and gRPC-Web frames with a flag byte and a 4-byte big-endian length.
+    Grpc,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -137,6 +304,7 @@ enum EditInputFormatArg {
     Base64,
     Hex,
     Binary,
+    Text,
 }
 
 impl From<InputFormatArg> for InputFormat {
@@ -146,6 +314,7 @@ impl From<InputFormatArg> for InputFormat {
             InputFormatArg::Base64 => Self::Base64,
             InputFormatArg::Hex => Self::Hex,
             InputFormatArg::Binary => Self::Binary,
+            InputFormatArg::Text => Self::Text,
         }
     }
 }
@@ -158,6 +327,29 @@ impl From<EditInputFormatArg> for InputFormat {
             EditInputFormatArg::Base64 => Self::Base64,
             EditInputFormatArg::Hex => Self::Hex,
             EditInputFormatArg::Binary => Self::Binary,
+            EditInputFormatArg::Text => Self::Text,
+        }
+    }
+}
+
+impl From<OutputFormatArg> for InputFormat {
+    fn from(value: OutputFormatArg) -> Self {
+        match value {
+            OutputFormatArg::Json => Self::Json,
+            OutputFormatArg::Binary => Self::Binary,
+            OutputFormatArg::Base64 => Self::Base64,
+            OutputFormatArg::Hex => Self::Hex,
+            OutputFormatArg::Text => Self::Text,
+        }
+    }
+}
+
+impl From<FramingArg> for Framing {
+    fn from(value: FramingArg) -> Self {
+        match value {
+            FramingArg::Lines => Self::Lines,
+            FramingArg::Delimited => Self::Delimited,
+            FramingArg::Grpc => Self::Grpc,
         }
     }
 }
@@ -172,6 +364,20 @@ fn parse_width(value: &str) -> Result<usize, String> {
     Ok(width)
 }
 
+fn schema_source(
+    schemas: Vec<Utf8PathBuf>,
+    includes: Vec<Utf8PathBuf>,
+    descriptor_set: Option<Utf8PathBuf>,
+) -> SchemaSource {
+    match descriptor_set {
+        Some(path) => SchemaSource::DescriptorSet(path),
+        None => SchemaSource::Proto {
+            paths: schemas,
+            includes,
+        },
+    }
+}
+
 fn default_edit_output_format(input_format: EditInputFormatArg, multiple: bool) -> OutputFormatArg {
     if multiple {
         match input_format {
@@ -184,12 +390,31 @@ fn default_edit_output_format(input_format: EditInputFormatArg, multiple: bool)
     }
 }
 
-fn write_output(bytes: &[u8], output_format: OutputFormatArg) -> std::io::Result<()> {
+fn print_encoded(
+    options: InspectOptions,
+    output_format: OutputFormatArg,
+    framing: Framing,
+) -> Result<(), Report<ProtobugError>> {
+    let encoded = inspect_to_bytes(options).change_context(ProtobugError)?;
+    write_output(&encoded, output_format, framing)
+        .change_context(ProtobugError)
+        .attach("Failed to write encoded protobuf output")
+}
+
+/// Writes `encoded` in `framing`, followed by the trailer frames it came with.
+fn write_output(
+    encoded: &EncodedMessage,
+    output_format: OutputFormatArg,
+    framing: Framing,
+) -> std::io::Result<()> {
+    let mut bytes = join_frames([encoded.bytes.as_slice()], framing);
+    bytes.extend_from_slice(&encoded.trailer);
+    let bytes = &bytes;
     let mut stdout = std::io::stdout().lock();
 
     match output_format {
-        OutputFormatArg::Json => {
-            unreachable!("json output is handled before raw output is written")
+        OutputFormatArg::Json | OutputFormatArg::Text => {
+            unreachable!("json and text output are handled before raw output is written")
         }
         OutputFormatArg::Binary => stdout.write_all(bytes)?,
         OutputFormatArg::Base64 => {
@@ -205,40 +430,96 @@ fn write_output(bytes: &[u8], output_format: OutputFormatArg) -> std::io::Result
 #[display("Exit")]
 pub(crate) struct ProtobugError;
 
-fn main() -> std::result::Result<(), Report<ProtobugError>> {
+/// Exit status of `diff` when it fails, following diff(1): `0` means the payloads are equal and
+/// `1` that they differ.
+const DIFF_ERROR_STATUS: u8 = 2;
+
+fn main() -> ExitCode {
     let args = Args::parse();
+    let error_status = match args.command {
+        Commands::Diff { .. } => DIFF_ERROR_STATUS,
+        _ => 1,
+    };
+
+    match run(args) {
+        Ok(status) => status,
+        Err(report) => {
+            eprintln!("Error: {report:?}");
+            ExitCode::from(error_status)
+        }
+    }
+}
 
+fn run(args: Args) -> std::result::Result<ExitCode, Report<ProtobugError>> {
     match args.command {
         Commands::Validate {
             schema: schema_path,
+            includes,
         } => {
             println!(
                 "{}",
-                validate_schema(schema_path).map_err(|err| err.change_context(ProtobugError))?,
+                validate_schema(schema_path, &includes)
+                    .map_err(|err| err.change_context(ProtobugError))?,
             );
         }
 
         Commands::Inspect {
-            schema,
+            schemas,
+            includes,
+            descriptor_set,
             message,
+            embedded,
             file,
             input_format,
             multiple,
+            framing,
             columns,
+            summary,
+            local_time,
             save_json,
             save_bin,
             save_hex,
             save_base64,
+            save_all,
+            save_all_in_place,
+            save_all_format,
             print_format,
+            raw,
         } => {
+            if raw {
+                let options = RawOptions {
+                    file,
+                    input_format: input_format.into(),
+                    multiple,
+                    framing: framing.into(),
+                };
+                print!("{}", inspect_raw(options).change_context(ProtobugError)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let save_all = if save_all_in_place {
+                if file.as_ref().is_none_or(|file| file.as_str() == "-") {
+                    return Err(Report::new(ProtobugError).attach(
+                        "`--save-all-in-place` does not support stdin; pass a file path instead",
+                    ));
+                }
+                file.clone()
+            } else {
+                save_all
+            };
+
             let options = InspectOptions {
-                schema,
+                schema: schema_source(schemas, includes, descriptor_set),
                 message,
+                embedded,
                 file,
                 input_format: input_format.into(),
                 multiple,
+                framing: framing.into(),
                 display_options: DisplayOptions {
                     columns,
+                    message_summary: summary,
+                    local_time,
                     ..Default::default()
                 },
                 save_targets: SaveTargets {
@@ -246,21 +527,38 @@ fn main() -> std::result::Result<(), Report<ProtobugError>> {
                     base64: save_base64,
                     hex: save_hex,
                     binary: save_bin,
+                    all: save_all,
+                    all_format: save_all_format.map(Into::into),
                 },
             };
 
             match print_format {
-                Some(OutputFormatArg::Json) => {
+                Some(InspectPrintFormatArg::Json) => {
                     println!(
                         "{}",
                         inspect_to_json(options).change_context(ProtobugError)?,
                     );
                 }
-                Some(output_format) => {
-                    let bytes = inspect_to_bytes(options).change_context(ProtobugError)?;
-                    write_output(&bytes, output_format)
-                        .change_context(ProtobugError)
-                        .attach("Failed to write encoded protobuf output")?;
+                Some(InspectPrintFormatArg::Text) => {
+                    print!(
+                        "{}",
+                        inspect_to_text(options).change_context(ProtobugError)?
+                    );
+                }
+                Some(InspectPrintFormatArg::Human) => {
+                    print!(
+                        "{}",
+                        inspect_to_human(options).change_context(ProtobugError)?
+                    );
+                }
+                Some(InspectPrintFormatArg::Binary) => {
+                    print_encoded(options, OutputFormatArg::Binary, framing.into())?;
+                }
+                Some(InspectPrintFormatArg::Base64) => {
+                    print_encoded(options, OutputFormatArg::Base64, framing.into())?;
+                }
+                Some(InspectPrintFormatArg::Hex) => {
+                    print_encoded(options, OutputFormatArg::Hex, framing.into())?;
                 }
                 None => {
                     run_inspect(options).change_context(ProtobugError)?;
@@ -269,26 +567,60 @@ fn main() -> std::result::Result<(), Report<ProtobugError>> {
         }
 
         Commands::Edit {
-            schema,
+            schemas,
+            includes,
+            descriptor_set,
             message,
+            embedded,
             file,
             input_format,
             filter,
             multiple,
+            framing,
             in_place,
             print_format,
         } => {
             let options = EditOptions {
-                schema,
+                schema: schema_source(schemas, includes, descriptor_set),
                 message,
+                embedded,
                 file,
                 input_format: input_format.into(),
                 filter,
                 multiple,
+                framing: framing.into(),
             };
 
             if in_place {
                 edit_in_place(options).change_context(ProtobugError)?;
+            } else if multiple && framing != FramingArg::Lines {
+                match print_format
+                    .unwrap_or_else(|| default_edit_output_format(input_format, multiple))
+                {
+                    OutputFormatArg::Json => {
+                        print!(
+                            "{}",
+                            edit_to_json_lines(options).change_context(ProtobugError)?
+                        );
+                    }
+                    OutputFormatArg::Text => {
+                        print!(
+                            "{}",
+                            edit_to_encoded_lines(options, InputFormat::Text)
+                                .change_context(ProtobugError)?
+                        );
+                    }
+                    output_format => {
+                        // The stream is already framed, trailers included.
+                        let encoded = EncodedMessage {
+                            bytes: edit_to_framed_bytes(options).change_context(ProtobugError)?,
+                            trailer: Vec::new(),
+                        };
+                        write_output(&encoded, output_format, Framing::Lines)
+                            .change_context(ProtobugError)
+                            .attach("Failed to write encoded protobuf output")?;
+                    }
+                }
             } else {
                 match print_format
                     .unwrap_or_else(|| default_edit_output_format(input_format, multiple))
@@ -302,6 +634,16 @@ fn main() -> std::result::Result<(), Report<ProtobugError>> {
                     OutputFormatArg::Json => {
                         println!("{}", edit_to_json(options).change_context(ProtobugError)?);
                     }
+                    OutputFormatArg::Text if multiple => {
+                        print!(
+                            "{}",
+                            edit_to_encoded_lines(options, InputFormat::Text)
+                                .change_context(ProtobugError)?
+                        );
+                    }
+                    OutputFormatArg::Text => {
+                        print!("{}", edit_to_text(options).change_context(ProtobugError)?);
+                    }
                     OutputFormatArg::Hex if multiple => {
                         print!(
                             "{}",
@@ -322,15 +664,49 @@ fn main() -> std::result::Result<(), Report<ProtobugError>> {
                         ));
                     }
                     output_format => {
-                        let bytes = edit_to_bytes(options).change_context(ProtobugError)?;
-                        write_output(&bytes, output_format)
+                        let encoded = edit_to_bytes(options).change_context(ProtobugError)?;
+                        write_output(&encoded, output_format, framing.into())
                             .change_context(ProtobugError)
                             .attach("Failed to write encoded protobuf output")?;
                     }
                 }
             }
         }
+
+        Commands::Diff {
+            schemas,
+            includes,
+            descriptor_set,
+            message,
+            embedded,
+            left,
+            right,
+            left_format,
+            right_format,
+            json,
+        } => {
+            let diff = diff_payloads(DiffOptions {
+                schema: schema_source(schemas, includes, descriptor_set),
+                message,
+                embedded,
+                left,
+                right,
+                left_input_format: left_format.into(),
+                right_input_format: right_format.into(),
+            })
+            .change_context(ProtobugError)?;
+
+            if json {
+                println!("{}", diff.to_json().change_context(ProtobugError)?);
+            } else {
+                print!("{}", diff.to_text());
+            }
+
+            if diff.has_changes() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
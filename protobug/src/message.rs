@@ -3,12 +3,20 @@ use std::{collections::BTreeSet, fs};
 use base64::{Engine as _, prelude::BASE64_STANDARD};
 use camino::Utf8PathBuf;
 use error_stack::{Report, ResultExt as _};
-use protobuf::{MessageDyn, reflect::MessageDescriptor};
+use protobuf::{
+    MessageDyn,
+    reflect::{MessageDescriptor, MessageRef, ReflectEq as _, ReflectEqMode, ReflectValueRef},
+};
 
 use crate::{
+    any::{self, TypeRegistry},
+    detect,
+    diff::{self, FieldChange},
     enum_edit,
     error::Inspect,
-    selection::{self, FieldPath, ProtobufLine},
+    json,
+    selection::{self, FieldPath, ProtobufLine, ProtobufNode},
+    unknown_fields,
 };
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -19,6 +27,7 @@ pub enum InputFormat {
     Base64,
     Hex,
     Binary,
+    Text,
 }
 
 impl InputFormat {
@@ -29,29 +38,54 @@ impl InputFormat {
             Self::Base64 => "base64",
             Self::Hex => "hex",
             Self::Binary => "binary",
+            Self::Text => "text",
         }
     }
 }
 
+/// How messages are separated from each other in the input and output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// One hex or base64 payload per line with `--multiple`, and no framing otherwise.
+    #[default]
+    Lines,
+    /// Each message is prefixed with its length as a varint, as written by `writeDelimitedTo`.
+    Delimited,
+    /// Each message is prefixed with a compressed flag byte and a 4-byte big-endian length, as
+    /// sent over gRPC and gRPC-Web.
+    Grpc,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct SaveTargets {
     pub json: Option<Utf8PathBuf>,
     pub base64: Option<Utf8PathBuf>,
     pub hex: Option<Utf8PathBuf>,
     pub binary: Option<Utf8PathBuf>,
+    /// Receives every message of a `--multiple` input at once, framed like the input.
+    pub all: Option<Utf8PathBuf>,
+    /// Encoding of `all`; the input's encoding when unset.
+    pub all_format: Option<InputFormat>,
 }
 
 impl SaveTargets {
+    /// Whether no per-message targets are configured; `all` is saved separately.
     pub(crate) fn is_empty(&self) -> bool {
         self.json.is_none() && self.base64.is_none() && self.hex.is_none() && self.binary.is_none()
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DisplayOptions {
     pub columns: Option<usize>,
     pub show_hex: bool,
     pub show_ascii: bool,
+    /// Show how the selected field is encoded below the JSON pane.
+    pub show_details: bool,
+    /// jaq filter that summarizes each message in the message list.
+    pub message_summary: Option<String>,
+    /// Show timestamps in the Protobuf pane with the local UTC offset rather than in UTC.
+    pub local_time: bool,
 }
 
 impl Default for DisplayOptions {
@@ -60,6 +94,9 @@ impl Default for DisplayOptions {
             columns: None,
             show_hex: true,
             show_ascii: true,
+            show_details: false,
+            message_summary: None,
+            local_time: false,
         }
     }
 }
@@ -73,58 +110,217 @@ pub(crate) struct EnumSelection {
 pub(crate) struct Inspector {
     md: MessageDescriptor,
     data: Box<dyn MessageDyn>,
+    /// Message types that `google.protobuf.Any` values are unpacked as.
+    registry: TypeRegistry,
     parse_error: Option<String>,
+    /// Bytes typed in the hex editor, kept exactly as typed until the message is edited another
+    /// way. `data` holds the last of them that decoded.
+    raw_bytes: Option<Vec<u8>>,
+    /// Why `raw_bytes` do not decode as the message.
+    decode_error: Option<String>,
+    /// Message types the payload parsed as when `--message auto` picked `md`, best match first;
+    /// empty when the type was named.
+    detected_candidates: Vec<detect::MessageCandidate>,
+    /// Why `--embed` fields are shown as bytes when the payload was parsed without them.
+    embed_note: Option<String>,
+}
+
+/// A copy of an inspector's message that [`Inspector::restore`] can bring back.
+pub(crate) struct Snapshot {
+    data: Box<dyn MessageDyn>,
+    raw_bytes: Option<Vec<u8>>,
+    decode_error: Option<String>,
+}
+
+impl Clone for Snapshot {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone_box(),
+            raw_bytes: self.raw_bytes.clone(),
+            decode_error: self.decode_error.clone(),
+        }
+    }
 }
 
 impl Inspector {
-    pub(crate) fn new(md: MessageDescriptor, data: Box<dyn MessageDyn>) -> Self {
+    pub(crate) fn new(
+        md: MessageDescriptor,
+        data: Box<dyn MessageDyn>,
+        registry: TypeRegistry,
+    ) -> Self {
         Self {
             md,
             data,
+            registry,
             parse_error: None,
+            raw_bytes: None,
+            decode_error: None,
+            detected_candidates: Vec::new(),
+            embed_note: None,
         }
     }
 
+    pub(crate) fn with_detected_candidates(
+        mut self,
+        candidates: Vec<detect::MessageCandidate>,
+    ) -> Self {
+        self.detected_candidates = candidates;
+        self
+    }
+
+    pub(crate) fn with_embed_note(mut self, note: String) -> Self {
+        self.embed_note = Some(note);
+        self
+    }
+
     pub(crate) fn apply_json(&mut self, json: &str) -> Result<(), String> {
-        match protobuf_json_mapping::parse_dyn_from_str(&self.md, json) {
-            Ok(msg) => {
+        let parsed = any::pack_json(&self.registry, &self.md, json).and_then(|json| {
+            protobuf_json_mapping::parse_dyn_from_str(&self.md, &json)
+                .map_err(|error| error.to_string())
+        });
+
+        match parsed {
+            Ok(mut msg) => {
+                // JSON has no representation for unknown fields, so carry them over from the
+                // message the edited JSON was rendered from.
+                unknown_fields::restore_unknown_fields(&*self.data, &mut *msg);
                 self.data = msg;
                 self.parse_error = None;
+                self.raw_bytes = None;
+                self.decode_error = None;
                 Ok(())
             }
-            Err(err) => {
-                let error = err.to_string();
+            Err(error) => {
                 self.parse_error = Some(error.clone());
                 Err(error)
             }
         }
     }
 
+    /// Replaces the message with `bytes` edited in the hex pane. The bytes are kept as they are
+    /// even when they do not decode, in which case the message keeps its last decodable value
+    /// and the error names the offset where decoding fails.
+    pub(crate) fn apply_bytes(&mut self, bytes: Vec<u8>) -> Result<(), String> {
+        let decoded = match self.md.parse_from_bytes(&bytes) {
+            Ok(message) => {
+                self.data = message;
+                Ok(())
+            }
+            Err(error) => Err(match detect::first_invalid_offset(&self.md, &bytes) {
+                Some(offset) => format!("Bytes do not decode at offset {offset}: {error}"),
+                None => format!("Bytes do not decode: {error}"),
+            }),
+        };
+
+        self.raw_bytes = Some(bytes);
+        self.parse_error = None;
+        self.decode_error = decoded.as_ref().err().cloned();
+        decoded
+    }
+
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            data: self.data.clone_box(),
+            raw_bytes: self.raw_bytes.clone(),
+            decode_error: self.decode_error.clone(),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: Snapshot) {
+        self.data = snapshot.data;
+        self.raw_bytes = snapshot.raw_bytes;
+        self.decode_error = snapshot.decode_error;
+        self.parse_error = None;
+    }
+
+    /// Whether `other`, a snapshot of a message of the same type, has the same field values, or
+    /// the same bytes when either was edited in the hex pane.
+    pub(crate) fn same_message(&self, other: &Snapshot) -> bool {
+        if self.raw_bytes.is_some() || other.raw_bytes.is_some() {
+            let other_bytes = match &other.raw_bytes {
+                Some(bytes) => Some(bytes.clone()),
+                None => other.data.write_to_bytes_dyn().ok(),
+            };
+            return self.bytes().ok() == other_bytes;
+        }
+
+        ReflectValueRef::Message(MessageRef::new(&*self.data)).reflect_eq(
+            &ReflectValueRef::Message(MessageRef::new(&*other.data)),
+            &ReflectEqMode::nan_equal(),
+        )
+    }
+
+    pub(crate) fn descriptor(&self) -> &MessageDescriptor {
+        &self.md
+    }
+
+    /// The full name of the message type when `--message auto` picked it.
+    pub(crate) fn detected_type(&self) -> Option<&str> {
+        (!self.detected_candidates.is_empty()).then(|| self.md.full_name())
+    }
+
+    pub(crate) fn detected_candidates(&self) -> &[detect::MessageCandidate] {
+        &self.detected_candidates
+    }
+
+    pub(crate) fn embed_note(&self) -> Option<&str> {
+        self.embed_note.as_deref()
+    }
+
     pub(crate) fn parse_error(&self) -> Option<&str> {
         self.parse_error.as_deref()
     }
 
+    /// Why the bytes edited in the hex pane do not decode; the JSON and Protobuf views show the
+    /// last bytes that did.
+    pub(crate) fn decode_error(&self) -> Option<&str> {
+        self.decode_error.as_deref()
+    }
+
     pub(crate) fn canonical_json(&self) -> std::result::Result<String, Report<Inspect>> {
         let json = protobuf_json_mapping::print_to_string_with_options(
             &*self.data,
-            &protobuf_json_mapping::PrintOptions {
-                enum_values_int: false,
-                proto_field_name: false,
-                always_output_default_values: true,
-                ..Default::default()
-            },
+            &json::print_options(),
         )
         .change_context(Inspect)?;
 
-        let value = serde_json::from_str::<serde_json::Value>(&json).change_context(Inspect)?;
+        let mut value = serde_json::from_str::<serde_json::Value>(&json).change_context(Inspect)?;
+        any::expand_json(&self.registry, &self.md, &mut value);
         serde_json::to_string_pretty(&value).change_context(Inspect)
     }
 
-    pub(crate) fn protobuf_lines(&self) -> Vec<ProtobufLine> {
-        selection::protobuf_lines(&self.md, &*self.data)
+    /// The message in protobuf text format, as used by `.textproto` files.
+    pub(crate) fn text_format(&self) -> String {
+        protobuf::text_format::print_to_string_pretty(&*self.data)
+    }
+
+    /// The message in protobuf text format on a single line.
+    pub(crate) fn compact_text_format(&self) -> String {
+        protobuf::text_format::print_to_string(&*self.data)
+    }
+
+    /// The message as shown in the Protobuf pane, with timestamps in local time when `local_time`
+    /// is set and UTC otherwise.
+    pub(crate) fn protobuf_lines(&self, local_time: bool) -> Vec<ProtobufLine> {
+        selection::protobuf_lines(&self.registry, local_time, &self.md, &*self.data)
+    }
+
+    /// The lines of [`Self::protobuf_lines`] grouped into the runs the Protobuf pane can fold.
+    pub(crate) fn protobuf_nodes(&self, local_time: bool) -> Vec<ProtobufNode> {
+        selection::protobuf_nodes(&self.registry, local_time, &self.md, &*self.data)
+    }
+
+    /// Field-level changes from this message to `other`, or `None` when `other` is a different
+    /// message type.
+    pub(crate) fn diff(&self, other: &Inspector) -> Option<Vec<FieldChange>> {
+        (self.md == other.md).then(|| diff::diff_messages(&*self.data, &*other.data))
     }
 
     pub(crate) fn bytes(&self) -> std::result::Result<Vec<u8>, Report<Inspect>> {
+        if let Some(bytes) = &self.raw_bytes {
+            return Ok(bytes.clone());
+        }
+
         self.data.write_to_bytes_dyn().change_context(Inspect)
     }
 
@@ -133,30 +329,76 @@ impl Inspector {
         json: &str,
         cursor: (usize, usize),
     ) -> Option<FieldPath> {
-        selection::selected_path_for_json_cursor(&self.md, json, cursor)
+        selection::selected_path_for_json_cursor(&self.registry, &self.md, json, cursor)
     }
 
-    pub(crate) fn highlighted_byte_indices(
+    pub(crate) fn json_cursor_for_path(
+        &self,
+        json: &str,
+        path: &[selection::FieldPathSegment],
+    ) -> Option<(usize, usize)> {
+        selection::json_cursor_for_path(&self.registry, &self.md, json, path)
+    }
+
+    pub(crate) fn path_for_byte(
+        &self,
+        byte_index: usize,
+    ) -> std::result::Result<Option<FieldPath>, Report<Inspect>> {
+        Ok(selection::path_for_byte(
+            &self.registry,
+            &self.md,
+            &self.bytes()?,
+            byte_index,
+        ))
+    }
+
+    /// Where the field at `selected_path` is encoded, along with the encoded message. An element
+    /// of a packed field resolves to the packed field holding it.
+    pub(crate) fn field_occurrences(
         &self,
         selected_path: &[selection::FieldPathSegment],
-    ) -> std::result::Result<BTreeSet<usize>, Report<Inspect>> {
+    ) -> std::result::Result<(Vec<selection::FieldOccurrence>, Vec<u8>), Report<Inspect>> {
         let bytes = self.bytes()?;
-        let mut highlighted = selection::highlighted_byte_indices(&self.md, &bytes, selected_path);
+        let mut occurrences =
+            selection::field_occurrences(&self.registry, &self.md, &bytes, selected_path);
 
-        if highlighted.is_empty()
+        if occurrences.is_empty()
             && matches!(
                 selected_path.last(),
                 Some(selection::FieldPathSegment::Index(_))
             )
         {
-            highlighted = selection::highlighted_byte_indices(
+            occurrences = selection::field_occurrences(
+                &self.registry,
                 &self.md,
                 &bytes,
-                &selected_path[..selected_path.len().saturating_sub(1)],
+                &selected_path[..selected_path.len() - 1],
             );
         }
 
-        Ok(highlighted)
+        Ok((occurrences, bytes))
+    }
+
+    pub(crate) fn highlighted_byte_indices(
+        &self,
+        selected_path: &[selection::FieldPathSegment],
+    ) -> std::result::Result<BTreeSet<usize>, Report<Inspect>> {
+        self.highlighted_byte_indices_for_paths(&[selected_path])
+    }
+
+    /// Returns the bytes of the fields under any of `selected_paths`, such as every change of a
+    /// diff, in a single pass over the encoded message.
+    pub(crate) fn highlighted_byte_indices_for_paths(
+        &self,
+        selected_paths: &[&[selection::FieldPathSegment]],
+    ) -> std::result::Result<BTreeSet<usize>, Report<Inspect>> {
+        let bytes = self.bytes()?;
+        Ok(selection::highlighted_byte_indices(
+            &self.registry,
+            &self.md,
+            &bytes,
+            selected_paths,
+        ))
     }
 
     pub(crate) fn omitted_default_enum_hint(
@@ -181,6 +423,8 @@ impl Inspector {
         let next_variant =
             enum_edit::cycle_enum_variant(&mut *self.data, &self.md, selected_path, delta)?;
         self.parse_error = None;
+        self.raw_bytes = None;
+        self.decode_error = None;
         Some(next_variant)
     }
 
@@ -194,6 +438,12 @@ impl Inspector {
             ));
         }
 
+        if let (Some(error), Some(_)) = (&self.decode_error, &targets.json) {
+            return Err(Report::new(Inspect).attach(format!(
+                "Cannot save JSON while the bytes do not decode. {error}"
+            )));
+        }
+
         let bytes = self.bytes()?;
         let mut saved = Vec::new();
 
@@ -0,0 +1,270 @@
+use std::ops::Range;
+
+use camino::Utf8PathBuf;
+use error_stack::Report;
+
+use crate::{
+    decode,
+    error::Inspect,
+    framing,
+    message::{Framing, InputFormat},
+    selection::{self, ParsedField},
+};
+
+/// Nested length-delimited payloads are not decoded as messages past this depth.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawOptions {
+    pub file: Option<Utf8PathBuf>,
+    pub input_format: InputFormat,
+    pub multiple: bool,
+    pub framing: Framing,
+}
+
+/// Decodes payloads without a schema, like `protoc --decode_raw`.
+pub fn inspect_raw(options: RawOptions) -> std::result::Result<String, Report<Inspect>> {
+    let input = decode::read_input(options.file.as_deref())?;
+
+    if !options.multiple {
+        let single = framing::unframe_single(&input, options.framing, options.input_format)?;
+        let bytes = decode::decode_input(&single.payload, single.input_format)?;
+        return Ok(decode_raw(&bytes));
+    }
+
+    let multiple = framing::split_input(&input, options.framing, options.input_format, "inspect")?;
+    let mut blocks = Vec::new();
+
+    for (index, payload) in multiple.payloads.iter().enumerate() {
+        let bytes = decode::decode_input(payload, multiple.payload_format)?;
+        blocks.push(format!("# message {}\n{}", index + 1, decode_raw(&bytes)));
+    }
+
+    Ok(blocks.join("\n"))
+}
+
+pub(crate) fn decode_raw(bytes: &[u8]) -> String {
+    let mut lines = Vec::new();
+    render_fields(bytes, 0, 0, &mut lines);
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RawValue {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    LengthDelimited,
+}
+
+#[derive(Debug, Clone)]
+struct RawField {
+    number: u32,
+    value: RawValue,
+    payload: Range<usize>,
+}
+
+/// Splits `bytes` into fields, returning the offset of the first byte that is not valid wire data.
+fn read_fields(bytes: &[u8]) -> (Vec<RawField>, Option<usize>) {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let field_start = offset;
+        let Some(tag) = selection::read_varint(bytes, &mut offset) else {
+            return (fields, Some(field_start));
+        };
+        let number = (tag >> 3) as u32;
+        let wire_type = (tag & 0x07) as u8;
+
+        if number == 0 {
+            return (fields, Some(field_start));
+        }
+
+        let field = match selection::parse_field(bytes, &mut offset, wire_type) {
+            Some(ParsedField::Value(range)) => {
+                let value = match wire_type {
+                    0 => {
+                        let mut value_offset = range.start;
+                        RawValue::Varint(
+                            selection::read_varint(bytes, &mut value_offset).unwrap_or_default(),
+                        )
+                    }
+                    1 => RawValue::Fixed64(u64::from_le_bytes(
+                        bytes[range.clone()].try_into().unwrap_or_default(),
+                    )),
+                    _ => RawValue::Fixed32(u32::from_le_bytes(
+                        bytes[range.clone()].try_into().unwrap_or_default(),
+                    )),
+                };
+
+                RawField {
+                    number,
+                    value,
+                    payload: range,
+                }
+            }
+            Some(ParsedField::LengthDelimited { payload_range, .. }) => RawField {
+                number,
+                value: RawValue::LengthDelimited,
+                payload: payload_range,
+            },
+            None => return (fields, Some(field_start)),
+        };
+
+        fields.push(field);
+    }
+
+    (fields, None)
+}
+
+/// Renders the fields of a payload nested `depth` messages deep, indented by two spaces a level.
+fn render_fields(bytes: &[u8], base_offset: usize, depth: usize, lines: &mut Vec<String>) {
+    let (fields, error_offset) = read_fields(bytes);
+    let pad = "  ".repeat(depth);
+
+    for field in fields {
+        let number = field.number;
+
+        match field.value {
+            RawValue::Varint(value) => {
+                let mut alternatives = Vec::new();
+                if (value as i64) < 0 {
+                    alternatives.push(format!("int64: {}", value as i64));
+                }
+                alternatives.push(format!("sint: {}", zigzag_decode(value)));
+
+                lines.push(format!(
+                    "{pad}{number} <varint>: {value} ({})",
+                    alternatives.join(", ")
+                ));
+            }
+            RawValue::Fixed64(value) => lines.push(format!(
+                "{pad}{number} <i64>: {value} (sfixed64: {}, double: {})",
+                value as i64,
+                f64::from_bits(value),
+            )),
+            RawValue::Fixed32(value) => lines.push(format!(
+                "{pad}{number} <i32>: {value} (sfixed32: {}, float: {})",
+                value as i32,
+                f32::from_bits(value),
+            )),
+            RawValue::LengthDelimited => {
+                let payload = &bytes[field.payload.clone()];
+
+                match classify_payload(payload, depth) {
+                    Payload::String(text) => {
+                        lines.push(format!("{pad}{number} <len>: {text:?}"));
+                    }
+                    Payload::Message => {
+                        lines.push(format!("{pad}{number} <len> {{"));
+                        render_fields(payload, base_offset + field.payload.start, depth + 1, lines);
+                        lines.push(format!("{pad}}}"));
+                    }
+                    Payload::Bytes => {
+                        lines.push(format!("{pad}{number} <len>: bytes {}", hex_bytes(payload)));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(error_offset) = error_offset {
+        lines.push(format!(
+            "{pad}<invalid wire data at offset {}>: bytes {}",
+            base_offset + error_offset,
+            hex_bytes(&bytes[error_offset..]),
+        ));
+    }
+}
+
+enum Payload<'a> {
+    String(&'a str),
+    Message,
+    Bytes,
+}
+
+/// Guesses what a length-delimited payload holds. Printable UTF-8 wins over a nested message
+/// because short text frequently happens to be valid wire data too.
+fn classify_payload(payload: &[u8], depth: usize) -> Payload<'_> {
+    if let Ok(text) = std::str::from_utf8(payload)
+        && text
+            .chars()
+            .all(|ch| !ch.is_control() || matches!(ch, '\n' | '\r' | '\t'))
+    {
+        return Payload::String(text);
+    }
+
+    if depth < MAX_DEPTH && !payload.is_empty() && read_fields(payload).1.is_none() {
+        return Payload::Message;
+    }
+
+    Payload::Bytes
+}
+
+pub(crate) fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn sample_bytes(name: &str) -> Vec<u8> {
+        std::fs::read(format!(
+            "{}/../protogen/samples/{name}.bin",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn raw_system_event_matches_snapshot() {
+        assert_snapshot!(decode_raw(&sample_bytes("system-event")));
+    }
+
+    #[test]
+    fn raw_trace_bundle_matches_snapshot() {
+        assert_snapshot!(decode_raw(&sample_bytes("trace-bundle-http")));
+    }
+
+    #[test]
+    fn raw_decode_shows_alternate_numeric_interpretations() {
+        let bytes = [
+            0x08, 0x03, // 1: varint 3
+            0x10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // 2: varint -1
+            0x1d, 0x00, 0x00, 0x80, 0x3f, // 3: fixed32 1.0f
+        ];
+
+        assert_eq!(
+            decode_raw(&bytes),
+            "1 <varint>: 3 (sint: -2)\n\
+             2 <varint>: 18446744073709551615 (int64: -1, sint: -9223372036854775808)\n\
+             3 <i32>: 1065353216 (sfixed32: 1065353216, float: 1)\n",
+        );
+    }
+
+    #[test]
+    fn raw_decode_reports_truncated_wire_data() {
+        let bytes = [0x08, 0x01, 0x12, 0x05, b'a'];
+
+        assert_eq!(
+            decode_raw(&bytes),
+            "1 <varint>: 1 (sint: -1)\n<invalid wire data at offset 2>: bytes 12 05 61\n",
+        );
+    }
+}
@@ -1,107 +1,625 @@
+use std::{collections::HashSet, fmt, fs, str::FromStr};
+
 use camino::{Utf8Path, Utf8PathBuf};
 use error_stack::{IntoReportCompat as _, Report, ResultExt as _};
 use protobuf::{
-    descriptor::FileDescriptorProto,
-    reflect::{FileDescriptor, MessageDescriptor},
+    Message as _,
+    descriptor::{
+        DescriptorProto, FileDescriptorProto, FileDescriptorSet, field_descriptor_proto::Type,
+    },
+    reflect::{FieldDescriptor, FileDescriptor, MessageDescriptor, RuntimeFieldType, RuntimeType},
     text_format,
 };
+use protobuf_parse::ParsedAndTypechecked;
 
 use crate::{
+    any::{self, TypeRegistry},
     decode,
-    error::{Inspect, InvalidSchema, MultipleTopLevelMessages, NoTopLevelMessages},
+    detect::{self, AUTO_MESSAGE},
+    error::{
+        AmbiguousMessageName, Inspect, InvalidSchema, MultipleTopLevelMessages, NoMatchingMessage,
+        NoTopLevelMessages,
+    },
     message::{InputFormat, Inspector},
 };
 
+/// Where message descriptors are loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaSource {
+    /// `.proto` schema files, or directories of them, plus extra directories to search for their
+    /// imports.
+    Proto {
+        paths: Vec<Utf8PathBuf>,
+        includes: Vec<Utf8PathBuf>,
+    },
+
+    /// A compiled binary `FileDescriptorSet`, e.g. from `protoc --descriptor_set_out` or
+    /// `buf build -o`.
+    DescriptorSet(Utf8PathBuf),
+}
+
+impl fmt::Display for SchemaSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Proto { paths, .. } => f.write_str(&join_paths(paths)),
+            Self::DescriptorSet(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+/// A `bytes` field that holds another serialized message, decoded and edited as that message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedMessage {
+    /// Dotted path to the field from the top-level message, using proto or JSON field names,
+    /// e.g. `rawEnvelope` or `trace_bundle.raw_envelope`. The path only finds the field: the
+    /// field is retyped on the message type that declares it, so every occurrence of that type
+    /// decodes it, however it is reached.
+    pub field_path: String,
+
+    /// Message type the field holds, resolved like `--message`.
+    pub message: String,
+}
+
+impl FromStr for EmbeddedMessage {
+    type Err = String;
+
+    /// Parses `FIELD_PATH=MESSAGE`.
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.split_once('=') {
+            Some((field_path, message)) if !field_path.is_empty() && !message.is_empty() => {
+                Ok(Self {
+                    field_path: field_path.to_owned(),
+                    message: message.to_owned(),
+                })
+            }
+            _ => Err("expected FIELD_PATH=MESSAGE, e.g. raw_envelope=TraceBundle".to_owned()),
+        }
+    }
+}
+
+/// Message types loaded from a [`SchemaSource`].
+#[derive(Debug, Clone)]
+pub(crate) struct Schema {
+    /// Files the user asked for; their messages are listed and resolvable by package-relative name.
+    files: Vec<FileDescriptor>,
+
+    /// Every built file, including imports; any of their messages resolve by fully qualified name.
+    all_files: Vec<FileDescriptor>,
+}
+
+impl Schema {
+    /// Every loaded message type, for unpacking `google.protobuf.Any` values.
+    pub(crate) fn type_registry(&self) -> TypeRegistry {
+        TypeRegistry::new(self.all_files.clone())
+    }
+}
+
 pub(crate) fn validate_schema(
     schema_path: Utf8PathBuf,
+    includes: &[Utf8PathBuf],
 ) -> std::result::Result<String, Report<anyhow::Error>> {
-    let fds = protobuf_parse::Parser::new()
-        .pure()
-        .includes(schema_path.parent().as_slice())
-        .input(&schema_path)
-        .parse_and_typecheck()
-        .into_report()?;
-
-    let schema_name = schema_path.file_name().unwrap_or(schema_path.as_str());
-    let fd = fds
+    let parsed = parse_schema(std::slice::from_ref(&schema_path), includes)?;
+    let schema_name = parsed
+        .relative_paths
+        .first()
+        .map(|path| path.to_str())
+        .unwrap_or_else(|| schema_path.file_name().unwrap_or(schema_path.as_str()));
+    let fd = parsed
         .file_descriptors
         .iter()
         .find(|fd| fd.name() == schema_name)
-        .or_else(|| fds.file_descriptors.last())
+        .or_else(|| parsed.file_descriptors.last())
         .ok_or_else(|| anyhow::anyhow!("No file descriptors resolved from schema: {schema_path}"))
         .into_report()?;
 
     Ok(text_format::print_to_string_pretty(fd))
 }
 
+/// Parses schema files, resolving imports against the include paths followed by each schema's
+/// own directory.
+fn parse_schema(
+    schemas: &[Utf8PathBuf],
+    includes: &[Utf8PathBuf],
+) -> std::result::Result<ParsedAndTypechecked, Report<anyhow::Error>> {
+    let search_paths = import_search_paths(schemas, includes);
+
+    protobuf_parse::Parser::new()
+        .pure()
+        .includes(&search_paths)
+        .inputs(schemas)
+        .parse_and_typecheck()
+        .into_report()
+        .attach_with(|| format!("Schema files: {}", join_paths(schemas)))
+        .attach_with(|| format!("Import search paths: {}", join_paths(&search_paths)))
+}
+
+fn join_paths(paths: &[Utf8PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn import_search_paths(schemas: &[Utf8PathBuf], includes: &[Utf8PathBuf]) -> Vec<Utf8PathBuf> {
+    let mut search_paths = includes.to_vec();
+
+    for parent in schemas.iter().filter_map(|schema| schema.parent()) {
+        if !search_paths.iter().any(|path| path == parent) {
+            search_paths.push(parent.to_owned());
+        }
+    }
+
+    search_paths
+}
+
+/// Expands directories into the `.proto` files they contain, recursively and in sorted order.
+fn expand_schema_paths(
+    paths: &[Utf8PathBuf],
+) -> std::result::Result<Vec<Utf8PathBuf>, Report<Inspect>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            let before = files.len();
+            collect_proto_files(path, &mut files)?;
+
+            if files.len() == before {
+                return Err(Report::new(Inspect)
+                    .attach(format!("Schema directory contains no .proto files: {path}")));
+            }
+        } else if !files.contains(path) {
+            files.push(path.clone());
+        }
+    }
+
+    Ok(files)
+}
+
+fn collect_proto_files(
+    dir: &Utf8Path,
+    files: &mut Vec<Utf8PathBuf>,
+) -> std::result::Result<(), Report<Inspect>> {
+    let mut entries = dir
+        .read_dir_utf8()
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .attach_with(|| format!("Schema directory: {dir}"))
+        .change_context(Inspect)?;
+    entries.sort_by(|left, right| left.file_name().cmp(right.file_name()));
+
+    for entry in entries {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_proto_files(path, files)?;
+        } else if path.extension() == Some("proto") && !files.iter().any(|file| file == path) {
+            files.push(path.to_owned());
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn load_inspector(
-    schema: &Utf8Path,
+    schema: &SchemaSource,
     message: Option<&str>,
+    embedded: &[EmbeddedMessage],
+    raw_input: &[u8],
+    input_format: InputFormat,
+) -> std::result::Result<Inspector, Report<Inspect>> {
+    load_message_selection(schema, message, embedded)?.parse(raw_input, input_format)
+}
+
+/// The message type payloads are parsed as: either resolved up front, or detected separately for
+/// every payload with `--message auto`.
+#[derive(Debug, Clone)]
+pub(crate) enum MessageSelection {
+    Fixed(MessageDescriptor, TypeRegistry),
+    /// `--embed` retyped `bytes` fields of `plain` as messages. A binary payload whose embedded
+    /// bytes do not decode is parsed as `plain` instead, keeping those fields as bytes.
+    Embedded {
+        md: MessageDescriptor,
+        registry: TypeRegistry,
+        plain: MessageDescriptor,
+        plain_registry: TypeRegistry,
+        /// The `--embed` arguments, for the note on payloads that fall back to `plain`.
+        embedded: String,
+    },
+    Auto(Schema),
+}
+
+impl MessageSelection {
+    pub(crate) fn parse(
+        &self,
+        raw_input: &[u8],
+        input_format: InputFormat,
+    ) -> std::result::Result<Inspector, Report<Inspect>> {
+        match self {
+            Self::Fixed(md, registry) => {
+                parse_inspector(md.clone(), registry.clone(), raw_input, input_format)
+            }
+            Self::Embedded {
+                md,
+                registry,
+                plain,
+                plain_registry,
+                embedded,
+            } => parse_inspector(md.clone(), registry.clone(), raw_input, input_format).or_else(
+                |error| {
+                    if matches!(input_format, InputFormat::Json | InputFormat::Text) {
+                        return Err(error);
+                    }
+                    let inspector = parse_inspector(
+                        plain.clone(),
+                        plain_registry.clone(),
+                        raw_input,
+                        input_format,
+                    )
+                    .map_err(|_| error)?;
+                    Ok(inspector.with_embed_note(format!(
+                        "Kept the `--embed {embedded}` fields as bytes because they do not decode \
+                         as their messages"
+                    )))
+                },
+            ),
+            Self::Auto(_) if matches!(input_format, InputFormat::Json | InputFormat::Text) => {
+                Err(Report::new(Inspect)
+                    .attach("`--message auto` requires a binary, hex, or base64 payload"))
+            }
+            Self::Auto(schema) => {
+                let decoded = decode::decode_input(raw_input, input_format)?;
+                let candidates = detect_message(schema, &decoded)?;
+                let md = candidates[0].descriptor.clone();
+                Ok(
+                    parse_inspector(md, schema.type_registry(), &decoded, InputFormat::Binary)?
+                        .with_detected_candidates(candidates),
+                )
+            }
+        }
+    }
+}
+
+pub(crate) fn load_message_selection(
+    schema: &SchemaSource,
+    message: Option<&str>,
+    embedded: &[EmbeddedMessage],
+) -> std::result::Result<MessageSelection, Report<Inspect>> {
+    if message == Some(AUTO_MESSAGE) {
+        if !embedded.is_empty() {
+            return Err(Report::new(Inspect)
+                .attach("`--embed` requires `--message` to name the top-level message type"));
+        }
+        return Ok(MessageSelection::Auto(load_schema(schema)?));
+    }
+
+    let loaded = load_schema(schema)?;
+    let md = select_message(&loaded, message)
+        .attach_with(|| format!("Schema file: {schema}"))
+        .change_context(Inspect)?;
+    if embedded.is_empty() {
+        return Ok(MessageSelection::Fixed(md, loaded.type_registry()));
+    }
+    let plain = md.clone();
+    let plain_registry = loaded.type_registry();
+
+    embedded
+        .iter()
+        .try_fold((loaded, md), |(loaded, md), embedded| {
+            embed_message(loaded, &md, embedded).attach_with(|| {
+                format!(
+                    "Embedded message: {}={}",
+                    embedded.field_path, embedded.message
+                )
+            })
+        })
+        .map(|(loaded, md)| MessageSelection::Embedded {
+            md,
+            registry: loaded.type_registry(),
+            plain,
+            plain_registry,
+            embedded: embedded
+                .iter()
+                .map(|embedded| format!("{}={}", embedded.field_path, embedded.message))
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+}
+
+/// Rebuilds the schema with the `bytes` field at `embedded.field_path` retyped as the embedded
+/// message, and returns it with `root` resolved again. Both encode as length-delimited fields, so
+/// payloads parse the same while the field's contents are decoded, shown, and re-encoded as a
+/// message. The field is retyped on the message that declares it, so it is decoded under every
+/// path to that message, not only `embedded.field_path`.
+fn embed_message(
+    schema: Schema,
+    root: &MessageDescriptor,
+    embedded: &EmbeddedMessage,
+) -> std::result::Result<(Schema, MessageDescriptor), Report<Inspect>> {
+    let field = embedded_field(root, &embedded.field_path)?;
+    let target = message_by_name(&schema, &embedded.message)
+        .change_context(Inspect)?
+        .ok_or_else(|| {
+            Report::new(Inspect).attach(format!(
+                "Available messages: {}",
+                available_message_names(&schema).join(", ")
+            ))
+        })?;
+
+    let container = field.containing_message();
+    let container_file = container.file_descriptor().name().to_owned();
+    let target_file = target.file_descriptor().name().to_owned();
+    let mut protos = schema
+        .all_files
+        .iter()
+        .map(|fd| fd.proto().clone())
+        .collect::<Vec<_>>();
+    let file = protos
+        .iter_mut()
+        .find(|proto| proto.name() == container_file)
+        .expect("the field's file should be part of the schema it was resolved from");
+    let message = message_proto(file, container.full_name())
+        .expect("the field's message should be declared in its file");
+    let field_proto = message
+        .field
+        .iter_mut()
+        .find(|proto| proto.number() == field.number())
+        .expect("the field should be declared in its message");
+    field_proto.set_type(Type::TYPE_MESSAGE);
+    field_proto.set_type_name(format!(".{}", target.full_name()));
+    field_proto.clear_default_value();
+    if target_file != container_file && !file.dependency.contains(&target_file) {
+        file.dependency.push(target_file);
+    }
+
+    let names = schema
+        .files
+        .iter()
+        .map(|fd| fd.name().to_owned())
+        .collect::<Vec<_>>();
+    let all_files = build_file_descriptors(protos)?;
+    let files = all_files
+        .iter()
+        .filter(|fd| names.iter().any(|name| name == fd.name()))
+        .cloned()
+        .collect();
+    let schema = Schema { files, all_files };
+    let root = message_by_name(&schema, root.full_name())
+        .change_context(Inspect)?
+        .ok_or_else(|| {
+            Report::new(Inspect).attach(format!("Message disappeared: {}", root.full_name()))
+        })?;
+
+    Ok((schema, root))
+}
+
+/// Resolves a dotted path of message fields from `root`, ending at a singular or repeated `bytes`
+/// field.
+fn embedded_field(
+    root: &MessageDescriptor,
+    field_path: &str,
+) -> std::result::Result<FieldDescriptor, Report<Inspect>> {
+    let mut message = root.clone();
+    let mut segments = field_path.split('.').peekable();
+
+    while let Some(name) = segments.next() {
+        let field = message.field_by_name_or_json_name(name).ok_or_else(|| {
+            Report::new(Inspect).attach(format!(
+                "Message {} has no field named {name}",
+                message.full_name()
+            ))
+        })?;
+
+        if segments.peek().is_none() {
+            if field.proto().type_() != Type::TYPE_BYTES {
+                return Err(Report::new(Inspect).attach(format!(
+                    "Only bytes fields can hold an embedded message, but {} is not one",
+                    field.full_name()
+                )));
+            }
+            return Ok(field);
+        }
+
+        message = match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(md))
+            | RuntimeFieldType::Repeated(RuntimeType::Message(md)) => md,
+            _ => {
+                return Err(Report::new(Inspect).attach(format!(
+                    "{} is not a message field, so {field_path} cannot go through it",
+                    field.full_name()
+                )));
+            }
+        };
+    }
+
+    Err(Report::new(Inspect).attach("The embedded field path is empty"))
+}
+
+/// The declaration of the message named `full_name` in `file`, looking through nested messages.
+fn message_proto<'a>(
+    file: &'a mut FileDescriptorProto,
+    full_name: &str,
+) -> Option<&'a mut DescriptorProto> {
+    let relative_name = if file.package().is_empty() {
+        full_name
+    } else {
+        full_name.strip_prefix(file.package())?.strip_prefix('.')?
+    };
+    let mut names = relative_name.split('.');
+    let first = names.next()?;
+    let mut message = file.message_type.iter_mut().find(|md| md.name() == first)?;
+
+    for name in names {
+        message = message
+            .nested_type
+            .iter_mut()
+            .find(|md| md.name() == name)?;
+    }
+
+    Some(message)
+}
+
+/// Ranks every message in the schema by how well it explains `bytes` and returns those that
+/// parse, best match first, so the first is the detected type.
+fn detect_message(
+    schema: &Schema,
+    bytes: &[u8],
+) -> std::result::Result<Vec<detect::MessageCandidate>, Report<Inspect>> {
+    let mut ranked = detect::rank_messages(available_messages(schema), bytes);
+
+    match ranked.first() {
+        Some(best) if !best.score.parse_failed => {
+            ranked.retain(|candidate| !candidate.score.parse_failed);
+            Ok(ranked)
+        }
+        Some(_) => {
+            let mut report = Report::new(NoMatchingMessage);
+            for candidate in &ranked {
+                report = report.attach(format!("Candidate: {}", candidate.summary()));
+            }
+            Err(report.change_context(Inspect))
+        }
+        None => Err(NoTopLevelMessages).change_context(Inspect),
+    }
+}
+
+pub(crate) fn parse_inspector(
+    md: MessageDescriptor,
+    registry: TypeRegistry,
     raw_input: &[u8],
     input_format: InputFormat,
 ) -> std::result::Result<Inspector, Report<Inspect>> {
-    let md = load_message_descriptor(schema, message)?;
     let msg = match input_format {
         InputFormat::Json => {
-            protobuf_json_mapping::parse_dyn_from_str(&md, decode::json_input_as_text(raw_input)?)
-                .attach_with(|| format!("Message type: {}", md.name_to_package()))
+            let json = any::pack_json(&registry, &md, decode::json_input_as_text(raw_input)?)
+                .map_err(|error| Report::new(Inspect).attach(error))?;
+            protobuf_json_mapping::parse_dyn_from_str(&md, &json)
+                .attach_with(|| format!("Message type: {}", md.full_name()))
                 .change_context(Inspect)?
         }
+        InputFormat::Text => {
+            let mut msg = md.new_instance();
+            text_format::merge_from_str(&mut *msg, decode::text_format_input_as_text(raw_input)?)
+                .attach("Input format: text")
+                .attach_with(|| format!("Message type: {}", md.full_name()))
+                .change_context(Inspect)?;
+            msg
+        }
         _ => {
             let decoded = decode::decode_input(raw_input, input_format)?;
             md.parse_from_bytes(&decoded)
-                .attach_with(|| format!("Message type: {}", md.name_to_package()))
+                .attach_with(|| format!("Message type: {}", md.full_name()))
                 .change_context(Inspect)?
         }
     };
 
-    Ok(Inspector::new(md, msg))
+    Ok(Inspector::new(md, msg, registry))
+}
+
+pub(crate) fn available_message_names(schema: &Schema) -> Vec<String> {
+    available_messages(schema)
+        .iter()
+        .map(|md| md.full_name().to_owned())
+        .collect()
 }
 
-pub(crate) fn available_message_names(fd: &FileDescriptor) -> Vec<String> {
-    let mut names = Vec::new();
+/// Every message declared in the requested schema files, including nested messages, sorted by
+/// full name.
+fn available_messages(schema: &Schema) -> Vec<MessageDescriptor> {
+    let mut messages = Vec::new();
 
-    for message in fd.messages() {
-        collect_message_names(message, &mut names);
+    for fd in &schema.files {
+        for message in fd.messages() {
+            collect_messages(message, &mut messages);
+        }
     }
 
-    names.sort();
-    names
+    messages.sort_by(|left, right| left.full_name().cmp(right.full_name()));
+    messages.dedup_by(|left, right| left.full_name() == right.full_name());
+    messages
 }
 
-fn collect_message_names(message: MessageDescriptor, names: &mut Vec<String>) {
-    names.push(message.name_to_package().to_owned());
+fn collect_messages(message: MessageDescriptor, messages: &mut Vec<MessageDescriptor>) {
+    // Synthesized map entry types cannot be instantiated on their own.
+    if message.is_map_entry() {
+        return;
+    }
+
+    messages.push(message.clone());
 
     for nested in message.nested_messages() {
-        collect_message_names(nested, names);
+        collect_messages(nested, messages);
     }
 }
 
-pub(crate) fn load_file_descriptor(
-    schema: &Utf8Path,
-) -> std::result::Result<FileDescriptor, Report<Inspect>> {
-    let fds = protobuf_parse::Parser::new()
-        .pure()
-        .includes(schema.parent().as_slice())
-        .input(schema)
-        .parse_and_typecheck()
-        .into_report()
-        .attach_with(|| format!("Schema file: {schema}"))
-        .change_context(Inspect)?;
+pub(crate) fn load_schema(schema: &SchemaSource) -> std::result::Result<Schema, Report<Inspect>> {
+    match schema {
+        SchemaSource::Proto { paths, includes } => load_proto_schema(paths, includes),
+        SchemaSource::DescriptorSet(path) => load_descriptor_set(path),
+    }
+}
 
-    let descriptors = build_file_descriptors(fds.file_descriptors)?;
-    let schema_name = schema.file_name().unwrap_or(schema.as_str());
+fn load_proto_schema(
+    paths: &[Utf8PathBuf],
+    includes: &[Utf8PathBuf],
+) -> std::result::Result<Schema, Report<Inspect>> {
+    let schemas = expand_schema_paths(paths)?;
+    let mut includes = includes.to_vec();
+    includes.extend(paths.iter().filter(|path| path.is_dir()).cloned());
 
-    descriptors
+    let parsed = parse_schema(&schemas, &includes).change_context(Inspect)?;
+    let schema_names = parsed
+        .relative_paths
         .iter()
-        .find(|fd| fd.name() == schema_name)
+        .map(|path| path.to_str().to_owned())
+        .collect::<Vec<_>>();
+    let all_files = build_file_descriptors(parsed.file_descriptors)?;
+    let files = all_files
+        .iter()
+        .filter(|fd| schema_names.iter().any(|name| name == fd.name()))
         .cloned()
-        .or_else(|| descriptors.last().cloned())
-        .ok_or_else(|| {
-            Report::new(Inspect).attach(format!(
-                "No file descriptors resolved from schema: {schema}"
-            ))
-        })
+        .collect::<Vec<_>>();
+
+    if files.is_empty() {
+        return Err(Report::new(Inspect).attach(format!(
+            "No file descriptors resolved from schema: {}",
+            schema_names.join(", ")
+        )));
+    }
+
+    Ok(Schema { files, all_files })
+}
+
+/// Loads a binary `FileDescriptorSet`. Files that no other file in the set imports are treated as
+/// the requested schemas; the rest are only resolvable by fully qualified name.
+fn load_descriptor_set(path: &Utf8Path) -> std::result::Result<Schema, Report<Inspect>> {
+    let bytes = fs::read(path)
+        .attach_with(|| format!("Descriptor set file: {path}"))
+        .change_context(Inspect)?;
+    let set = FileDescriptorSet::parse_from_bytes(&bytes)
+        .attach_with(|| format!("Descriptor set file: {path}"))
+        .change_context(Inspect)?;
+    let imported = set
+        .file
+        .iter()
+        .flat_map(|proto| proto.dependency.iter().cloned())
+        .collect::<HashSet<_>>();
+
+    let all_files = build_file_descriptors(set.file)
+        .attach_with(|| format!("Descriptor set file: {path}"))
+        .attach("Descriptor sets must include their imports, e.g. `protoc --include_imports`")?;
+    let files = all_files
+        .iter()
+        .filter(|fd| !imported.contains(fd.name()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if files.is_empty() {
+        return Err(
+            Report::new(Inspect).attach(format!("Descriptor set file contains no files: {path}"))
+        );
+    }
+
+    Ok(Schema { files, all_files })
 }
 
 fn build_file_descriptors(
@@ -159,13 +677,13 @@ fn build_file_descriptors(
 }
 
 pub(crate) fn select_message(
-    fd: &FileDescriptor,
+    schema: &Schema,
     message: Option<&str>,
 ) -> std::result::Result<MessageDescriptor, Report<InvalidSchema>> {
-    let names = available_message_names(fd);
+    let names = available_message_names(schema);
 
     match message {
-        Some(name) => fd.message_by_package_relative_name(name).ok_or_else(|| {
+        Some(name) => message_by_name(schema, name)?.ok_or_else(|| {
             Report::new(InvalidSchema)
                 .attach(format!("Requested message: {name}"))
                 .attach(format!("Available messages: {}", names.join(", ")))
@@ -173,7 +691,7 @@ pub(crate) fn select_message(
         None if names.is_empty() => Err(NoTopLevelMessages).change_context(InvalidSchema),
         None if names.len() == 1 => {
             let name = &names[0];
-            fd.message_by_package_relative_name(name).ok_or_else(|| {
+            message_by_name(schema, name)?.ok_or_else(|| {
                 Report::new(InvalidSchema).attach(format!("Resolved message disappeared: {name}"))
             })
         }
@@ -183,12 +701,46 @@ pub(crate) fn select_message(
     }
 }
 
-fn load_message_descriptor(
-    schema: &Utf8Path,
-    message: Option<&str>,
-) -> std::result::Result<MessageDescriptor, Report<Inspect>> {
-    let fd = load_file_descriptor(schema)?;
-    select_message(&fd, message)
-        .attach_with(|| format!("Schema file: {schema}"))
-        .change_context(Inspect)
+/// Resolves a fully qualified message name against every loaded file, falling back to names
+/// relative to the package of each requested schema file.
+fn message_by_name(
+    schema: &Schema,
+    name: &str,
+) -> std::result::Result<Option<MessageDescriptor>, Report<InvalidSchema>> {
+    let full_name = name.strip_prefix('.').unwrap_or(name);
+
+    for fd in &schema.all_files {
+        let relative_name = if fd.package().is_empty() {
+            Some(full_name)
+        } else {
+            full_name
+                .strip_prefix(fd.package())
+                .and_then(|rest| rest.strip_prefix('.'))
+        };
+
+        if let Some(md) = relative_name.and_then(|name| fd.message_by_package_relative_name(name)) {
+            return Ok(Some(md));
+        }
+    }
+
+    let mut matches = schema
+        .files
+        .iter()
+        .filter_map(|fd| fd.message_by_package_relative_name(name))
+        .collect::<Vec<_>>();
+
+    if matches.len() > 1 {
+        return Err(Report::new(AmbiguousMessageName)
+            .attach(format!(
+                "Matching messages: {}",
+                matches
+                    .iter()
+                    .map(|md| md.full_name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .change_context(InvalidSchema));
+    }
+
+    Ok(matches.pop())
 }
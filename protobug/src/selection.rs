@@ -1,5 +1,7 @@
 use std::{
+    cmp::Reverse,
     collections::{BTreeSet, HashMap},
+    fmt::Write as _,
     ops::Range,
 };
 
@@ -11,12 +13,19 @@ use protobuf::{
     },
 };
 
+use crate::{
+    any::{self, TypeRegistry},
+    well_known,
+};
+
 pub(crate) type FieldPath = Vec<FieldPathSegment>;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum FieldPathSegment {
     Field(String),
     Index(usize),
+    /// An entry of a map field, by its key as [`format_value`] shows it, e.g. `"env"` or `7`.
+    Key(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -25,7 +34,52 @@ pub(crate) struct ProtobufLine {
     pub(crate) text: String,
 }
 
+/// A line of the Protobuf pane, or a run of them that can be folded away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProtobufNode {
+    Line(ProtobufLine),
+    /// A message from its `name {` line to its closing `}`.
+    Message {
+        open: ProtobufLine,
+        children: Vec<ProtobufNode>,
+        close: ProtobufLine,
+    },
+    /// Every element of a repeated field, or every entry of a map field.
+    Elements {
+        path: FieldPath,
+        /// The indented field name.
+        text: String,
+        map: bool,
+        elements: Vec<ProtobufNode>,
+    },
+}
+
+impl ProtobufNode {
+    pub(crate) fn flatten_into(&self, lines: &mut Vec<ProtobufLine>) {
+        match self {
+            Self::Line(line) => lines.push(line.clone()),
+            Self::Message {
+                open,
+                children,
+                close,
+            } => {
+                lines.push(open.clone());
+                for child in children {
+                    child.flatten_into(lines);
+                }
+                lines.push(close.clone());
+            }
+            Self::Elements { elements, .. } => {
+                for element in elements {
+                    element.flatten_into(lines);
+                }
+            }
+        }
+    }
+}
+
 pub(crate) fn selected_path_for_json_cursor(
+    registry: &TypeRegistry,
     descriptor: &MessageDescriptor,
     json: &str,
     cursor: (usize, usize),
@@ -33,37 +87,193 @@ pub(crate) fn selected_path_for_json_cursor(
     let offset = byte_offset_for_cursor(json, cursor)?;
     let root = JsonParser::new(json).parse().ok()?;
     let raw_path = root.path_at(offset)?;
-    normalize_path(descriptor, &raw_path)
+    normalize_path(descriptor, &raw_path, |any_path| {
+        root.packed_type(registry, json, any_path)
+    })
+}
+
+/// Where to put the JSON cursor to select `path`: at the key or array element of the deepest
+/// field along `path` that is shown in `json`.
+pub(crate) fn json_cursor_for_path(
+    registry: &TypeRegistry,
+    descriptor: &MessageDescriptor,
+    json: &str,
+    path: &[FieldPathSegment],
+) -> Option<(usize, usize)> {
+    let root = JsonParser::new(json).parse().ok()?;
+    let mut targets = Vec::new();
+    root.collect_targets(&mut Vec::new(), &mut targets);
+
+    let (_, offset) = targets
+        .into_iter()
+        .filter_map(|(raw_path, offset)| {
+            let normalized = normalize_path(descriptor, &raw_path, |any_path| {
+                root.packed_type(registry, json, any_path)
+            })?;
+            path_is_prefix(&normalized, path).then_some((normalized.len(), offset))
+        })
+        .min_by_key(|&(depth, offset)| (Reverse(depth), offset))?;
+
+    Some(cursor_for_byte_offset(json, offset))
 }
 
+/// The path of the innermost field whose encoding contains the byte at `byte_index`.
+pub(crate) fn path_for_byte(
+    registry: &TypeRegistry,
+    descriptor: &MessageDescriptor,
+    bytes: &[u8],
+    byte_index: usize,
+) -> Option<FieldPath> {
+    let mut occurrences = Vec::new();
+    collect_occurrences(
+        registry,
+        descriptor,
+        bytes,
+        0,
+        &mut Vec::new(),
+        &mut occurrences,
+    );
+
+    occurrences
+        .into_iter()
+        .filter(|occurrence| occurrence.range.contains(&byte_index))
+        .max_by_key(|occurrence| occurrence.path.len())
+        .map(|occurrence| occurrence.path)
+}
+
+/// Renders `path` the way `diff` does, e.g. `services[1].name` or `labels["env"]`.
+pub(crate) fn display_path(path: &[FieldPathSegment]) -> String {
+    let mut display = String::new();
+
+    for segment in path {
+        match segment {
+            FieldPathSegment::Field(name) => {
+                if !display.is_empty() {
+                    display.push('.');
+                }
+                display.push_str(name);
+            }
+            FieldPathSegment::Index(index) => {
+                let _ = write!(display, "[{index}]");
+            }
+            FieldPathSegment::Key(key) => {
+                let _ = write!(display, "[{key}]");
+            }
+        }
+    }
+
+    display
+}
+
+/// The message laid out like the text format, one field per line, with well-known types such as
+/// timestamps shown on a single line. Timestamps use the local offset when `local_time` is set and
+/// UTC otherwise.
 pub(crate) fn protobuf_lines(
+    registry: &TypeRegistry,
+    local_time: bool,
     descriptor: &MessageDescriptor,
     message: &dyn MessageDyn,
 ) -> Vec<ProtobufLine> {
     let mut lines = Vec::new();
-    render_message(descriptor, message, &mut Vec::new(), 0, &mut lines);
+    for node in protobuf_nodes(registry, local_time, descriptor, message) {
+        node.flatten_into(&mut lines);
+    }
     lines
 }
 
+/// The lines of [`protobuf_lines`] grouped into the messages, repeated fields, and maps they
+/// belong to.
+pub(crate) fn protobuf_nodes(
+    registry: &TypeRegistry,
+    local_time: bool,
+    descriptor: &MessageDescriptor,
+    message: &dyn MessageDyn,
+) -> Vec<ProtobufNode> {
+    let mut nodes = Vec::new();
+    render_message(
+        registry,
+        local_time,
+        descriptor,
+        message,
+        &mut Vec::new(),
+        0,
+        &mut nodes,
+    );
+    nodes
+}
+
+/// Returns the bytes of the fields under any of `selected_paths`, decoding `bytes` once however
+/// many paths there are. A path to an element of a packed field, which has no bytes of its own,
+/// marks the whole field.
 pub(crate) fn highlighted_byte_indices(
+    registry: &TypeRegistry,
     descriptor: &MessageDescriptor,
     bytes: &[u8],
-    selected_path: &[FieldPathSegment],
+    selected_paths: &[&[FieldPathSegment]],
 ) -> BTreeSet<usize> {
     let mut occurrences = Vec::new();
-    collect_occurrences(descriptor, bytes, 0, &mut Vec::new(), &mut occurrences);
+    collect_occurrences(
+        registry,
+        descriptor,
+        bytes,
+        0,
+        &mut Vec::new(),
+        &mut occurrences,
+    );
 
     let mut highlighted = BTreeSet::new();
 
-    for occurrence in occurrences {
-        if path_is_prefix(selected_path, &occurrence.path) {
-            highlighted.extend(occurrence.range);
+    for selected_path in selected_paths {
+        let mut matched = false;
+        for occurrence in &occurrences {
+            if path_is_prefix(selected_path, &occurrence.path) {
+                highlighted.extend(occurrence.range.clone());
+                matched = true;
+            }
+        }
+
+        if !matched && let Some((FieldPathSegment::Index(_), parent)) = selected_path.split_last() {
+            for occurrence in &occurrences {
+                if path_is_prefix(parent, &occurrence.path) {
+                    highlighted.extend(occurrence.range.clone());
+                }
+            }
         }
     }
 
     highlighted
 }
 
+/// The encoded occurrences of the field at `selected_path`, or of each element or entry when it
+/// names a whole repeated or map field.
+pub(crate) fn field_occurrences(
+    registry: &TypeRegistry,
+    descriptor: &MessageDescriptor,
+    bytes: &[u8],
+    selected_path: &[FieldPathSegment],
+) -> Vec<FieldOccurrence> {
+    let mut occurrences = Vec::new();
+    collect_occurrences(
+        registry,
+        descriptor,
+        bytes,
+        0,
+        &mut Vec::new(),
+        &mut occurrences,
+    );
+
+    occurrences.retain(|occurrence| {
+        occurrence.path == selected_path
+            || (occurrence.path.len() == selected_path.len() + 1
+                && path_is_prefix(selected_path, &occurrence.path)
+                && matches!(
+                    occurrence.path.last(),
+                    Some(FieldPathSegment::Index(_) | FieldPathSegment::Key(_))
+                ))
+    });
+    occurrences
+}
+
 pub(crate) fn related_path(selected: &[FieldPathSegment], candidate: &[FieldPathSegment]) -> bool {
     path_is_prefix(selected, candidate) || path_is_prefix(candidate, selected)
 }
@@ -72,17 +282,41 @@ fn path_is_prefix(prefix: &[FieldPathSegment], path: &[FieldPathSegment]) -> boo
     prefix.len() <= path.len() && prefix.iter().zip(path).all(|(left, right)| left == right)
 }
 
+/// Maps a path of JSON keys and array indices onto proto field names. Keys of an expanded
+/// `google.protobuf.Any` resolve within the type `packed_type` finds for the JSON path of the
+/// `Any`, below its `value` field, and keys of a map field name its entries.
 fn normalize_path(
     descriptor: &MessageDescriptor,
     raw_path: &[FieldPathSegment],
+    packed_type: impl Fn(&[FieldPathSegment]) -> Option<MessageDescriptor>,
 ) -> Option<FieldPath> {
     let mut normalized = Vec::new();
     let mut current = descriptor.clone();
     let mut current_field: Option<FieldDescriptor> = None;
 
-    for segment in raw_path {
+    for (depth, segment) in raw_path.iter().enumerate() {
         match segment {
             FieldPathSegment::Field(name) => {
+                if let Some(RuntimeFieldType::Map(key_type, _)) = current_field
+                    .as_ref()
+                    .map(FieldDescriptor::runtime_field_type)
+                {
+                    normalized.push(FieldPathSegment::Key(json_map_key(&key_type, name)));
+                    current_field = None;
+                    continue;
+                }
+
+                if any::is_any(&current) {
+                    if name == any::TYPE_KEY {
+                        normalized.push(FieldPathSegment::Field("type_url".to_owned()));
+                        break;
+                    }
+                    if let Some(packed) = packed_type(&raw_path[..depth]) {
+                        normalized.push(FieldPathSegment::Field("value".to_owned()));
+                        current = packed;
+                    }
+                }
+
                 let field = match current.field_by_name_or_json_name(name) {
                     Some(field) => field,
                     None if normalized.is_empty() => return None,
@@ -115,6 +349,7 @@ fn normalize_path(
                     break;
                 }
             }
+            FieldPathSegment::Key(_) => break,
         }
     }
 
@@ -125,151 +360,263 @@ fn normalize_path(
     }
 }
 
+/// A JSON object key of a map field as [`format_value`] shows the key it stands for.
+fn json_map_key(key_type: &RuntimeType, key: &str) -> String {
+    match key_type {
+        RuntimeType::String => format!("{key:?}"),
+        _ => key.to_owned(),
+    }
+}
+
 fn render_message(
+    registry: &TypeRegistry,
+    local_time: bool,
     descriptor: &MessageDescriptor,
     message: &dyn MessageDyn,
     parent_path: &mut FieldPath,
     indent: usize,
-    lines: &mut Vec<ProtobufLine>,
+    nodes: &mut Vec<ProtobufNode>,
 ) {
+    // Like the text format, an `Any` whose type is loaded shows the message it packs under its
+    // type URL.
+    if let Some((type_url, packed)) = registry.unpack(descriptor, message) {
+        let mut value_path = parent_path.clone();
+        value_path.push(FieldPathSegment::Field("value".to_owned()));
+
+        let mut children = Vec::new();
+        render_message(
+            registry,
+            local_time,
+            &packed.descriptor_dyn(),
+            &*packed,
+            &mut value_path.clone(),
+            indent + 2,
+            &mut children,
+        );
+        nodes.push(ProtobufNode::Message {
+            open: ProtobufLine {
+                path: value_path.clone(),
+                text: format!("{}[{type_url}] {{", " ".repeat(indent)),
+            },
+            children,
+            close: ProtobufLine {
+                path: value_path,
+                text: format!("{}}}", " ".repeat(indent)),
+            },
+        });
+        return;
+    }
+
     for field in descriptor.fields() {
+        let mut field_path = parent_path.clone();
+        field_path.push(FieldPathSegment::Field(field.name().to_owned()));
+
         match field.get_reflect(message) {
             ReflectFieldRef::Optional(optional) => {
                 let Some(value) = optional.value() else {
                     continue;
                 };
 
-                let mut field_path = parent_path.clone();
-                field_path.push(FieldPathSegment::Field(field.name().to_owned()));
-                render_value(field.name(), value, &field_path, indent, lines);
+                render_value(
+                    registry,
+                    local_time,
+                    field.name(),
+                    value,
+                    &field_path,
+                    indent,
+                    nodes,
+                );
             }
             ReflectFieldRef::Repeated(repeated) => {
+                if repeated.is_empty() {
+                    continue;
+                }
+
+                let mut elements = Vec::new();
                 for index in 0..repeated.len() {
-                    let mut field_path = parent_path.clone();
-                    field_path.push(FieldPathSegment::Field(field.name().to_owned()));
-                    field_path.push(FieldPathSegment::Index(index));
+                    let mut element_path = field_path.clone();
+                    element_path.push(FieldPathSegment::Index(index));
                     render_value(
+                        registry,
+                        local_time,
                         field.name(),
                         repeated.get(index),
-                        &field_path,
+                        &element_path,
                         indent,
-                        lines,
+                        &mut elements,
                     );
                 }
+                nodes.push(ProtobufNode::Elements {
+                    path: field_path,
+                    text: format!("{}{}", " ".repeat(indent), field.name()),
+                    map: false,
+                    elements,
+                });
             }
             ReflectFieldRef::Map(map) => {
+                if map.is_empty() {
+                    continue;
+                }
+
+                let mut entries = Vec::new();
                 for (key, value) in &map {
-                    let field_path = parent_path
-                        .iter()
-                        .cloned()
-                        .chain([FieldPathSegment::Field(field.name().to_owned())])
-                        .collect::<Vec<_>>();
-
-                    lines.push(ProtobufLine {
-                        path: field_path.clone(),
-                        text: format!("{}{} {{", " ".repeat(indent), field.name()),
-                    });
-                    lines.push(ProtobufLine {
-                        path: field_path.clone(),
+                    let mut entry_path = field_path.clone();
+                    entry_path.push(FieldPathSegment::Key(format_value(key.clone())));
+
+                    let mut children = vec![ProtobufNode::Line(ProtobufLine {
+                        path: entry_path.clone(),
                         text: format!("{}key: {}", " ".repeat(indent + 2), format_value(key)),
-                    });
+                    })];
 
+                    let formatted = well_known_text(&value, local_time);
                     match value {
-                        ReflectValueRef::Message(message) => {
-                            lines.push(ProtobufLine {
-                                path: field_path.clone(),
-                                text: format!("{}value {{", " ".repeat(indent + 2)),
-                            });
+                        ReflectValueRef::Message(message) if formatted.is_none() => {
+                            let mut value_children = Vec::new();
                             render_message(
+                                registry,
+                                local_time,
                                 &message.descriptor_dyn(),
                                 &*message,
-                                &mut field_path.clone(),
+                                &mut entry_path.clone(),
                                 indent + 4,
-                                lines,
+                                &mut value_children,
                             );
-                            lines.push(ProtobufLine {
-                                path: field_path.clone(),
-                                text: format!("{}}}", " ".repeat(indent + 2)),
+                            children.push(ProtobufNode::Message {
+                                open: ProtobufLine {
+                                    path: entry_path.clone(),
+                                    text: format!("{}value {{", " ".repeat(indent + 2)),
+                                },
+                                children: value_children,
+                                close: ProtobufLine {
+                                    path: entry_path.clone(),
+                                    text: format!("{}}}", " ".repeat(indent + 2)),
+                                },
                             });
                         }
-                        _ => lines.push(ProtobufLine {
-                            path: field_path.clone(),
+                        _ => children.push(ProtobufNode::Line(ProtobufLine {
+                            path: entry_path.clone(),
                             text: format!(
                                 "{}value: {}",
                                 " ".repeat(indent + 2),
-                                format_value(value)
+                                formatted.unwrap_or_else(|| format_value(value))
                             ),
-                        }),
+                        })),
                     }
 
-                    lines.push(ProtobufLine {
-                        path: field_path,
-                        text: format!("{}}}", " ".repeat(indent)),
+                    entries.push(ProtobufNode::Message {
+                        open: ProtobufLine {
+                            path: entry_path.clone(),
+                            text: format!("{}{} {{", " ".repeat(indent), field.name()),
+                        },
+                        children,
+                        close: ProtobufLine {
+                            path: entry_path.clone(),
+                            text: format!("{}}}", " ".repeat(indent)),
+                        },
                     });
                 }
+                nodes.push(ProtobufNode::Elements {
+                    path: field_path,
+                    text: format!("{}{}", " ".repeat(indent), field.name()),
+                    map: true,
+                    elements: entries,
+                });
             }
         }
     }
 }
 
 fn render_value(
+    registry: &TypeRegistry,
+    local_time: bool,
     field_name: &str,
     value: ReflectValueRef<'_>,
     path: &[FieldPathSegment],
     indent: usize,
-    lines: &mut Vec<ProtobufLine>,
+    nodes: &mut Vec<ProtobufNode>,
 ) {
+    let formatted = well_known_text(&value, local_time);
     match value {
-        ReflectValueRef::Message(message) => {
-            lines.push(ProtobufLine {
-                path: path.to_vec(),
-                text: format!("{}{} {{", " ".repeat(indent), field_name),
-            });
+        ReflectValueRef::Message(message) if formatted.is_none() => {
+            let mut children = Vec::new();
             render_message(
+                registry,
+                local_time,
                 &message.descriptor_dyn(),
                 &*message,
                 &mut path.to_vec(),
                 indent + 2,
-                lines,
+                &mut children,
             );
-            lines.push(ProtobufLine {
-                path: path.to_vec(),
-                text: format!("{}}}", " ".repeat(indent)),
+            nodes.push(ProtobufNode::Message {
+                open: ProtobufLine {
+                    path: path.to_vec(),
+                    text: format!("{}{} {{", " ".repeat(indent), field_name),
+                },
+                children,
+                close: ProtobufLine {
+                    path: path.to_vec(),
+                    text: format!("{}}}", " ".repeat(indent)),
+                },
             });
         }
-        _ => lines.push(ProtobufLine {
+        _ => nodes.push(ProtobufNode::Line(ProtobufLine {
             path: path.to_vec(),
             text: format!(
                 "{}{}: {}",
                 " ".repeat(indent),
                 field_name,
-                format_value(value)
+                formatted.unwrap_or_else(|| format_value(value))
             ),
-        }),
+        })),
     }
 }
 
-fn format_value(value: ReflectValueRef<'_>) -> String {
+/// A message value shown on one line in the Protobuf pane, when it is a well-known type.
+fn well_known_text(value: &ReflectValueRef<'_>, local_time: bool) -> Option<String> {
+    match value {
+        ReflectValueRef::Message(message) => well_known::format(&**message, local_time),
+        _ => None,
+    }
+}
+
+/// A scalar as shown in the Protobuf pane and diffs; bytes are quoted with non-printable bytes
+/// escaped, like the text format.
+pub(crate) fn format_value(value: ReflectValueRef<'_>) -> String {
     match value {
         ReflectValueRef::String(value) => format!("{value:?}"),
-        ReflectValueRef::Bytes(value) => format!("{:?}", value),
+        ReflectValueRef::Bytes(value) => format!("\"{}\"", value.escape_ascii()),
         _ => value.to_string(),
     }
 }
 
-#[derive(Debug, Clone)]
-struct FieldOccurrence {
-    path: FieldPath,
-    range: Range<usize>,
+/// Where one field of a message sits in its encoding. Ranges are byte offsets into the
+/// top-level message.
+#[derive(Clone)]
+pub(crate) struct FieldOccurrence {
+    pub(crate) path: FieldPath,
+    pub(crate) field: FieldDescriptor,
+    pub(crate) wire_type: u8,
+    /// The whole field: tag, length prefix, and value.
+    pub(crate) range: Range<usize>,
+    pub(crate) tag: Range<usize>,
+    pub(crate) length_prefix: Option<Range<usize>>,
+    pub(crate) value: Range<usize>,
 }
 
 fn collect_occurrences(
+    registry: &TypeRegistry,
     descriptor: &MessageDescriptor,
     bytes: &[u8],
     base_offset: usize,
     parent_path: &mut FieldPath,
     occurrences: &mut Vec<FieldOccurrence>,
 ) {
+    let packed_type = if any::is_any(descriptor) {
+        registry.packed_type(bytes)
+    } else {
+        None
+    };
     let mut offset = 0;
     let mut repeated_indices = HashMap::<u32, usize>::new();
 
@@ -278,6 +625,7 @@ fn collect_occurrences(
         let Some(tag) = read_varint(bytes, &mut offset) else {
             break;
         };
+        let tag_range = (base_offset + field_start)..(base_offset + offset);
         let field_number = (tag >> 3) as u32;
         let wire_type = (tag & 0x07) as u8;
         let Some(field) = descriptor.field_by_number(field_number) else {
@@ -304,32 +652,64 @@ fn collect_occurrences(
             Some(ParsedField::Value(range)) => {
                 occurrences.push(FieldOccurrence {
                     path: occurrence_path,
+                    field: field.clone(),
+                    wire_type,
                     range: (base_offset + field_start)..(base_offset + range.end),
+                    tag: tag_range,
+                    length_prefix: None,
+                    value: (base_offset + range.start)..(base_offset + range.end),
                 });
             }
             Some(ParsedField::LengthDelimited {
                 full_range,
                 payload_range,
             }) => {
-                let payload = &bytes[payload_range.clone()];
+                let mut occurrence_path = occurrence_path;
+                let mut child_range = payload_range.clone();
+                let child = match field.runtime_field_type() {
+                    RuntimeFieldType::Singular(RuntimeType::Message(child))
+                    | RuntimeFieldType::Repeated(RuntimeType::Message(child)) => Some(child),
+                    // Each map entry is selected by its key, and holds its value in field 2.
+                    RuntimeFieldType::Map(_, value_type) => {
+                        if let Some(key) = map_key(descriptor, &field, &bytes[field_start..offset])
+                        {
+                            occurrence_path.push(FieldPathSegment::Key(key));
+                        }
+                        match (value_type, map_value_range(&bytes[payload_range.clone()])) {
+                            (RuntimeType::Message(child), Some(value)) => {
+                                child_range = (payload_range.start + value.start)
+                                    ..(payload_range.start + value.end);
+                                Some(child)
+                            }
+                            _ => None,
+                        }
+                    }
+                    // The value of an `Any` is encoded as the message its type URL names.
+                    _ if field_number == 2 => packed_type.clone(),
+                    _ => None,
+                };
 
                 occurrences.push(FieldOccurrence {
                     path: occurrence_path.clone(),
+                    field: field.clone(),
+                    wire_type,
                     range: (base_offset + field_start)..(base_offset + full_range.end),
+                    tag: tag_range,
+                    length_prefix: Some(
+                        (base_offset + full_range.start)..(base_offset + payload_range.start),
+                    ),
+                    value: (base_offset + payload_range.start)..(base_offset + payload_range.end),
                 });
 
-                match field.runtime_field_type() {
-                    RuntimeFieldType::Singular(RuntimeType::Message(child))
-                    | RuntimeFieldType::Repeated(RuntimeType::Message(child)) => {
-                        collect_occurrences(
-                            &child,
-                            payload,
-                            base_offset + payload_range.start,
-                            &mut occurrence_path.clone(),
-                            occurrences,
-                        );
-                    }
-                    _ => {}
+                if let Some(child) = child {
+                    collect_occurrences(
+                        registry,
+                        &child,
+                        &bytes[child_range.clone()],
+                        base_offset + child_range.start,
+                        &mut occurrence_path.clone(),
+                        occurrences,
+                    );
                 }
             }
             None => break,
@@ -337,7 +717,38 @@ fn collect_occurrences(
     }
 }
 
-enum ParsedField {
+/// The key of the one map entry encoded in `field_bytes`, as [`format_value`] shows it.
+fn map_key(
+    descriptor: &MessageDescriptor,
+    field: &FieldDescriptor,
+    field_bytes: &[u8],
+) -> Option<String> {
+    let mut message = descriptor.new_instance();
+    message.merge_from_bytes_dyn(field_bytes).ok()?;
+    let map = field.get_map(&*message);
+    let (key, _) = (&map).into_iter().next()?;
+    Some(format_value(key))
+}
+
+/// Where a map entry encodes a length-delimited value, relative to the entry.
+fn map_value_range(entry: &[u8]) -> Option<Range<usize>> {
+    let mut offset = 0;
+    let mut value = None;
+
+    while offset < entry.len() {
+        let tag = read_varint(entry, &mut offset)?;
+        let parsed = parse_field(entry, &mut offset, (tag & 0x07) as u8)?;
+        if tag >> 3 == 2
+            && let ParsedField::LengthDelimited { payload_range, .. } = parsed
+        {
+            value = Some(payload_range);
+        }
+    }
+
+    value
+}
+
+pub(crate) enum ParsedField {
     Value(Range<usize>),
     LengthDelimited {
         full_range: Range<usize>,
@@ -345,7 +756,7 @@ enum ParsedField {
     },
 }
 
-fn parse_field(bytes: &[u8], offset: &mut usize, wire_type: u8) -> Option<ParsedField> {
+pub(crate) fn parse_field(bytes: &[u8], offset: &mut usize, wire_type: u8) -> Option<ParsedField> {
     match wire_type {
         0 => {
             let start = *offset;
@@ -391,7 +802,7 @@ fn skip_unknown_field(bytes: &[u8], offset: &mut usize, wire_type: u8) -> Option
     }
 }
 
-fn read_varint(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+pub(crate) fn read_varint(bytes: &[u8], offset: &mut usize) -> Option<u64> {
     let mut result = 0_u64;
     let mut shift = 0_u32;
 
@@ -428,6 +839,16 @@ fn byte_offset_for_cursor(json: &str, cursor: (usize, usize)) -> Option<usize> {
     Some(offset + column_offset)
 }
 
+fn cursor_for_byte_offset(json: &str, offset: usize) -> (usize, usize) {
+    let before = &json[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    (
+        before.matches('\n').count(),
+        before[line_start..].chars().count(),
+    )
+}
+
 #[derive(Debug, Clone)]
 enum JsonNode {
     Object {
@@ -452,6 +873,69 @@ impl JsonNode {
         }
     }
 
+    /// Collects the path of every key and array element below this node with the offset it
+    /// starts at.
+    fn collect_targets(&self, path: &mut FieldPath, targets: &mut Vec<(FieldPath, usize)>) {
+        match self {
+            JsonNode::Object { entries, .. } => {
+                for entry in entries {
+                    path.push(FieldPathSegment::Field(entry.key.clone()));
+                    targets.push((path.clone(), entry.key_span.start));
+                    entry.value.collect_targets(path, targets);
+                    path.pop();
+                }
+            }
+            JsonNode::Array { items, .. } => {
+                for (index, item) in items.iter().enumerate() {
+                    path.push(FieldPathSegment::Index(index));
+                    targets.push((path.clone(), item.span().start));
+                    item.collect_targets(path, targets);
+                    path.pop();
+                }
+            }
+            JsonNode::Scalar { .. } => {}
+        }
+    }
+
+    /// The node at `path` of object keys and array indices below this one.
+    fn at(&self, path: &[FieldPathSegment]) -> Option<&JsonNode> {
+        let Some((segment, rest)) = path.split_first() else {
+            return Some(self);
+        };
+
+        match (self, segment) {
+            (JsonNode::Object { entries, .. }, FieldPathSegment::Field(key)) => entries
+                .iter()
+                .find(|entry| &entry.key == key)?
+                .value
+                .at(rest),
+            (JsonNode::Array { items, .. }, FieldPathSegment::Index(index)) => {
+                items.get(*index)?.at(rest)
+            }
+            _ => None,
+        }
+    }
+
+    /// The type named by the `@type` key of the expanded `Any` at `path` in `json`.
+    fn packed_type(
+        &self,
+        registry: &TypeRegistry,
+        json: &str,
+        path: &[FieldPathSegment],
+    ) -> Option<MessageDescriptor> {
+        let JsonNode::Object { entries, .. } = self.at(path)? else {
+            return None;
+        };
+        let type_url = entries
+            .iter()
+            .find(|entry| entry.key == any::TYPE_KEY)?
+            .value
+            .span()
+            .clone();
+
+        registry.resolve(&serde_json::from_str::<String>(&json[type_url]).ok()?)
+    }
+
     fn path_at(&self, offset: usize) -> Option<FieldPath> {
         if !self.span().contains(&offset) && offset != self.span().end {
             return None;
@@ -781,7 +1265,7 @@ mod tests {
     };
 
     use super::*;
-    use crate::{InputFormat, schema::load_inspector};
+    use crate::{InputFormat, SchemaSource, schema::load_inspector};
 
     fn sample_message() -> SystemEvent {
         SystemEvent {
@@ -815,18 +1299,22 @@ mod tests {
         SystemEvent::descriptor()
     }
 
-    fn schema_path() -> camino::Utf8PathBuf {
-        camino::Utf8PathBuf::from(concat!(
-            env!("CARGO_MANIFEST_DIR"),
-            "/../protogen/proto/system-event.proto"
-        ))
+    fn schema() -> SchemaSource {
+        SchemaSource::Proto {
+            paths: vec![camino::Utf8PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../protogen/proto/system-event.proto"
+            ))],
+            includes: Vec::new(),
+        }
     }
 
     #[test]
     fn resolves_json_cursor_to_nested_field_path() {
         let inspector = load_inspector(
-            schema_path().as_ref(),
+            &schema(),
             Some("SystemEvent"),
+            &[],
             &sample_message().write_to_bytes().unwrap(),
             InputFormat::Binary,
         )
@@ -834,7 +1322,9 @@ mod tests {
         let json = inspector.canonical_json().unwrap();
 
         let cursor = json_cursor(&json, "\"seconds\"");
-        let path = selected_path_for_json_cursor(&descriptor(), &json, cursor).unwrap();
+        let path =
+            selected_path_for_json_cursor(&TypeRegistry::default(), &descriptor(), &json, cursor)
+                .unwrap();
 
         assert_eq!(
             path,
@@ -845,14 +1335,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn maps_bytes_back_to_the_innermost_field_and_its_json_cursor() {
+        let bytes = sample_message().write_to_bytes().unwrap();
+        let inspector = load_inspector(
+            &schema(),
+            Some("SystemEvent"),
+            &[],
+            &bytes,
+            InputFormat::Binary,
+        )
+        .unwrap();
+        let json = inspector.canonical_json().unwrap();
+        let x_offset = bytes
+            .windows(2)
+            .position(|pair| pair == [0x10, 0x2a])
+            .unwrap();
+
+        let path = path_for_byte(
+            &TypeRegistry::default(),
+            &descriptor(),
+            &bytes,
+            x_offset + 1,
+        )
+        .unwrap();
+        assert_eq!(display_path(&path), "click.x");
+        assert_eq!(
+            json_cursor_for_path(&TypeRegistry::default(), &descriptor(), &json, &path),
+            Some(json_cursor(&json, "\"x\""))
+        );
+
+        let click = path_for_byte(
+            &TypeRegistry::default(),
+            &descriptor(),
+            &bytes,
+            x_offset - 2,
+        )
+        .unwrap();
+        assert_eq!(display_path(&click), "click");
+        assert_eq!(
+            json_cursor_for_path(&TypeRegistry::default(), &descriptor(), &json, &click),
+            Some(json_cursor(&json, "\"click\""))
+        );
+        assert_eq!(
+            path_for_byte(&TypeRegistry::default(), &descriptor(), &bytes, bytes.len()),
+            None
+        );
+    }
+
     #[test]
     fn highlights_bytes_for_selected_scalar_field() {
         let message = sample_message();
         let bytes = message.write_to_bytes().unwrap();
         let highlighted = highlighted_byte_indices(
+            &TypeRegistry::default(),
             &descriptor(),
             &bytes,
-            &[FieldPathSegment::Field("reason".to_owned())],
+            &[&[FieldPathSegment::Field("reason".to_owned())]],
         );
 
         let selected_bytes = highlighted
@@ -874,10 +1413,10 @@ mod tests {
     #[test]
     fn protobuf_lines_include_nested_paths() {
         let message = sample_message();
-        let lines = protobuf_lines(&descriptor(), &message);
+        let lines = protobuf_lines(&TypeRegistry::default(), false, &descriptor(), &message);
         let selected = vec![
-            FieldPathSegment::Field("timestamp".to_owned()),
-            FieldPathSegment::Field("seconds".to_owned()),
+            FieldPathSegment::Field("click".to_owned()),
+            FieldPathSegment::Field("x".to_owned()),
         ];
 
         let highlighted = lines
@@ -886,8 +1425,27 @@ mod tests {
             .map(|line| line.text)
             .collect::<BTreeSet<_>>();
 
-        assert!(highlighted.contains("timestamp {"));
-        assert!(highlighted.contains("  seconds: 1234567"));
+        assert!(highlighted.contains("click {"));
+        assert!(highlighted.contains("  x: 42"));
         assert!(highlighted.contains("}"));
+        assert!(!highlighted.contains("timestamp: 1970-01-15T06:56:07.000000123Z"));
+    }
+
+    #[test]
+    fn protobuf_lines_show_timestamps_on_one_line_selected_by_their_fields() {
+        let message = sample_message();
+        let lines = protobuf_lines(&TypeRegistry::default(), false, &descriptor(), &message);
+        let selected = vec![
+            FieldPathSegment::Field("timestamp".to_owned()),
+            FieldPathSegment::Field("seconds".to_owned()),
+        ];
+
+        let highlighted = lines
+            .into_iter()
+            .filter(|line| related_path(&selected, &line.path))
+            .map(|line| line.text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(highlighted, ["timestamp: 1970-01-15T06:56:07.000000123Z"]);
     }
 }
@@ -0,0 +1,205 @@
+use std::ops::Range;
+
+use protobuf::{
+    descriptor::field_descriptor_proto::{Label, Type},
+    reflect::{FieldDescriptor, RuntimeFieldType, RuntimeType},
+};
+use ratatui::prelude::*;
+
+use crate::{
+    raw::zigzag_decode,
+    selection::{self, FieldOccurrence},
+};
+
+/// Value bytes past this many are elided in the detail pane.
+const MAX_VALUE_BYTES: usize = 16;
+
+/// Describes how the first occurrence of the selected field is encoded: its number, declared
+/// type, wire type, the bytes of its tag, length prefix, and value, and what a scalar value reads
+/// as under the other types sharing its wire type.
+pub(super) fn detail_lines(occurrences: &[FieldOccurrence], bytes: &[u8]) -> Vec<Line<'static>> {
+    let Some(occurrence) = occurrences.first() else {
+        return vec![Line::styled(
+            "Not encoded: the field holds its default value",
+            Style::default().fg(Color::DarkGray),
+        )];
+    };
+    let field = &occurrence.field;
+
+    let mut field_label = format!("{} = {}", field.name(), field.number());
+    if let Some(oneof) = field.containing_oneof() {
+        field_label.push_str(&format!(" in oneof {}", oneof.name()));
+    }
+    if occurrences.len() > 1 {
+        field_label.push_str(&format!(" (1 of {} occurrences)", occurrences.len()));
+    }
+
+    let mut wire = format!(
+        "{} {}",
+        occurrence.wire_type,
+        wire_type_name(occurrence.wire_type)
+    );
+    if occurrence.wire_type == 2 && field.is_repeated() && !is_length_delimited_type(field) {
+        wire.push_str(", packed");
+    }
+
+    let mut lines = vec![
+        detail_line("Field", field_label),
+        detail_line("Type", declared_type(field)),
+        detail_line("Wire", wire),
+        detail_line("Tag", byte_span(bytes, &occurrence.tag)),
+    ];
+    if let Some(length_prefix) = &occurrence.length_prefix {
+        lines.push(detail_line(
+            "Length",
+            format!(
+                "{} = {}",
+                byte_span(bytes, length_prefix),
+                occurrence.value.len()
+            ),
+        ));
+    }
+    lines.push(detail_line("Value", byte_span(bytes, &occurrence.value)));
+    lines.push(detail_line(
+        "Offsets",
+        format!(
+            "{}..{} ({} bytes)",
+            occurrence.range.start,
+            occurrence.range.end,
+            occurrence.range.len()
+        ),
+    ));
+    if let Some(alternates) =
+        alternate_values(occurrence.wire_type, &bytes[occurrence.value.clone()])
+    {
+        lines.push(detail_line("As", alternates));
+    }
+
+    lines
+}
+
+fn detail_line(label: &'static str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{label:<8}"), Style::default().fg(Color::DarkGray)),
+        Span::raw(value),
+    ])
+}
+
+fn wire_type_name(wire_type: u8) -> &'static str {
+    match wire_type {
+        0 => "VARINT",
+        1 => "I64",
+        2 => "LEN",
+        5 => "I32",
+        _ => "unknown",
+    }
+}
+
+/// Hex bytes and the offsets they span, e.g. `10 03 @ 2..4`.
+fn byte_span(bytes: &[u8], range: &Range<usize>) -> String {
+    let mut hex = bytes[range.clone()]
+        .iter()
+        .take(MAX_VALUE_BYTES)
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if range.len() > MAX_VALUE_BYTES {
+        hex.push_str(" …");
+    }
+
+    format!("{hex} @ {}..{}", range.start, range.end)
+}
+
+/// The value read as each scalar type encoded with `wire_type`.
+fn alternate_values(wire_type: u8, value: &[u8]) -> Option<String> {
+    match wire_type {
+        0 => {
+            let value = selection::read_varint(value, &mut 0)?;
+            Some(format!(
+                "uint64 {value} | int64 {} | sint64 {} | int32 {}",
+                value as i64,
+                zigzag_decode(value),
+                value as i32
+            ))
+        }
+        1 => {
+            let value = u64::from_le_bytes(value.try_into().ok()?);
+            Some(format!(
+                "fixed64 {value} | sfixed64 {} | double {}",
+                value as i64,
+                f64::from_bits(value)
+            ))
+        }
+        5 => {
+            let value = u32::from_le_bytes(value.try_into().ok()?);
+            Some(format!(
+                "fixed32 {value} | sfixed32 {} | float {}",
+                value as i32,
+                f32::from_bits(value)
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn is_length_delimited_type(field: &FieldDescriptor) -> bool {
+    matches!(
+        field.proto().type_(),
+        Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE | Type::TYPE_GROUP
+    )
+}
+
+/// The field's type as written in the schema, e.g. `repeated sint32` or `map<string, Foo>`.
+fn declared_type(field: &FieldDescriptor) -> String {
+    if let RuntimeFieldType::Map(key, value) = field.runtime_field_type() {
+        return format!(
+            "map<{}, {}>",
+            runtime_type_name(&key),
+            runtime_type_name(&value)
+        );
+    }
+
+    let proto = field.proto();
+    let name = match proto.type_() {
+        Type::TYPE_DOUBLE => "double",
+        Type::TYPE_FLOAT => "float",
+        Type::TYPE_INT64 => "int64",
+        Type::TYPE_UINT64 => "uint64",
+        Type::TYPE_INT32 => "int32",
+        Type::TYPE_FIXED64 => "fixed64",
+        Type::TYPE_FIXED32 => "fixed32",
+        Type::TYPE_BOOL => "bool",
+        Type::TYPE_STRING => "string",
+        Type::TYPE_BYTES => "bytes",
+        Type::TYPE_UINT32 => "uint32",
+        Type::TYPE_SFIXED32 => "sfixed32",
+        Type::TYPE_SFIXED64 => "sfixed64",
+        Type::TYPE_SINT32 => "sint32",
+        Type::TYPE_SINT64 => "sint64",
+        Type::TYPE_GROUP | Type::TYPE_MESSAGE | Type::TYPE_ENUM => {
+            proto.type_name().trim_start_matches('.')
+        }
+    };
+
+    if proto.label() == Label::LABEL_REPEATED {
+        format!("repeated {name}")
+    } else {
+        name.to_owned()
+    }
+}
+
+fn runtime_type_name(runtime_type: &RuntimeType) -> String {
+    match runtime_type {
+        RuntimeType::I32 => "int32".to_owned(),
+        RuntimeType::I64 => "int64".to_owned(),
+        RuntimeType::U32 => "uint32".to_owned(),
+        RuntimeType::U64 => "uint64".to_owned(),
+        RuntimeType::F32 => "float".to_owned(),
+        RuntimeType::F64 => "double".to_owned(),
+        RuntimeType::Bool => "bool".to_owned(),
+        RuntimeType::String => "string".to_owned(),
+        RuntimeType::VecU8 => "bytes".to_owned(),
+        RuntimeType::Enum(descriptor) => descriptor.full_name().to_owned(),
+        RuntimeType::Message(descriptor) => descriptor.full_name().to_owned(),
+    }
+}
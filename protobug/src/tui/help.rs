@@ -0,0 +1,73 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+use super::centered_rect;
+
+/// Keys of the inspector outside of prompts, the hex editor, and the focused Protobuf tree, in
+/// the order the help overlay lists them.
+const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("Ctrl-C", "quit"),
+    ("Ctrl-S", "save outputs"),
+    ("Ctrl-W", "save all messages"),
+    ("Ctrl-Z/Y", "undo / redo"),
+    ("Ctrl-O", "revert message"),
+    ("Ctrl-N/P", "next / previous enum value"),
+    ("/", "search JSON or Protobuf"),
+    ("F3/Shift-F3", "next / previous search match"),
+    ("Ctrl-B", "browse Protobuf tree"),
+    ("Ctrl-E", "edit bytes in hex pane"),
+    ("Ctrl-T", "field encoding pane"),
+    ("Ctrl-U", "UTC / local timestamps"),
+    ("Ctrl-X", "hex pane"),
+    ("Ctrl-A", "ASCII pane"),
+    ("[ ]", "fewer / more byte columns"),
+    ("Ctrl-J/K", "next / previous message"),
+    ("Ctrl-G", "message picker"),
+    ("Ctrl-L", "message list"),
+    ("Ctrl-F", "find messages by filter"),
+    ("F4/Shift-F4", "next / previous match"),
+    ("Ctrl-D", "pin / unpin for diff"),
+    ("F1", "this help"),
+];
+
+const KEY_WIDTH: usize = 12;
+const ACTION_WIDTH: usize = 30;
+
+/// Lists every key binding in two columns over the middle of the screen.
+pub(super) fn render_help(frame: &mut Frame<'_>) {
+    let rows = KEY_BINDINGS.len().div_ceil(2);
+    let key_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+
+    let lines = (0..rows)
+        .map(|row| {
+            let mut spans = Vec::new();
+            for &(key, action) in [KEY_BINDINGS.get(row), KEY_BINDINGS.get(row + rows)]
+                .into_iter()
+                .flatten()
+            {
+                spans.push(Span::styled(format!("{key:<KEY_WIDTH$}"), key_style));
+                spans.push(Span::raw(format!("{action:<ACTION_WIDTH$}")));
+            }
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+
+    let width = 2 * (KEY_WIDTH + ACTION_WIDTH) + 2;
+    let overlay_area = centered_rect(width as u16, rows as u16 + 2, frame.area());
+
+    frame.render_widget(Clear, overlay_area);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title("Keys (press any key to close)")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .style(Style::default().bg(Color::Black).fg(Color::White)),
+        ),
+        overlay_area,
+    );
+}
@@ -0,0 +1,122 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Byte cursor of the hex pane while its bytes are being edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct HexEditor {
+    /// Byte under the cursor; one past the last byte is where typed bytes are appended.
+    pub(super) cursor: usize,
+    /// Whether typed bytes are inserted before the cursor rather than written over it.
+    pub(super) insert: bool,
+    /// First digit of the byte being typed.
+    pub(super) pending: Option<u8>,
+}
+
+impl HexEditor {
+    pub(super) fn new(cursor: usize) -> Self {
+        Self {
+            cursor,
+            insert: false,
+            pending: None,
+        }
+    }
+
+    pub(super) fn mode(&self) -> &'static str {
+        if self.insert { "insert" } else { "overwrite" }
+    }
+
+    /// Keeps the cursor within `len` bytes after they changed under it.
+    pub(super) fn clamp(&mut self, len: usize) {
+        if self.cursor > len {
+            self.cursor = len;
+            self.pending = None;
+        }
+    }
+
+    pub(super) fn move_to(&mut self, cursor: usize, len: usize) {
+        self.cursor = cursor.min(len);
+        self.pending = None;
+    }
+
+    /// Applies `key` to `bytes`, laid out `columns` to a row, and returns the edited bytes when
+    /// it changed them. A byte is written once both of its hex digits have been typed.
+    pub(super) fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        bytes: &[u8],
+        columns: usize,
+    ) -> Option<Vec<u8>> {
+        let len = bytes.len();
+        self.clamp(len);
+
+        match key.code {
+            KeyCode::Left => self.move_to(self.cursor.saturating_sub(1), len),
+            KeyCode::Right => self.move_to(self.cursor + 1, len),
+            KeyCode::Up => self.move_to(self.cursor.saturating_sub(columns.max(1)), len),
+            KeyCode::Down => self.move_to(self.cursor + columns.max(1), len),
+            KeyCode::Home => self.move_to(0, len),
+            KeyCode::End => self.move_to(len, len),
+            KeyCode::Insert => {
+                self.insert = !self.insert;
+                self.pending = None;
+            }
+            KeyCode::Backspace if self.pending.is_some() => self.pending = None,
+            KeyCode::Backspace if self.cursor > 0 => {
+                let mut edited = bytes.to_vec();
+                edited.remove(self.cursor - 1);
+                self.cursor -= 1;
+                return Some(edited);
+            }
+            KeyCode::Delete if self.cursor < len => {
+                let mut edited = bytes.to_vec();
+                edited.remove(self.cursor);
+                self.pending = None;
+                return Some(edited);
+            }
+            KeyCode::Char(ch) => {
+                let digit = ch.to_digit(16)? as u8;
+                let Some(high) = self.pending.take() else {
+                    self.pending = Some(digit);
+                    return None;
+                };
+
+                let mut edited = bytes.to_vec();
+                let byte = (high << 4) | digit;
+                if self.insert || self.cursor == len {
+                    edited.insert(self.cursor, byte);
+                } else {
+                    edited[self.cursor] = byte;
+                }
+                self.cursor += 1;
+                return Some(edited);
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// Keys the hex editor handles itself; everything else keeps its usual meaning.
+pub(super) fn is_hex_edit_key(key: &KeyEvent) -> bool {
+    if key
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    {
+        return false;
+    }
+
+    match key.code {
+        KeyCode::Char(ch) => ch.is_ascii_hexdigit(),
+        KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::Insert
+        | KeyCode::Backspace
+        | KeyCode::Delete
+        | KeyCode::Esc => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,83 @@
+use crate::message::{Inspector, Snapshot};
+
+/// Oldest undo steps are dropped beyond this many per message.
+const MAX_UNDO_STEPS: usize = 200;
+
+/// Undo and redo stacks for one message, plus the message as it was loaded so edits can be
+/// reverted wholesale.
+pub(super) struct EditHistory {
+    original: Snapshot,
+    /// The message as last written by a save, or as loaded before the first one.
+    saved: Snapshot,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl EditHistory {
+    pub(super) fn new(inspector: &Inspector) -> Self {
+        Self {
+            original: inspector.snapshot(),
+            saved: inspector.snapshot(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Records `before` as the state an undo returns to, unless the edit left the message as it
+    /// was.
+    pub(super) fn record(&mut self, before: Snapshot, inspector: &Inspector) {
+        if inspector.same_message(&before) {
+            return;
+        }
+
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    pub(super) fn undo(&mut self, inspector: &mut Inspector) -> bool {
+        let Some(previous) = self.undo.pop() else {
+            return false;
+        };
+
+        self.redo.push(inspector.snapshot());
+        inspector.restore(previous);
+        true
+    }
+
+    pub(super) fn redo(&mut self, inspector: &mut Inspector) -> bool {
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+
+        self.undo.push(inspector.snapshot());
+        inspector.restore(next);
+        true
+    }
+
+    /// Restores the message as it was loaded. The revert is itself a step that can be undone.
+    pub(super) fn revert(&mut self, inspector: &mut Inspector) -> bool {
+        if !self.is_modified(inspector) {
+            return false;
+        }
+
+        let before = inspector.snapshot();
+        inspector.restore(self.original.clone());
+        self.record(before, inspector);
+        true
+    }
+
+    pub(super) fn is_modified(&self, inspector: &Inspector) -> bool {
+        !inspector.same_message(&self.original)
+    }
+
+    pub(super) fn mark_saved(&mut self, inspector: &Inspector) {
+        self.saved = inspector.snapshot();
+    }
+
+    pub(super) fn has_unsaved_edits(&self, inspector: &Inspector) -> bool {
+        !inspector.same_message(&self.saved)
+    }
+}
@@ -0,0 +1,68 @@
+use super::search::report_message;
+use crate::{json, message::Inspector};
+
+/// Sidebar listing every loaded message. Rows are built the first time they are shown and
+/// rebuilt after their message is edited.
+pub(super) struct MessageList {
+    pub(super) visible: bool,
+    /// Whether the arrow keys move through the list rather than the JSON editor.
+    pub(super) focused: bool,
+    /// jaq filter summarizing each message; the compact JSON is shown without one.
+    summary: Option<String>,
+    rows: Vec<Option<String>>,
+}
+
+impl MessageList {
+    pub(super) fn new(len: usize, summary: Option<String>) -> Self {
+        Self {
+            visible: false,
+            focused: false,
+            summary,
+            rows: vec![None; len],
+        }
+    }
+
+    pub(super) fn invalidate(&mut self, index: usize) {
+        if let Some(row) = self.rows.get_mut(index) {
+            *row = None;
+        }
+    }
+
+    /// The `index`, byte size and summary of a message, padded so the columns line up. The type
+    /// `--message auto` detected comes before the summary.
+    pub(super) fn row(&mut self, index: usize, inspector: &Inspector) -> &str {
+        let number_width = self.rows.len().to_string().len();
+        let summary = self.summary.as_deref();
+
+        self.rows[index].get_or_insert_with(|| {
+            let size = inspector
+                .bytes()
+                .map_or_else(|_| "?".to_owned(), |bytes| bytes.len().to_string());
+            let detected_type = inspector
+                .detected_type()
+                .map_or_else(String::new, |name| format!("{name}  "));
+            format!(
+                "{:>number_width$} {size:>6} B  {detected_type}{}",
+                index + 1,
+                summarize(inspector, summary)
+            )
+        })
+    }
+}
+
+/// Renders the summary filter's output on one line, showing strings without their quotes.
+fn summarize(inspector: &Inspector, summary: Option<&str>) -> String {
+    let output = match summary {
+        Some(filter) => inspector
+            .canonical_json()
+            .and_then(|json| json::apply_json_filter(&json, filter)),
+        None => json::compact_json(inspector),
+    };
+
+    match output.map(|output| serde_json::from_str::<serde_json::Value>(&output)) {
+        Ok(Ok(serde_json::Value::String(text))) => text,
+        Ok(Ok(value)) => value.to_string(),
+        Ok(Err(error)) => format!("error: {error}"),
+        Err(error) => format!("error: {}", report_message(&error)),
+    }
+}
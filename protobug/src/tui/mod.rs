@@ -1,31 +1,53 @@
+mod details;
+mod help;
+mod hex_edit;
+mod history;
+mod message_list;
 mod render;
+mod search;
 #[cfg(test)]
 mod tests;
+mod tree;
 
 use std::{
+    cell::RefCell,
+    collections::BTreeSet,
     io,
+    rc::Rc,
     time::{Duration, Instant},
 };
 
 use crossterm::{
-    event::{self, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
     execute, terminal,
 };
+use error_stack::ResultExt as _;
 use ratatui::{
     Terminal,
     prelude::*,
-    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
 };
-use tui_textarea::TextArea;
-
-use self::render::{
-    adjust_width, auto_columns_for_pane_width, enum_hint_style, highlight_style, render_byte_lines,
-    scroll_offset_for_line,
+use regex::Regex;
+use tui_textarea::{CursorMove, TextArea};
+
+use self::{
+    hex_edit::{HexEditor, is_hex_edit_key},
+    history::EditHistory,
+    message_list::MessageList,
+    render::{
+        ByteCursor, adjust_width, auto_columns_for_pane_width, diff_style, enum_hint_style,
+        highlight_style, render_byte_lines, scroll_offset_for_line, search_match_spans,
+        search_match_style, tree_cursor_style,
+    },
+    search::{MessageMatches, ProtobufSearch, SearchOptions, SearchPrompt},
+    tree::{ProtobufTree, TREE_PAGE, TreeLine, is_tree_key},
 };
 use crate::{
+    diff::FieldChange,
     error::Inspect,
+    framing::{self, StreamLayout},
     message::{DisplayOptions, EnumSelection, Inspector, SaveTargets},
-    selection::{self, FieldPath},
+    selection::{self, FieldPath, ProtobufNode},
 };
 
 pub type Tui = Terminal<CrosstermBackend<io::Stdout>>;
@@ -36,7 +58,11 @@ pub(crate) struct Session {
 
 impl Session {
     pub(crate) fn new() -> io::Result<Self> {
-        execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture
+        )?;
         terminal::enable_raw_mode()?;
 
         Ok(Self {
@@ -52,18 +78,51 @@ impl Session {
 impl Drop for Session {
     fn drop(&mut self) {
         let _ = terminal::disable_raw_mode();
-        let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen);
+        let _ = execute!(
+            io::stdout(),
+            event::DisableMouseCapture,
+            terminal::LeaveAlternateScreen
+        );
     }
 }
 
 pub(crate) struct App<'a> {
     inspectors: Vec<Inspector>,
+    /// Undo history of each message, by index.
+    histories: Vec<EditHistory>,
     current_index: usize,
+    /// Message the current one is compared against in diff mode.
+    diff_pin: Option<usize>,
+    /// Diff of the current message against `diff_pin`, dropped on an edit, a pin change, or
+    /// navigation and rebuilt when next drawn.
+    diff_cache: Option<Rc<DiffView>>,
+    /// Protobuf tree nodes of the current message, dropped with `diff_cache` or when the time zone
+    /// changes and rebuilt when next needed.
+    tree_nodes: RefCell<Option<Rc<[ProtobufNode]>>>,
     json_editor: TextArea<'a>,
     message_selector: Option<String>,
+    search_prompt: Option<SearchPrompt>,
+    search_options: SearchOptions,
+    protobuf_search: Option<ProtobufSearch>,
+    /// jaq filter being typed into the find-messages prompt.
+    message_query: Option<String>,
+    message_matches: Option<MessageMatches>,
+    message_list: MessageList,
+    protobuf_tree: ProtobufTree,
+    /// Set while the bytes of the current message are edited in the hex pane.
+    hex_editor: Option<HexEditor>,
+    /// Set while asking whether to save unsaved edits before quitting.
+    quit_prompt: bool,
+    /// Set while the key binding overlay is shown.
+    help_visible: bool,
     save_targets: SaveTargets,
+    /// How the messages were framed in the input, for saving them all at once.
+    stream_layout: Option<StreamLayout>,
     display_options: DisplayOptions,
     last_byte_pane_width: u16,
+    /// Where the panes were drawn last, for finding what a mouse event points at.
+    pane_areas: PaneAreas,
+    scroll_overrides: ScrollOverrides,
     last_status: Option<Status>,
     exit: bool,
 }
@@ -75,6 +134,36 @@ struct Status {
     expires_at: Instant,
 }
 
+/// The pinned message in diff mode and the changes from it to the current message. Messages of
+/// different types are shown side by side without any changes.
+struct DiffView {
+    pinned_index: usize,
+    changes: Vec<FieldChange>,
+    same_type: bool,
+    /// Bytes of the current message that belong to a changed field.
+    differing_bytes: BTreeSet<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PaneAreas {
+    protobuf: Rect,
+    protobuf_scroll: u16,
+    hex: Option<Rect>,
+    ascii: Option<Rect>,
+    byte_columns: usize,
+    byte_scroll: u16,
+    json: Rect,
+    message_list: Option<Rect>,
+}
+
+/// Scroll positions set with the mouse wheel, which stop the Protobuf and byte panes from
+/// following the selection until a key is pressed.
+#[derive(Debug, Clone, Copy, Default)]
+struct ScrollOverrides {
+    protobuf: Option<u16>,
+    bytes: Option<u16>,
+}
+
 #[derive(Debug, Clone, Copy)]
 enum StatusKind {
     Info,
@@ -82,6 +171,9 @@ enum StatusKind {
 }
 
 const STATUS_DURATION: Duration = Duration::from_secs(4);
+const MESSAGE_LIST_WIDTH: u16 = 40;
+const MESSAGE_LIST_PAGE: isize = 10;
+const MOUSE_SCROLL_LINES: i16 = 3;
 
 impl Status {
     fn new(kind: StatusKind, message: impl Into<String>) -> Self {
@@ -114,20 +206,47 @@ impl App<'_> {
 
         let mut json_editor = TextArea::new(json.lines().map(ToOwned::to_owned).collect());
         json_editor.set_line_number_style(Style::default().fg(Color::DarkGray));
+        json_editor.set_search_style(search_match_style());
+
+        let histories = inspectors.iter().map(EditHistory::new).collect();
+        let message_list =
+            MessageList::new(inspectors.len(), display_options.message_summary.clone());
 
         Ok(Self {
             inspectors,
+            histories,
             current_index: 0,
+            diff_pin: None,
+            diff_cache: None,
+            tree_nodes: RefCell::new(None),
             json_editor,
             message_selector: None,
+            search_prompt: None,
+            search_options: SearchOptions::default(),
+            protobuf_search: None,
+            message_query: None,
+            message_matches: None,
+            message_list,
+            protobuf_tree: ProtobufTree::default(),
+            hex_editor: None,
+            quit_prompt: false,
+            help_visible: false,
             save_targets,
+            stream_layout: None,
             display_options,
             last_byte_pane_width: 0,
+            pane_areas: PaneAreas::default(),
+            scroll_overrides: ScrollOverrides::default(),
             last_status: None,
             exit: false,
         })
     }
 
+    pub(crate) fn with_stream_layout(mut self, layout: StreamLayout) -> Self {
+        self.stream_layout = Some(layout);
+        self
+    }
+
     pub(crate) fn run(&mut self, tui: &mut Tui) -> io::Result<()> {
         while !self.exit {
             tui.draw(|frame| self.render_frame(frame))?;
@@ -140,6 +259,19 @@ impl App<'_> {
     fn render_frame(&mut self, frame: &mut Frame<'_>) {
         let root_layout = Layout::vertical([Constraint::Min(0), Constraint::Length(2)]);
         let [main_area, footer_area] = root_layout.areas(frame.area());
+        let main_area = if self.message_list.visible {
+            let [list_area, main_area] = Layout::horizontal([
+                Constraint::Length(MESSAGE_LIST_WIDTH.min(main_area.width / 3)),
+                Constraint::Min(0),
+            ])
+            .areas(main_area);
+            self.render_message_list(frame, list_area);
+            self.pane_areas.message_list = Some(list_area);
+            main_area
+        } else {
+            self.pane_areas.message_list = None;
+            main_area
+        };
 
         let layout = Layout::horizontal(Constraint::from_fills([3, 2]));
         let [left_area, right_area] = layout.areas(main_area);
@@ -151,6 +283,14 @@ impl App<'_> {
             } else {
                 (left_area, None)
             };
+        let diff_view = self.diff_view();
+        let (pinned_area, top_left_area) = if diff_view.is_some() {
+            let [pinned_area, current_area] =
+                Layout::horizontal(Constraint::from_fills([1, 1])).areas(top_left_area);
+            (Some(pinned_area), current_area)
+        } else {
+            (None, top_left_area)
+        };
         let byte_pane_width = bottom_left_area.map_or(0, |area| self.byte_pane_width(area.width));
         let display_columns = self.effective_columns_for_pane_width(byte_pane_width);
         self.last_byte_pane_width = byte_pane_width;
@@ -171,34 +311,131 @@ impl App<'_> {
             .as_ref()
             .and_then(|path| self.current_inspector().highlighted_byte_indices(path).ok())
             .unwrap_or_default();
-        let protobuf_text = self.protobuf_text(selected_path.as_ref());
-        let protobuf_scroll = self.protobuf_scroll_offset(&protobuf_text, top_left_area.height);
-        let byte_scroll = bottom_left_area.map_or(0, |area| {
-            self.byte_scroll_offset(&highlighted_bytes, display_columns, area.height)
+        let changes = diff_view.as_ref().map_or(&[][..], |view| &view.changes);
+        let no_differing_bytes = BTreeSet::new();
+        let differing_bytes = diff_view
+            .as_ref()
+            .map_or(&no_differing_bytes, |view| &view.differing_bytes);
+        let protobuf_text = self.protobuf_text(selected_path.as_ref(), changes);
+        let protobuf_scroll = self.scroll_overrides.protobuf.unwrap_or_else(|| {
+            if self.protobuf_tree.focused {
+                scroll_offset_for_line(self.protobuf_tree.cursor, top_left_area.height)
+            } else {
+                self.protobuf_scroll_offset(&protobuf_text, top_left_area.height)
+            }
         });
+        let byte_scroll = self.scroll_overrides.bytes.unwrap_or_else(|| {
+            bottom_left_area.map_or(0, |area| match self.hex_editor {
+                Some(editor) => {
+                    scroll_offset_for_line(editor.cursor / display_columns.max(1), area.height)
+                }
+                None => self.byte_scroll_offset(&highlighted_bytes, display_columns, area.height),
+            })
+        });
+        let detected_type = detected_type_suffix(self.current_inspector());
+        let title = if diff_view.is_some() {
+            format!(
+                "Protobuf{detected_type}{}{}",
+                self.message_suffix(),
+                self.decode_suffix()
+            )
+        } else {
+            format!("Protobuf{detected_type}{}", self.decode_suffix())
+        };
+
+        let protobuf_border_style = if self.protobuf_tree.focused {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
 
         let para_tf = Paragraph::new(protobuf_text)
             .scroll((protobuf_scroll, 0))
             .block(
                 Block::default()
-                    .title("Protobuf")
+                    .title(title)
                     .borders(Borders::ALL)
-                    .border_type(BorderType::Plain),
+                    .border_type(BorderType::Plain)
+                    .border_style(protobuf_border_style),
+            );
+        let right_area = if self.display_options.show_details {
+            let details = self.detail_lines(selected_path.as_deref());
+            let inner_width = usize::from(right_area.width.saturating_sub(2)).max(1);
+            let wrapped_lines = details
+                .iter()
+                .map(|line| line.width().div_ceil(inner_width).max(1))
+                .sum::<usize>();
+            let height = (wrapped_lines as u16 + 2).min(right_area.height * 2 / 3);
+            let [json_area, details_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(height)])
+                    .areas(right_area);
+            frame.render_widget(
+                Paragraph::new(details).wrap(Wrap { trim: false }).block(
+                    Block::default()
+                        .title("Field Encoding")
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Plain),
+                ),
+                details_area,
             );
+            json_area
+        } else {
+            right_area
+        };
         let right_block = Block::default()
-            .title(format!("JSON{}", self.message_suffix()))
+            .title(format!(
+                "JSON{}{}{}",
+                self.message_suffix(),
+                if self.is_current_modified() {
+                    " [modified]"
+                } else {
+                    ""
+                },
+                self.decode_suffix()
+            ))
             .borders(Borders::ALL)
             .border_type(BorderType::Plain);
         let right_inner = right_block.inner(right_area);
+        let (hex_area, ascii_area) =
+            bottom_left_area.map_or((None, None), |area| self.byte_pane_areas(area));
+        self.pane_areas = PaneAreas {
+            protobuf: top_left_area,
+            protobuf_scroll,
+            hex: hex_area,
+            ascii: ascii_area,
+            byte_columns: display_columns,
+            byte_scroll,
+            json: right_area,
+            message_list: self.pane_areas.message_list,
+        };
 
         frame.render_widget(para_tf, top_left_area);
-        if let Some(bottom_left_area) = bottom_left_area {
-            self.render_byte_panes(
-                frame,
-                bottom_left_area,
-                &highlighted_bytes,
-                display_columns,
-                byte_scroll,
+        if let (Some(pinned_area), Some(diff_view)) = (pinned_area, &diff_view) {
+            frame.render_widget(
+                self.pinned_paragraph(diff_view, pinned_area.height),
+                pinned_area,
+            );
+        }
+        if let Some(hex_area) = hex_area {
+            frame.render_widget(
+                self.hex_paragraph(
+                    &highlighted_bytes,
+                    differing_bytes,
+                    display_columns,
+                    byte_scroll,
+                ),
+                hex_area,
+            );
+        }
+        if let Some(ascii_area) = ascii_area {
+            frame.render_widget(
+                self.ascii_paragraph(
+                    &highlighted_bytes,
+                    differing_bytes,
+                    display_columns,
+                    byte_scroll,
+                ),
+                ascii_area,
             );
         }
 
@@ -222,10 +459,25 @@ impl App<'_> {
             self.render_message_selector(frame, selector);
         }
 
+        if let Some(prompt) = &self.search_prompt {
+            self.render_search_prompt(frame, prompt, right_area);
+        }
+
+        if let Some(query) = &self.message_query {
+            self.render_message_query(frame, query);
+        }
+
+        if self.help_visible {
+            help::render_help(frame);
+        }
+
+        if self.quit_prompt {
+            self.render_quit_prompt(frame);
+        }
+
         let footer_block = Block::default().borders(Borders::TOP);
         frame.render_widget(
-            Paragraph::new(self.status_line_for_columns(display_columns))
-                .style(self.status_style()),
+            Paragraph::new(self.status_line()).style(self.status_style()),
             footer_block.inner(footer_area),
         );
         frame.render_widget(footer_block, footer_area);
@@ -241,11 +493,99 @@ impl App<'_> {
             return Ok(());
         }
 
-        match event::read()? {
+        let event = event::read()?;
+        if matches!(event, event::Event::Key(_)) {
+            self.scroll_overrides = ScrollOverrides::default();
+        }
+
+        match event {
+            event::Event::Mouse(mouse) => {
+                self.handle_mouse(mouse);
+            }
+
+            event::Event::Key(ev) if self.quit_prompt => {
+                self.handle_quit_prompt_key(ev);
+            }
+
+            event::Event::Key(ev) if self.help_visible => {
+                self.handle_help_key(ev);
+            }
+
             event::Event::Key(ev) if self.message_selector.is_some() => {
                 self.handle_message_selector_key(ev);
             }
 
+            event::Event::Key(ev) if self.search_prompt.is_some() => {
+                self.handle_search_key(ev);
+            }
+
+            event::Event::Key(ev) if self.message_query.is_some() => {
+                self.handle_message_query_key(ev);
+            }
+
+            event::Event::Key(ev) if self.message_list.focused && is_message_list_key(&ev) => {
+                self.handle_message_list_key(ev);
+            }
+
+            event::Event::Key(ev) if self.protobuf_tree.focused && is_tree_key(&ev) => {
+                self.handle_tree_key(ev);
+            }
+
+            event::Event::Key(ev) if self.hex_editor.is_some() && is_hex_edit_key(&ev) => {
+                self.handle_hex_key(ev);
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('/'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press && !self.cursor_in_json_string() => {
+                self.open_search();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('b'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                if self.protobuf_tree.focused {
+                    self.focus_json_editor();
+                } else {
+                    self.focus_protobuf_tree();
+                }
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::F(1),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press => {
+                self.show_help();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::F(3),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press => {
+                self.repeat_search(!ev.modifiers.contains(KeyModifiers::SHIFT));
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::F(4),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press => {
+                self.step_message_match(!ev.modifiers.contains(KeyModifiers::SHIFT));
+            }
+
             event::Event::Key(
                 ev @ KeyEvent {
                     code: KeyCode::Char('c'),
@@ -254,7 +594,7 @@ impl App<'_> {
             ) if ev.kind == event::KeyEventKind::Press
                 && ev.modifiers.contains(KeyModifiers::CONTROL) =>
             {
-                self.exit = true;
+                self.request_exit();
             }
 
             event::Event::Key(
@@ -268,6 +608,17 @@ impl App<'_> {
                 self.save_outputs();
             }
 
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('w'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.save_all_messages();
+            }
+
             event::Event::Key(
                 ev @ KeyEvent {
                     code: KeyCode::Char('n'),
@@ -312,6 +663,28 @@ impl App<'_> {
                 self.open_message_selector();
             }
 
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('f'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.open_message_query();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('l'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.toggle_message_list();
+            }
+
             event::Event::Key(
                 ev @ KeyEvent {
                     code: KeyCode::Char('j'),
@@ -334,6 +707,83 @@ impl App<'_> {
                 self.navigate_message(-1);
             }
 
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('d'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.toggle_diff_mode();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('z'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.undo_edit();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('y'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.redo_edit();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('o'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.revert_message();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('e'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.toggle_hex_editor();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('t'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.toggle_details_pane();
+            }
+
+            event::Event::Key(
+                ev @ KeyEvent {
+                    code: KeyCode::Char('u'),
+                    ..
+                },
+            ) if ev.kind == event::KeyEventKind::Press
+                && ev.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.toggle_local_time();
+            }
+
             event::Event::Key(
                 ev @ KeyEvent {
                     code: KeyCode::Char('x'),
@@ -363,10 +813,16 @@ impl App<'_> {
                 self.adjust_columns(1);
             }
 
+            // Typing does not reach the JSON editor while the message list or Protobuf tree has
+            // focus or the hex pane is being edited.
+            _ if self.message_list.focused
+                || self.protobuf_tree.focused
+                || self.hex_editor.is_some() => {}
+
             input => {
                 if self.json_editor.input(input) {
                     let json = self.json_editor.lines().join("\n");
-                    if let Err(error) = self.current_inspector_mut().apply_json(&json) {
+                    if let Err(error) = self.edit_current(|inspector| inspector.apply_json(&json)) {
                         self.show_error(format!("Parse error: {error}"));
                     } else if matches!(
                         self.visible_status().map(|status| status.kind),
@@ -381,9 +837,10 @@ impl App<'_> {
         Ok(())
     }
 
-    fn save_outputs(&mut self) {
+    fn save_outputs(&mut self) -> bool {
         match self.current_inspector().save(&self.save_targets) {
             Ok(paths) => {
+                self.histories[self.current_index].mark_saved(&self.inspectors[self.current_index]);
                 let message = paths
                     .iter()
                     .map(ToString::to_string)
@@ -391,95 +848,280 @@ impl App<'_> {
                     .join(", ");
 
                 self.show_info(format!("Saved outputs: {message}"));
+                true
             }
-            Err(error) => self.show_error(error.to_string()),
-        }
-    }
-
-    fn render_byte_panes(
-        &self,
-        frame: &mut Frame<'_>,
-        area: Rect,
-        highlighted_bytes: &std::collections::BTreeSet<usize>,
-        columns: usize,
-        byte_scroll: u16,
-    ) {
-        match (
-            self.display_options.show_hex,
-            self.display_options.show_ascii,
-        ) {
-            (true, true) => {
-                let byte_layout = Layout::horizontal(Constraint::from_fills([2, 1]));
-                let [hex_area, ascii_area] = byte_layout.areas(area);
-                frame.render_widget(
-                    self.hex_paragraph(highlighted_bytes, columns, byte_scroll),
-                    hex_area,
-                );
-                frame.render_widget(
-                    self.ascii_paragraph(highlighted_bytes, columns, byte_scroll),
-                    ascii_area,
-                );
+            Err(error) => {
+                self.show_error(error.to_string());
+                false
             }
-            (true, false) => frame.render_widget(
-                self.hex_paragraph(highlighted_bytes, columns, byte_scroll),
-                area,
-            ),
-            (false, true) => frame.render_widget(
-                self.ascii_paragraph(highlighted_bytes, columns, byte_scroll),
-                area,
-            ),
-            (false, false) => {}
         }
     }
 
-    #[cfg(test)]
-    fn status_line(&self) -> String {
-        self.status_line_for_columns(
-            self.effective_columns_for_pane_width(self.last_byte_pane_width),
-        )
-    }
-
-    fn status_line_for_columns(&self, columns: usize) -> String {
-        if let Some(status) = self.visible_status() {
-            return status.message.clone();
-        }
-
-        let message_help = if self.inspectors.len() > 1 {
-            format!(
-                " | Ctrl-G picker | Ctrl-J/K msg {}/{}",
-                self.current_index + 1,
-                self.inspectors.len()
-            )
-        } else {
-            String::new()
+    /// Writes every message to the save-all target in the input's framing.
+    fn save_all_messages(&mut self) -> bool {
+        let Some(path) = self.save_targets.all.clone() else {
+            self.show_error(
+                "No save-all target was configured. Pass --save-all or --save-all-in-place.",
+            );
+            return false;
+        };
+        let Some(layout) = &self.stream_layout else {
+            self.show_error("Saving all messages requires --multiple");
+            return false;
         };
 
-        format!(
-            "Ctrl-C quit | Ctrl-S save{message_help} | Ctrl-X hex | Ctrl-A ascii | [ ] columns {columns}"
-        )
-    }
-
-    fn status_style(&self) -> Style {
-        match self.visible_status().map(|status| status.kind) {
-            Some(StatusKind::Info) => Style::default().fg(Color::Green),
-            Some(StatusKind::Error) => Style::default().fg(Color::Red),
-            None => Style::default().fg(Color::DarkGray),
+        let written =
+            framing::encode_messages(&self.inspectors, layout, self.save_targets.all_format)
+                .and_then(|output| {
+                    std::fs::write(&path, output)
+                        .attach_with(|| format!("Output file: {path}"))
+                        .change_context(Inspect)
+                });
+        match written {
+            Ok(()) => {
+                for (history, inspector) in self.histories.iter_mut().zip(&self.inspectors) {
+                    history.mark_saved(inspector);
+                }
+                self.show_info(format!(
+                    "Saved {} messages to {path}",
+                    self.inspectors.len()
+                ));
+                true
+            }
+            Err(error) => {
+                self.show_error(search::report_message(&error));
+                false
+            }
         }
     }
 
-    fn visible_status(&self) -> Option<&Status> {
-        self.last_status
-            .as_ref()
-            .filter(|status| !status.is_expired())
+    /// Indices of the messages edited since they were loaded or last saved.
+    fn unsaved_messages(&self) -> Vec<usize> {
+        self.histories
+            .iter()
+            .zip(&self.inspectors)
+            .enumerate()
+            .filter(|(_, (history, inspector))| history.has_unsaved_edits(inspector))
+            .map(|(index, _)| index)
+            .collect()
     }
 
-    fn clear_expired_status(&mut self) {
-        if self.last_status.as_ref().is_some_and(Status::is_expired) {
+    fn request_exit(&mut self) {
+        if self.unsaved_messages().is_empty() {
+            self.exit = true;
+        } else {
+            self.quit_prompt = true;
             self.last_status = None;
         }
     }
 
-    fn status_timeout(&self) -> Option<Duration> {
+    fn handle_quit_prompt_key(&mut self, ev: KeyEvent) {
+        if ev.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        match ev.code {
+            KeyCode::Esc => {
+                self.quit_prompt = false;
+                self.show_info("Quit cancelled");
+            }
+            KeyCode::Char('s') if ev.modifiers.is_empty() => {
+                if !self.save_outputs() {
+                    self.quit_prompt = false;
+                    return;
+                }
+
+                let unsaved = self.unsaved_messages();
+                if unsaved.is_empty() {
+                    self.exit = true;
+                } else {
+                    self.quit_prompt = false;
+                    self.show_error(format!(
+                        "Saved message {}; {} still unsaved",
+                        self.current_index + 1,
+                        message_numbers(&unsaved)
+                    ));
+                }
+            }
+            KeyCode::Char('a') if ev.modifiers.is_empty() && self.save_targets.all.is_some() => {
+                if self.save_all_messages() {
+                    self.exit = true;
+                } else {
+                    self.quit_prompt = false;
+                }
+            }
+            KeyCode::Char('d') if ev.modifiers.is_empty() => self.exit = true,
+            // A second Ctrl-C discards the edits, as in most editors.
+            KeyCode::Char('c') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.exit = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn quit_prompt_save_line(&self) -> Line<'static> {
+        let label_style = Style::default().fg(Color::DarkGray);
+        let key_style = Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD);
+        let mut spans = Vec::new();
+
+        if !self.save_targets.is_empty() {
+            spans.push(Span::styled("s", key_style));
+            spans.push(Span::styled(
+                if self.inspectors.len() > 1 {
+                    ": save current message and quit  "
+                } else {
+                    ": save and quit  "
+                },
+                label_style,
+            ));
+        }
+        if self.save_targets.all.is_some() {
+            spans.push(Span::styled("a", key_style));
+            spans.push(Span::styled(": save all and quit", label_style));
+        }
+
+        if spans.is_empty() {
+            Line::styled("No --save-* outputs are configured", label_style)
+        } else {
+            Line::from(spans)
+        }
+    }
+
+    fn render_quit_prompt(&self, frame: &mut Frame<'_>) {
+        let unsaved = self.unsaved_messages();
+        let label_style = Style::default().fg(Color::DarkGray);
+        let summary = if self.inspectors.len() > 1 {
+            let verb = if unsaved.len() == 1 { "has" } else { "have" };
+            format!("{} {verb} unsaved edits", message_numbers(&unsaved))
+        } else {
+            "The message has unsaved edits".to_owned()
+        };
+        let lines = vec![
+            Line::from(summary),
+            self.quit_prompt_save_line(),
+            Line::from(vec![
+                Span::styled(
+                    "d",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(": discard edits and quit  ", label_style),
+                Span::styled(
+                    "Esc",
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(": cancel", label_style),
+            ]),
+        ];
+        let content_width = lines.iter().map(Line::width).max().unwrap_or_default();
+        let overlay_area = centered_rect((content_width as u16).saturating_add(4), 5, frame.area());
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .title("Unsaved Edits")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .style(Style::default().bg(Color::Black).fg(Color::White)),
+            ),
+            overlay_area,
+        );
+    }
+
+    /// Splits the byte pane area between the hex and ASCII panes that are shown.
+    fn byte_pane_areas(&self, area: Rect) -> (Option<Rect>, Option<Rect>) {
+        match (
+            self.display_options.show_hex,
+            self.display_options.show_ascii,
+        ) {
+            (true, true) => {
+                let byte_layout = Layout::horizontal(Constraint::from_fills([2, 1]));
+                let [hex_area, ascii_area] = byte_layout.areas(area);
+                (Some(hex_area), Some(ascii_area))
+            }
+            (true, false) => (Some(area), None),
+            (false, true) => (None, Some(area)),
+            (false, false) => (None, None),
+        }
+    }
+
+    fn show_help(&mut self) {
+        self.help_visible = true;
+    }
+
+    /// Any key closes the help overlay without doing anything else.
+    fn handle_help_key(&mut self, key: KeyEvent) {
+        if key.kind == event::KeyEventKind::Press {
+            self.help_visible = false;
+        }
+    }
+
+    /// The footer: the latest status, or the essential keys of the focused pane. Everything else
+    /// is listed by the `F1` help overlay.
+    fn status_line(&self) -> String {
+        if let Some(status) = self.visible_status() {
+            return status.message.clone();
+        }
+
+        let modified = if self.is_current_modified() {
+            "Modified | "
+        } else {
+            ""
+        };
+
+        if let Some(editor) = &self.hex_editor {
+            return format!(
+                "{modified}Hex {} | 0-9 a-f type | Ins mode | Del/Bksp delete | Esc done | F1 help",
+                editor.mode()
+            );
+        }
+
+        if self.protobuf_tree.focused {
+            return format!(
+                "{modified}Tree | Up/Down move | Left/Right fold/unfold | -/+ all | Tab/Esc JSON | F1 help"
+            );
+        }
+
+        let mut message_help = String::new();
+        if self.inspectors.len() > 1 {
+            message_help = format!(
+                " | Ctrl-J/K msg {}/{}",
+                self.current_index + 1,
+                self.inspectors.len()
+            );
+            if let Some(pinned_index) = self.diff_pin {
+                message_help.push_str(&format!(" | Ctrl-D unpin {}", pinned_index + 1));
+            }
+        }
+
+        format!("{modified}F1 help | Ctrl-C quit | Ctrl-S save | / search{message_help}")
+    }
+
+    fn status_style(&self) -> Style {
+        match self.visible_status().map(|status| status.kind) {
+            Some(StatusKind::Info) => Style::default().fg(Color::Green),
+            Some(StatusKind::Error) => Style::default().fg(Color::Red),
+            None => Style::default().fg(Color::DarkGray),
+        }
+    }
+
+    fn visible_status(&self) -> Option<&Status> {
+        self.last_status
+            .as_ref()
+            .filter(|status| !status.is_expired())
+    }
+
+    fn clear_expired_status(&mut self) {
+        if self.last_status.as_ref().is_some_and(Status::is_expired) {
+            self.last_status = None;
+        }
+    }
+
+    fn status_timeout(&self) -> Option<Duration> {
         self.visible_status().map(Status::timeout_remaining)
     }
 
@@ -584,69 +1226,863 @@ impl App<'_> {
             .max(help_width)
             .max(jump_width);
 
-        (content_width as u16).saturating_add(4)
+        (content_width as u16).saturating_add(4)
+    }
+
+    fn open_message_selector(&mut self) {
+        if self.inspectors.len() <= 1 {
+            self.show_info("Only one message is loaded");
+            return;
+        }
+
+        self.message_selector = Some((self.current_index + 1).to_string());
+        self.last_status = None;
+    }
+
+    fn handle_message_selector_key(&mut self, ev: KeyEvent) {
+        if ev.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        match ev.code {
+            KeyCode::Esc => {
+                self.message_selector = None;
+                self.show_info("Jump cancelled");
+            }
+            KeyCode::Enter => self.submit_message_selector(),
+            KeyCode::Home | KeyCode::Char('b') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.set_current_message(0);
+            }
+            KeyCode::End | KeyCode::Char('e') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.set_current_message(self.inspectors.len().saturating_sub(1));
+            }
+            KeyCode::Backspace => {
+                if let Some(selector) = &mut self.message_selector {
+                    selector.pop();
+                }
+            }
+            KeyCode::Char(ch) if ch.is_ascii_digit() => {
+                if let Some(selector) = &mut self.message_selector {
+                    selector.push(ch);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn submit_message_selector(&mut self) {
+        let Some(selector) = self.message_selector.take() else {
+            return;
+        };
+
+        let Ok(message_number) = selector.parse::<usize>() else {
+            self.show_error("Enter a valid message number");
+            return;
+        };
+
+        if !(1..=self.inspectors.len()).contains(&message_number) {
+            self.show_error(format!(
+                "Message number must be between 1 and {}",
+                self.inspectors.len()
+            ));
+            return;
+        }
+
+        self.set_current_message(message_number - 1);
+    }
+
+    fn render_message_query(&self, frame: &mut Frame<'_>, query: &str) {
+        let lines = vec![
+            Line::from("Show messages where this jaq filter is true"),
+            Line::from(format!("> {query}")),
+            self.message_query_help_line(),
+            self.message_query_step_line(),
+        ];
+        let content_width = lines.iter().map(Line::width).max().unwrap_or_default();
+        let overlay_area = centered_rect((content_width as u16).saturating_add(4), 6, frame.area());
+        let block = Block::default()
+            .title("Find Messages")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(overlay_area);
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(Paragraph::new(lines).block(block), overlay_area);
+        frame.set_cursor_position(Position::new(
+            inner.x + 2 + query.chars().count() as u16,
+            inner.y + 1,
+        ));
+    }
+
+    fn message_query_help_line(&self) -> Line<'static> {
+        let label_style = Style::default().fg(Color::DarkGray);
+
+        Line::from(vec![
+            Span::styled(
+                "Enter",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": find  ", label_style),
+            Span::styled(
+                "Esc",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": cancel", label_style),
+        ])
+    }
+
+    fn message_query_step_line(&self) -> Line<'static> {
+        let label_style = Style::default().fg(Color::DarkGray);
+
+        Line::from(vec![
+            Span::styled(
+                "F4",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": next match  ", label_style),
+            Span::styled(
+                "Shift-F4",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": previous match", label_style),
+        ])
+    }
+
+    fn open_message_query(&mut self) {
+        if self.inspectors.len() <= 1 {
+            self.show_info("Only one message is loaded");
+            return;
+        }
+
+        let query = self
+            .message_matches
+            .as_ref()
+            .map(|matches| matches.filter.clone())
+            .unwrap_or_default();
+        self.message_query = Some(query);
+        self.last_status = None;
+    }
+
+    fn handle_message_query_key(&mut self, ev: KeyEvent) {
+        if ev.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        match ev.code {
+            KeyCode::Esc => {
+                self.message_query = None;
+                self.show_info("Find cancelled");
+            }
+            KeyCode::Enter => self.submit_message_query(),
+            KeyCode::Backspace => {
+                if let Some(query) = &mut self.message_query {
+                    query.pop();
+                }
+            }
+            KeyCode::Char(ch) if !ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(query) = &mut self.message_query {
+                    query.push(ch);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Finds the messages matching the query and jumps to the first one from the current message
+    /// onwards. The prompt stays open when the filter fails so it can be corrected.
+    fn submit_message_query(&mut self) {
+        let Some(filter) = self.message_query.clone() else {
+            return;
+        };
+
+        if filter.trim().is_empty() {
+            self.show_error("Enter a jaq filter, e.g. .reason == \"user clicked\"");
+            return;
+        }
+
+        let indices = match search::matching_messages(&self.inspectors, &filter) {
+            Ok(indices) => indices,
+            Err(error) => {
+                self.show_error(error);
+                return;
+            }
+        };
+
+        self.message_query = None;
+        if indices.is_empty() {
+            self.message_matches = None;
+            self.show_info(format!("No messages match {filter}"));
+            return;
+        }
+
+        let matches = MessageMatches { filter, indices };
+        let target = matches
+            .indices
+            .iter()
+            .copied()
+            .find(|&index| index >= self.current_index)
+            .unwrap_or(matches.indices[0]);
+        self.message_matches = Some(matches);
+        self.set_current_message(target);
+    }
+
+    fn step_message_match(&mut self, forward: bool) {
+        let Some(matches) = &self.message_matches else {
+            self.show_info("No message matches; press Ctrl-F to find messages");
+            return;
+        };
+
+        if let Some(target) = matches.next_after(self.current_index, forward) {
+            self.set_current_message(target);
+        }
+    }
+
+    fn render_message_list(&mut self, frame: &mut Frame<'_>, area: Rect) {
+        let visible_rows = usize::from(area.height.saturating_sub(2));
+        let top = usize::from(scroll_offset_for_line(self.current_index, area.height));
+        let lines = (top..self.inspectors.len().min(top + visible_rows))
+            .map(|index| {
+                let row = self
+                    .message_list
+                    .row(index, &self.inspectors[index])
+                    .to_owned();
+                if index == self.current_index {
+                    Line::styled(row, highlight_style())
+                } else {
+                    Line::from(row)
+                }
+            })
+            .collect::<Vec<_>>();
+        let border_style = if self.message_list.focused {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .title("Messages")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Plain)
+                    .border_style(border_style),
+            ),
+            area,
+        );
+    }
+
+    /// The Protobuf tree nodes of the current message.
+    fn tree_nodes(&self) -> Rc<[ProtobufNode]> {
+        Rc::clone(self.tree_nodes.borrow_mut().get_or_insert_with(|| {
+            self.current_inspector()
+                .protobuf_nodes(self.display_options.local_time)
+                .into()
+        }))
+    }
+
+    /// The Protobuf pane's lines for the current message, with the tree's folds applied.
+    fn tree_lines(&self) -> Vec<TreeLine> {
+        self.protobuf_tree.lines(&self.tree_nodes())
+    }
+
+    /// Moves focus from the JSON editor to the Protobuf tree, with the cursor on the selected
+    /// field.
+    fn focus_protobuf_tree(&mut self) {
+        let json = self.current_json();
+        let selected_path = self.current_selected_path(&json);
+        self.protobuf_tree.cursor = selected_path
+            .and_then(|selected| {
+                self.tree_lines()
+                    .iter()
+                    .position(|line| selection::related_path(&selected, &line.path))
+            })
+            .unwrap_or(0);
+        self.protobuf_tree.focused = true;
+        self.message_list.focused = false;
+        self.select_tree_line();
+        self.show_info("Protobuf tree: Left/Right fold | Space toggle | Tab back to JSON");
+    }
+
+    /// Gives focus back from the Protobuf tree to the JSON editor.
+    fn focus_json_editor(&mut self) {
+        self.protobuf_tree.focused = false;
+        self.show_info("JSON editor focused");
+    }
+
+    fn handle_tree_key(&mut self, ev: KeyEvent) {
+        if ev.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        let lines = self.tree_lines();
+        let tree = &mut self.protobuf_tree;
+        match ev.code {
+            KeyCode::Up => tree.move_cursor(-1, lines.len()),
+            KeyCode::Down => tree.move_cursor(1, lines.len()),
+            KeyCode::PageUp => tree.move_cursor(-(TREE_PAGE as isize), lines.len()),
+            KeyCode::PageDown => tree.move_cursor(TREE_PAGE as isize, lines.len()),
+            KeyCode::Home => tree.cursor = 0,
+            KeyCode::End => tree.move_cursor(isize::MAX, lines.len()),
+            KeyCode::Left => {
+                tree.fold(&lines);
+            }
+            KeyCode::Right => {
+                tree.unfold(&lines);
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                tree.toggle(&lines);
+            }
+            KeyCode::Char('-') => self.fold_all(true),
+            KeyCode::Char('+' | '=') => self.fold_all(false),
+            KeyCode::Esc | KeyCode::Tab => {
+                self.focus_json_editor();
+                return;
+            }
+            _ => return,
+        }
+
+        self.select_tree_line();
+    }
+
+    /// Folds or unfolds every run of the Protobuf tree, keeping the cursor on the line that now
+    /// holds the field it was on.
+    fn fold_all(&mut self, folded: bool) {
+        let path = self
+            .tree_lines()
+            .get(self.protobuf_tree.cursor)
+            .map(|line| line.path.clone());
+
+        if folded {
+            let nodes = self.tree_nodes();
+            self.protobuf_tree.fold_all(&nodes);
+        } else {
+            self.protobuf_tree.unfold_all();
+        }
+
+        self.protobuf_tree.cursor = path
+            .and_then(|path| {
+                self.tree_lines()
+                    .iter()
+                    .position(|line| selection::related_path(&path, &line.path))
+            })
+            .unwrap_or(0);
+    }
+
+    /// Selects the field of the line under the tree's cursor.
+    fn select_tree_line(&mut self) {
+        let lines = self.tree_lines();
+        self.protobuf_tree.cursor = self.protobuf_tree.cursor.min(lines.len().saturating_sub(1));
+        if let Some(line) = lines.get(self.protobuf_tree.cursor) {
+            self.select_field(&line.path);
+        }
+    }
+
+    /// Opens the message list with focus, gives it focus back, or closes it when it already has
+    /// focus.
+    fn toggle_message_list(&mut self) {
+        if self.inspectors.len() <= 1 {
+            self.show_info("Only one message is loaded");
+            return;
+        }
+
+        if self.message_list.focused {
+            self.message_list.visible = false;
+            self.message_list.focused = false;
+            self.show_info("Message list hidden");
+        } else {
+            self.message_list.visible = true;
+            self.message_list.focused = true;
+            self.protobuf_tree.focused = false;
+            self.show_info("Message list: Up/Down select | Enter edit | Esc close");
+        }
+    }
+
+    fn handle_message_list_key(&mut self, ev: KeyEvent) {
+        if ev.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        match ev.code {
+            KeyCode::Up => self.navigate_message(-1),
+            KeyCode::Down => self.navigate_message(1),
+            KeyCode::PageUp => self.navigate_message(-MESSAGE_LIST_PAGE),
+            KeyCode::PageDown => self.navigate_message(MESSAGE_LIST_PAGE),
+            KeyCode::Home => self.set_current_message(0),
+            KeyCode::End => self.set_current_message(self.inspectors.len().saturating_sub(1)),
+            KeyCode::Enter => {
+                self.message_list.focused = false;
+                self.show_info(format!("Editing message {}", self.current_index + 1));
+            }
+            KeyCode::Esc => {
+                self.message_list.visible = false;
+                self.message_list.focused = false;
+                self.show_info("Message list hidden");
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if self.quit_prompt
+            || self.message_selector.is_some()
+            || self.search_prompt.is_some()
+            || self.message_query.is_some()
+        {
+            return;
+        }
+
+        let position = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(event::MouseButton::Left) => self.click(position),
+            MouseEventKind::ScrollDown => self.scroll_pane(position, MOUSE_SCROLL_LINES),
+            MouseEventKind::ScrollUp => self.scroll_pane(position, -MOUSE_SCROLL_LINES),
+            _ => {}
+        }
+    }
+
+    /// Selects the field drawn at `position` in the Protobuf, hex, or ASCII pane, or the message
+    /// at `position` in the message list.
+    fn click(&mut self, position: Position) {
+        let areas = self.pane_areas;
+
+        if let Some(list_area) = areas.message_list
+            && list_area.contains(position)
+        {
+            let inner = pane_inner(list_area);
+            if inner.contains(position) {
+                let top = usize::from(scroll_offset_for_line(self.current_index, list_area.height));
+                let index = top + usize::from(position.y - inner.y);
+                if index < self.inspectors.len() {
+                    self.message_list.focused = true;
+                    self.set_current_message(index);
+                }
+            }
+            return;
+        }
+
+        let protobuf_inner = pane_inner(areas.protobuf);
+        if protobuf_inner.contains(position) {
+            let line_index =
+                usize::from(position.y - protobuf_inner.y) + usize::from(areas.protobuf_scroll);
+            let lines = self.tree_lines();
+            if let Some(line) = lines.get(line_index) {
+                self.scroll_overrides = ScrollOverrides {
+                    protobuf: Some(areas.protobuf_scroll),
+                    bytes: None,
+                };
+                self.protobuf_tree.cursor = line_index;
+                self.select_field(&line.path);
+            }
+            return;
+        }
+
+        let byte_column =
+            [(areas.hex, 3), (areas.ascii, 1)]
+                .into_iter()
+                .find_map(|(area, cell_width)| {
+                    let inner = pane_inner(area?);
+                    inner
+                        .contains(position)
+                        .then(|| (inner, usize::from(position.x - inner.x) / cell_width))
+                });
+        if let Some((inner, column)) = byte_column
+            && column < areas.byte_columns
+        {
+            let row = usize::from(position.y - inner.y) + usize::from(areas.byte_scroll);
+            let byte_index = row * areas.byte_columns + column;
+            self.scroll_overrides = ScrollOverrides {
+                protobuf: None,
+                bytes: Some(areas.byte_scroll),
+            };
+            if self.hex_editor.is_some() {
+                let len = self
+                    .current_inspector()
+                    .bytes()
+                    .map_or(0, |bytes| bytes.len());
+                if let Some(editor) = &mut self.hex_editor {
+                    editor.move_to(byte_index, len);
+                }
+                self.report_hex_cursor();
+            } else {
+                self.select_byte(byte_index);
+            }
+        }
+    }
+
+    fn select_byte(&mut self, byte_index: usize) {
+        let path = match self.current_inspector().path_for_byte(byte_index) {
+            Ok(path) => path,
+            Err(error) => {
+                self.show_error(search::report_message(&error));
+                return;
+            }
+        };
+
+        match path {
+            Some(path) if self.select_field(&path) => self.show_info(format!(
+                "Byte {byte_index} is in {}",
+                selection::display_path(&path)
+            )),
+            Some(_) => {}
+            None => self.show_info(format!("Byte {byte_index} is not part of a known field")),
+        }
+    }
+
+    /// Moves the JSON cursor to the field at `path`.
+    fn select_field(&mut self, path: &[selection::FieldPathSegment]) -> bool {
+        let json = self.current_json();
+        let Some((row, column)) = self.current_inspector().json_cursor_for_path(&json, path) else {
+            self.show_error("Fix the JSON before selecting fields with the mouse");
+            return false;
+        };
+
+        self.json_editor.move_cursor(CursorMove::Jump(
+            row.min(u16::MAX as usize) as u16,
+            column.min(u16::MAX as usize) as u16,
+        ));
+        true
+    }
+
+    /// Scrolls the pane under `position` by `delta` lines.
+    fn scroll_pane(&mut self, position: Position, delta: i16) {
+        let areas = self.pane_areas;
+
+        if areas
+            .message_list
+            .is_some_and(|area| area.contains(position))
+        {
+            self.navigate_message(delta.signum().into());
+        } else if areas.json.contains(position) {
+            self.json_editor.scroll((delta, 0));
+        } else if areas.protobuf.contains(position) {
+            let lines = self.tree_lines().len();
+            self.scroll_overrides.protobuf = Some(scrolled(
+                self.scroll_overrides
+                    .protobuf
+                    .unwrap_or(areas.protobuf_scroll),
+                delta,
+                lines,
+                areas.protobuf.height,
+            ));
+        } else if let Some(area) = [areas.hex, areas.ascii]
+            .into_iter()
+            .flatten()
+            .find(|area| area.contains(position))
+        {
+            let rows = self
+                .current_inspector()
+                .bytes()
+                .map_or(0, |bytes| bytes.len().div_ceil(areas.byte_columns.max(1)));
+            self.scroll_overrides.bytes = Some(scrolled(
+                self.scroll_overrides.bytes.unwrap_or(areas.byte_scroll),
+                delta,
+                rows,
+                area.height,
+            ));
+        }
+    }
+
+    fn render_search_prompt(&self, frame: &mut Frame<'_>, prompt: &SearchPrompt, area: Rect) {
+        let height = 7.min(area.height);
+        let overlay_area = Rect::new(
+            area.x,
+            area.y + area.height.saturating_sub(height),
+            area.width,
+            height,
+        );
+        let block = Block::default()
+            .title("Search")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+        let inner = block.inner(overlay_area);
+        let result_line = match &prompt.error {
+            Some(error) => Line::styled(error.clone(), Style::default().fg(Color::Red)),
+            None => Line::styled(
+                self.search_result_summary().unwrap_or_default(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        };
+        let lines = vec![
+            Line::from(format!("/{}", prompt.query)),
+            result_line,
+            self.search_help_line(),
+            self.search_mode_line(),
+            self.search_pane_line(),
+        ];
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(Paragraph::new(lines).block(block), overlay_area);
+        frame.set_cursor_position(Position::new(
+            inner.x + 1 + prompt.query.chars().count() as u16,
+            inner.y,
+        ));
+    }
+
+    fn search_help_line(&self) -> Line<'static> {
+        let label_style = Style::default().fg(Color::DarkGray);
+
+        Line::from(vec![
+            Span::styled(
+                "Up/Dn",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": move  ", label_style),
+            Span::styled(
+                "Enter",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": done  ", label_style),
+            Span::styled(
+                "Esc",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(": cancel", label_style),
+        ])
+    }
+
+    fn search_mode_line(&self) -> Line<'static> {
+        let label_style = Style::default().fg(Color::DarkGray);
+        let key_style = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+
+        Line::from(vec![
+            Span::styled("Ctrl-T", key_style),
+            Span::styled(
+                format!(": icase {}  ", on_off(self.search_options.ignore_case)),
+                label_style,
+            ),
+            Span::styled("Ctrl-R", key_style),
+            Span::styled(
+                format!(": regex {}", on_off(self.search_options.regex)),
+                label_style,
+            ),
+        ])
     }
 
-    fn open_message_selector(&mut self) {
-        if self.inspectors.len() <= 1 {
-            self.show_info("Only one message is loaded");
-            return;
-        }
+    fn search_pane_line(&self) -> Line<'static> {
+        Line::from(vec![
+            Span::styled(
+                "Tab",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                if self.search_options.protobuf {
+                    ": searching Protobuf pane"
+                } else {
+                    ": searching JSON pane"
+                },
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    }
 
-        self.message_selector = Some((self.current_index + 1).to_string());
+    fn open_search(&mut self) {
+        self.search_prompt = Some(SearchPrompt::new(self.json_editor.cursor()));
+        let _ = self.json_editor.set_search_pattern("");
+        self.protobuf_search = None;
         self.last_status = None;
     }
 
-    fn handle_message_selector_key(&mut self, ev: KeyEvent) {
+    fn handle_search_key(&mut self, ev: KeyEvent) {
         if ev.kind != event::KeyEventKind::Press {
             return;
         }
 
         match ev.code {
             KeyCode::Esc => {
-                self.message_selector = None;
-                self.show_info("Jump cancelled");
+                if let Some(prompt) = self.search_prompt.take() {
+                    let (row, column) = prompt.origin;
+                    self.json_editor
+                        .move_cursor(CursorMove::Jump(row as u16, column as u16));
+                }
+                let _ = self.json_editor.set_search_pattern("");
+                self.protobuf_search = None;
+                self.show_info("Search cancelled");
             }
-            KeyCode::Enter => self.submit_message_selector(),
-            KeyCode::Home | KeyCode::Char('b') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.set_current_message(0);
+            KeyCode::Enter => {
+                self.search_prompt = None;
+                if let Some(summary) = self.search_result_summary() {
+                    self.show_info(summary);
+                }
             }
-            KeyCode::End | KeyCode::Char('e') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.set_current_message(self.inspectors.len().saturating_sub(1));
+            KeyCode::Down => self.step_search(true),
+            KeyCode::Up => self.step_search(false),
+            KeyCode::Char('n') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.step_search(true);
+            }
+            KeyCode::Char('p') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.step_search(false);
+            }
+            KeyCode::Char('t') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_options.ignore_case = !self.search_options.ignore_case;
+                self.update_search();
+            }
+            KeyCode::Char('r') if ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_options.regex = !self.search_options.regex;
+                self.update_search();
+            }
+            KeyCode::Tab => {
+                self.search_options.protobuf = !self.search_options.protobuf;
+                self.update_search();
             }
             KeyCode::Backspace => {
-                if let Some(selector) = &mut self.message_selector {
-                    selector.pop();
+                if let Some(prompt) = &mut self.search_prompt {
+                    prompt.query.pop();
                 }
+                self.update_search();
             }
-            KeyCode::Char(ch) if ch.is_ascii_digit() => {
-                if let Some(selector) = &mut self.message_selector {
-                    selector.push(ch);
+            KeyCode::Char(ch) if !ev.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(prompt) = &mut self.search_prompt {
+                    prompt.query.push(ch);
                 }
+                self.update_search();
             }
             _ => {}
         }
     }
 
-    fn submit_message_selector(&mut self) {
-        let Some(selector) = self.message_selector.take() else {
+    /// Re-applies the query after it or the search modes changed, moving the cursor to the first
+    /// match at or after where the search started.
+    fn update_search(&mut self) {
+        let Some(prompt) = &self.search_prompt else {
             return;
         };
 
-        let Ok(message_number) = selector.parse::<usize>() else {
-            self.show_error("Enter a valid message number");
+        let pattern = search::search_pattern(&prompt.query, self.search_options);
+        let (row, column) = prompt.origin;
+        self.json_editor
+            .move_cursor(CursorMove::Jump(row as u16, column as u16));
+        self.protobuf_search = None;
+
+        let error = if self.search_options.protobuf {
+            let _ = self.json_editor.set_search_pattern("");
+            self.start_protobuf_search(&pattern).err()
+        } else {
+            match self.json_editor.set_search_pattern(&pattern) {
+                Ok(()) => {
+                    self.json_editor.search_forward(true);
+                    None
+                }
+                Err(error) => {
+                    let _ = self.json_editor.set_search_pattern("");
+                    Some(error)
+                }
+            }
+        };
+
+        if let Some(prompt) = &mut self.search_prompt {
+            prompt.error = error.map(|error| format!("Invalid regex: {error}"));
+        }
+    }
+
+    /// Searches the Protobuf pane for `pattern` from the line of the field under the JSON cursor,
+    /// selecting the field of the first matching line.
+    fn start_protobuf_search(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        if pattern.is_empty() {
+            return Ok(());
+        }
+
+        let json = self.current_json();
+        let from = self
+            .current_selected_path(&json)
+            .and_then(|selected| {
+                self.tree_lines()
+                    .iter()
+                    .position(|line| selection::related_path(&selected, &line.path))
+            })
+            .unwrap_or(0);
+        self.protobuf_search = Some(ProtobufSearch {
+            pattern: Regex::new(pattern)?,
+            line: None,
+        });
+        self.step_protobuf_search(from, true, true);
+        Ok(())
+    }
+
+    /// Moves to the next Protobuf pane line matching the search, counted from line `from`, and
+    /// selects its field.
+    fn step_protobuf_search(&mut self, from: usize, forward: bool, inclusive: bool) {
+        let lines = self.tree_lines();
+        let Some(protobuf_search) = &mut self.protobuf_search else {
+            return;
+        };
+        let matches = search::matching_lines(&protobuf_search.pattern, &lines);
+        let Some(line) = search::next_match_line(&matches, from, forward, inclusive) else {
             return;
         };
 
-        if !(1..=self.inspectors.len()).contains(&message_number) {
-            self.show_error(format!(
-                "Message number must be between 1 and {}",
-                self.inspectors.len()
-            ));
+        protobuf_search.line = Some(line);
+        self.protobuf_tree.cursor = line;
+        self.select_field(&lines[line].path);
+    }
+
+    fn step_search(&mut self, forward: bool) {
+        if let Some(protobuf_search) = &self.protobuf_search {
+            let (from, inclusive) = protobuf_search.line.map_or((0, true), |line| (line, false));
+            self.step_protobuf_search(from, forward, inclusive);
+        } else if forward {
+            self.json_editor.search_forward(false);
+        } else {
+            self.json_editor.search_back(false);
+        }
+    }
+
+    /// Jumps to the next or previous match of the last search after the prompt was closed.
+    fn repeat_search(&mut self, forward: bool) {
+        if self.json_editor.search_pattern().is_none() && self.protobuf_search.is_none() {
+            self.show_info("No active search; press / to search");
             return;
         }
 
-        self.set_current_message(message_number - 1);
+        self.step_search(forward);
+        if let Some(summary) = self.search_result_summary() {
+            self.show_info(summary);
+        }
+    }
+
+    fn search_result_summary(&self) -> Option<String> {
+        let (current, total) = if let Some(protobuf_search) = &self.protobuf_search {
+            let matches = search::matching_lines(&protobuf_search.pattern, &self.tree_lines());
+            let current = protobuf_search
+                .line
+                .and_then(|line| matches.iter().position(|&found| found == line))
+                .map(|position| position + 1);
+            (current, matches.len())
+        } else {
+            let pattern = self.json_editor.search_pattern()?;
+            search::match_position(pattern, self.json_editor.lines(), self.json_editor.cursor())
+        };
+
+        Some(match (current, total) {
+            (_, 0) => "No matches".to_owned(),
+            (Some(current), total) => format!("Match {current} of {total}"),
+            (None, total) => format!("{total} matches"),
+        })
+    }
+
+    fn cursor_in_json_string(&self) -> bool {
+        let (row, column) = self.json_editor.cursor();
+
+        self.json_editor
+            .lines()
+            .get(row)
+            .is_some_and(|line| search::cursor_in_json_string(line, column))
     }
 
     fn current_json(&self) -> String {
@@ -655,14 +2091,20 @@ impl App<'_> {
 
     fn hex_paragraph(
         &self,
-        highlighted_bytes: &std::collections::BTreeSet<usize>,
+        highlighted_bytes: &BTreeSet<usize>,
+        differing_bytes: &BTreeSet<usize>,
         columns: usize,
         byte_scroll: u16,
     ) -> Paragraph<'static> {
-        Paragraph::new(self.hex_text(highlighted_bytes, columns))
+        let title = match &self.hex_editor {
+            Some(editor) => format!("Hex [{}] ({columns} columns)", editor.mode()),
+            None => format!("Hex ({columns} columns)"),
+        };
+
+        Paragraph::new(self.hex_text(highlighted_bytes, differing_bytes, columns))
             .block(
                 Block::default()
-                    .title("Hex")
+                    .title(title)
                     .borders(Borders::ALL)
                     .border_type(BorderType::Plain),
             )
@@ -671,11 +2113,12 @@ impl App<'_> {
 
     fn ascii_paragraph(
         &self,
-        highlighted_bytes: &std::collections::BTreeSet<usize>,
+        highlighted_bytes: &BTreeSet<usize>,
+        differing_bytes: &BTreeSet<usize>,
         columns: usize,
         byte_scroll: u16,
     ) -> Paragraph<'static> {
-        Paragraph::new(self.ascii_text(highlighted_bytes, columns))
+        Paragraph::new(self.ascii_text(highlighted_bytes, differing_bytes, columns))
             .scroll((byte_scroll, 0))
             .block(
                 Block::default()
@@ -686,7 +2129,9 @@ impl App<'_> {
     }
 
     fn current_selected_path(&self, json: &str) -> Option<FieldPath> {
-        if self.current_inspector().parse_error().is_some() {
+        if self.current_inspector().parse_error().is_some()
+            || self.current_inspector().decode_error().is_some()
+        {
             return None;
         }
 
@@ -694,15 +2139,68 @@ impl App<'_> {
             .selected_path_for_json_cursor(json, self.json_editor.cursor())
     }
 
-    fn protobuf_text(&self, selected_path: Option<&FieldPath>) -> Text<'static> {
+    fn protobuf_text(
+        &self,
+        selected_path: Option<&FieldPath>,
+        changes: &[FieldChange],
+    ) -> Text<'static> {
+        let differing_paths = changes
+            .iter()
+            .filter_map(|change| change.right_path.as_ref())
+            .collect::<Vec<_>>();
         let lines = self
-            .current_inspector()
-            .protobuf_lines()
+            .tree_lines()
+            .into_iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let style = if self.protobuf_tree.focused && index == self.protobuf_tree.cursor {
+                    tree_cursor_style()
+                } else if selected_path
+                    .is_some_and(|selected| selection::related_path(selected, &line.path))
+                {
+                    highlight_style()
+                } else if differing_paths
+                    .iter()
+                    .any(|path| selection::related_path(path, &line.path))
+                {
+                    diff_style()
+                } else {
+                    Style::default()
+                };
+
+                match &self.protobuf_search {
+                    Some(protobuf_search) => {
+                        search_match_spans(line.text, &protobuf_search.pattern, style)
+                    }
+                    None => Line::from(vec![Span::styled(line.text, style)]),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Text::from(lines)
+    }
+
+    fn pinned_protobuf_text(&self, diff_view: &DiffView) -> Text<'static> {
+        let differing_paths = diff_view
+            .changes
+            .iter()
+            .filter_map(|change| change.left_path.as_ref())
+            .collect::<Vec<_>>();
+        let nodes =
+            self.inspectors[diff_view.pinned_index].protobuf_nodes(self.display_options.local_time);
+        let lines = self
+            .protobuf_tree
+            .lines(&nodes)
             .into_iter()
             .map(|line| {
-                let style = selected_path
-                    .filter(|selected| selection::related_path(selected, &line.path))
-                    .map_or_else(Style::default, |_| highlight_style());
+                let style = if differing_paths
+                    .iter()
+                    .any(|path| selection::related_path(path, &line.path))
+                {
+                    diff_style()
+                } else {
+                    Style::default()
+                };
 
                 Line::from(vec![Span::styled(line.text, style)])
             })
@@ -711,12 +2209,29 @@ impl App<'_> {
         Text::from(lines)
     }
 
+    fn pinned_paragraph(&self, diff_view: &DiffView, area_height: u16) -> Paragraph<'static> {
+        let text = self.pinned_protobuf_text(diff_view);
+        let scroll = first_line_with_style(&text, diff_style()).map_or(0, |line_index| {
+            scroll_offset_for_line(line_index, area_height)
+        });
+
+        Paragraph::new(text).scroll((scroll, 0)).block(
+            Block::default()
+                .title(format!(
+                    "Pinned{} ({}/{})",
+                    detected_type_suffix(&self.inspectors[diff_view.pinned_index]),
+                    diff_view.pinned_index + 1,
+                    self.inspectors.len()
+                ))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain),
+        )
+    }
+
     fn protobuf_scroll_offset(&self, text: &Text<'_>, area_height: u16) -> u16 {
-        let Some(line_index) = text.lines.iter().position(|line| {
-            line.spans.iter().any(|span| {
-                span.style.bg == Some(Color::Blue) && span.style.fg == Some(Color::White)
-            })
-        }) else {
+        let Some(line_index) = first_line_with_style(text, highlight_style())
+            .or_else(|| first_line_with_style(text, diff_style()))
+        else {
             return 0;
         };
 
@@ -725,30 +2240,47 @@ impl App<'_> {
 
     fn hex_text(
         &self,
-        highlighted_bytes: &std::collections::BTreeSet<usize>,
+        highlighted_bytes: &BTreeSet<usize>,
+        differing_bytes: &BTreeSet<usize>,
         columns: usize,
     ) -> Text<'static> {
         match self.current_inspector().bytes() {
-            Ok(bytes) => Text::from(render_byte_lines(
-                &bytes,
-                highlighted_bytes,
-                columns,
-                " ",
-                |byte| format!("{byte:02x}"),
-            )),
+            Ok(bytes) => {
+                let cursor = self.hex_editor.map(|editor| ByteCursor {
+                    index: editor.cursor.min(bytes.len()),
+                    text: editor.pending.map(|digit| format!("{digit:x}_")),
+                });
+                Text::from(render_byte_lines(
+                    &bytes,
+                    highlighted_bytes,
+                    differing_bytes,
+                    cursor.as_ref(),
+                    columns,
+                    " ",
+                    |byte| format!("{byte:02x}"),
+                ))
+            }
             Err(error) => Text::from(error.to_string()),
         }
     }
 
     fn ascii_text(
         &self,
-        highlighted_bytes: &std::collections::BTreeSet<usize>,
+        highlighted_bytes: &BTreeSet<usize>,
+        differing_bytes: &BTreeSet<usize>,
         columns: usize,
     ) -> Text<'static> {
         match self.current_inspector().bytes() {
             Ok(bytes) => Text::from(render_byte_lines(
                 &bytes,
                 highlighted_bytes,
+                differing_bytes,
+                self.hex_editor
+                    .map(|editor| ByteCursor {
+                        index: editor.cursor.min(bytes.len()),
+                        text: None,
+                    })
+                    .as_ref(),
                 columns,
                 "",
                 |byte| match byte {
@@ -761,6 +2293,53 @@ impl App<'_> {
         }
     }
 
+    fn diff_view(&mut self) -> Option<Rc<DiffView>> {
+        let pinned_index = self.diff_pin?;
+        if let Some(view) = &self.diff_cache {
+            return Some(Rc::clone(view));
+        }
+
+        let current = self.current_inspector();
+        let changes = self.inspectors[pinned_index].diff(current);
+        let same_type = changes.is_some();
+        let changes = changes.unwrap_or_default();
+        let changed_paths = changes
+            .iter()
+            .filter_map(|change| change.right_path.as_deref())
+            .collect::<Vec<_>>();
+        let differing_bytes = current
+            .highlighted_byte_indices_for_paths(&changed_paths)
+            .unwrap_or_default();
+        let view = Rc::new(DiffView {
+            pinned_index,
+            changes,
+            same_type,
+            differing_bytes,
+        });
+
+        self.diff_cache = Some(Rc::clone(&view));
+        Some(view)
+    }
+
+    fn toggle_diff_mode(&mut self) {
+        self.diff_cache = None;
+        if let Some(pinned_index) = self.diff_pin.take() {
+            self.show_info(format!("Unpinned message {}", pinned_index + 1));
+            return;
+        }
+
+        if self.inspectors.len() <= 1 {
+            self.show_info("Only one message is loaded");
+            return;
+        }
+
+        self.diff_pin = Some(self.current_index);
+        self.show_info(format!(
+            "Pinned message {}; Ctrl-J/K picks a message to compare",
+            self.current_index + 1
+        ));
+    }
+
     fn adjust_columns(&mut self, delta: isize) {
         let current_columns = self.effective_columns_for_pane_width(self.last_byte_pane_width);
         self.display_options.columns = Some(adjust_width(current_columns, delta));
@@ -785,6 +2364,45 @@ impl App<'_> {
         }
     }
 
+    fn detail_lines(
+        &self,
+        selected_path: Option<&[selection::FieldPathSegment]>,
+    ) -> Vec<Line<'static>> {
+        let Some(selected_path) = selected_path else {
+            return vec![Line::styled(
+                "Move the cursor onto a field to see how it is encoded",
+                Style::default().fg(Color::DarkGray),
+            )];
+        };
+
+        match self.current_inspector().field_occurrences(selected_path) {
+            Ok((occurrences, bytes)) => details::detail_lines(&occurrences, &bytes),
+            Err(error) => vec![Line::styled(
+                search::report_message(&error),
+                Style::default().fg(Color::Red),
+            )],
+        }
+    }
+
+    fn toggle_details_pane(&mut self) {
+        self.display_options.show_details = !self.display_options.show_details;
+        self.show_info(if self.display_options.show_details {
+            "Encoding pane shown"
+        } else {
+            "Encoding pane hidden"
+        });
+    }
+
+    fn toggle_local_time(&mut self) {
+        self.display_options.local_time = !self.display_options.local_time;
+        *self.tree_nodes.get_mut() = None;
+        self.show_info(if self.display_options.local_time {
+            "Timestamps shown in local time"
+        } else {
+            "Timestamps shown in UTC"
+        });
+    }
+
     fn toggle_ascii_pane(&mut self) {
         self.display_options.show_ascii = !self.display_options.show_ascii;
         self.show_info(if self.display_options.show_ascii {
@@ -794,8 +2412,101 @@ impl App<'_> {
         });
     }
 
+    /// Starts editing the bytes of the current message in the hex pane, with the cursor on the
+    /// selected field, or stops.
+    fn toggle_hex_editor(&mut self) {
+        if self.hex_editor.take().is_some() {
+            self.show_info("Hex edit done");
+            return;
+        }
+
+        let json = self.current_json();
+        let cursor = self
+            .current_selected_path(&json)
+            .and_then(|path| {
+                self.current_inspector()
+                    .highlighted_byte_indices(&path)
+                    .ok()
+            })
+            .and_then(|highlighted| highlighted.first().copied())
+            .unwrap_or(0);
+        self.display_options.show_hex = true;
+        self.hex_editor = Some(HexEditor::new(cursor));
+        self.show_info(
+            "Hex edit: type hex digits | Insert toggles insert mode | Del/Backspace delete | Esc done",
+        );
+    }
+
+    fn handle_hex_key(&mut self, ev: KeyEvent) {
+        if ev.kind != event::KeyEventKind::Press {
+            return;
+        }
+        if ev.code == KeyCode::Esc {
+            self.hex_editor = None;
+            self.show_info("Hex edit done");
+            return;
+        }
+
+        let bytes = match self.current_inspector().bytes() {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                self.show_error(search::report_message(&error));
+                return;
+            }
+        };
+        let columns = self.effective_columns_for_pane_width(self.last_byte_pane_width);
+        let Some(editor) = &mut self.hex_editor else {
+            return;
+        };
+
+        if let Some(edited) = editor.handle_key(ev, &bytes, columns)
+            && self
+                .edit_current(|inspector| inspector.apply_bytes(edited))
+                .is_ok()
+        {
+            self.refresh_json_editor();
+        }
+        self.report_hex_cursor();
+    }
+
+    /// Shows why the edited bytes do not decode, or selects the field under the hex cursor.
+    fn report_hex_cursor(&mut self) {
+        if let Some(error) = self.current_inspector().decode_error() {
+            self.show_error(error.to_owned());
+            return;
+        }
+        let Some(editor) = self.hex_editor else {
+            return;
+        };
+
+        let len = self
+            .current_inspector()
+            .bytes()
+            .map_or(0, |bytes| bytes.len());
+        if editor.pending.is_some() {
+            self.show_info(format!("Byte {}: type the second hex digit", editor.cursor));
+        } else if editor.cursor >= len {
+            self.show_info("End of the message; typed bytes are appended");
+        } else {
+            self.select_byte(editor.cursor);
+        }
+    }
+
+    fn decode_suffix(&self) -> &'static str {
+        if self.current_inspector().decode_error().is_some() {
+            " [does not decode]"
+        } else if self.current_inspector().embed_note().is_some() {
+            " [--embed kept as bytes]"
+        } else {
+            ""
+        }
+    }
+
     fn toggle_hex_pane(&mut self) {
         self.display_options.show_hex = !self.display_options.show_hex;
+        if !self.display_options.show_hex {
+            self.hex_editor = None;
+        }
         self.show_info(if self.display_options.show_hex {
             "Hex pane shown"
         } else {
@@ -811,7 +2522,7 @@ impl App<'_> {
 
     fn byte_scroll_offset(
         &self,
-        highlighted_bytes: &std::collections::BTreeSet<usize>,
+        highlighted_bytes: &BTreeSet<usize>,
         columns: usize,
         area_height: u16,
     ) -> u16 {
@@ -860,22 +2571,97 @@ impl App<'_> {
             return;
         };
 
-        let Some(variant) = self
-            .current_inspector_mut()
-            .cycle_enum_variant(&selected_path, delta)
+        let Some(variant) =
+            self.edit_current(|inspector| inspector.cycle_enum_variant(&selected_path, delta))
         else {
             self.show_info("Move the cursor onto an enum value to switch variants");
             return;
         };
 
+        if self.refresh_json_editor() {
+            self.show_info(format!("Enum set to {variant}"));
+        }
+    }
+
+    /// Applies an edit to the current message and records it in the message's undo history.
+    fn edit_current<R>(&mut self, edit: impl FnOnce(&mut Inspector) -> R) -> R {
+        let before = self.current_inspector().snapshot();
+        let result = edit(self.current_inspector_mut());
+        self.histories[self.current_index].record(before, &self.inspectors[self.current_index]);
+        result
+    }
+
+    fn undo_edit(&mut self) {
+        let index = self.current_index;
+        if !self.histories[index].undo(&mut self.inspectors[index]) {
+            self.show_info("Nothing to undo");
+            return;
+        }
+
+        self.message_list.invalidate(index);
+        self.drop_message_caches();
+        if self.refresh_json_editor() {
+            self.show_info(format!("Undid edit | {}", self.modified_label()));
+        }
+    }
+
+    fn redo_edit(&mut self) {
+        let index = self.current_index;
+        if !self.histories[index].redo(&mut self.inspectors[index]) {
+            self.show_info("Nothing to redo");
+            return;
+        }
+
+        self.message_list.invalidate(index);
+        self.drop_message_caches();
+        if self.refresh_json_editor() {
+            self.show_info(format!("Redid edit | {}", self.modified_label()));
+        }
+    }
+
+    fn revert_message(&mut self) {
+        let index = self.current_index;
+        if !self.histories[index].revert(&mut self.inspectors[index]) {
+            self.show_info(format!("Message {} has no edits to revert", index + 1));
+            return;
+        }
+
+        self.message_list.invalidate(index);
+        self.drop_message_caches();
+        if self.refresh_json_editor() {
+            self.show_info(format!(
+                "Reverted message {} to its original bytes; Ctrl-Z undoes the revert",
+                index + 1
+            ));
+        }
+    }
+
+    fn is_current_modified(&self) -> bool {
+        self.histories[self.current_index].is_modified(self.current_inspector())
+    }
+
+    fn modified_label(&self) -> &'static str {
+        if self.is_current_modified() {
+            "modified"
+        } else {
+            "unmodified"
+        }
+    }
+
+    /// Re-renders the JSON editor from the current message, keeping the cursor where it was.
+    /// Returns false after showing the error when the message cannot be rendered.
+    fn refresh_json_editor(&mut self) -> bool {
         match self.current_inspector().canonical_json() {
             Ok(json) => {
                 let cursor = self.json_editor.cursor();
                 self.json_editor
                     .set_lines(json.lines().map(ToOwned::to_owned).collect(), cursor);
-                self.show_info(format!("Enum set to {variant}"));
+                true
+            }
+            Err(error) => {
+                self.show_error(error.to_string());
+                false
             }
-            Err(error) => self.show_error(error.to_string()),
         }
     }
 
@@ -883,10 +2669,21 @@ impl App<'_> {
         &self.inspectors[self.current_index]
     }
 
+    /// Also drops the message list row and caches of the current message, which the caller may be
+    /// about to edit.
     fn current_inspector_mut(&mut self) -> &mut Inspector {
+        self.message_list.invalidate(self.current_index);
+        self.drop_message_caches();
         &mut self.inspectors[self.current_index]
     }
 
+    /// Drops what is cached about the current message, after it is edited or another message
+    /// becomes current.
+    fn drop_message_caches(&mut self) {
+        self.diff_cache = None;
+        *self.tree_nodes.get_mut() = None;
+    }
+
     fn message_suffix(&self) -> String {
         if self.inspectors.len() > 1 {
             format!(" ({}/{})", self.current_index + 1, self.inspectors.len())
@@ -919,23 +2716,104 @@ impl App<'_> {
 
     fn set_current_message(&mut self, index: usize) {
         self.current_index = index;
+        self.drop_message_caches();
         self.message_selector = None;
         match self.current_inspector().canonical_json() {
             Ok(json) => {
                 self.json_editor
                     .set_lines(json.lines().map(ToOwned::to_owned).collect(), (0, 0));
                 self.last_status = None;
-                self.show_info(format!(
+                let mut message = format!(
                     "Message {} of {}",
                     self.current_index + 1,
                     self.inspectors.len()
-                ));
+                );
+                if let Some(view) = self.diff_view()
+                    && view.pinned_index != self.current_index
+                {
+                    message.push_str(&if view.same_type {
+                        format!(
+                            " | {} difference{} from message {}",
+                            view.changes.len(),
+                            if view.changes.len() == 1 { "" } else { "s" },
+                            view.pinned_index + 1
+                        )
+                    } else {
+                        format!(" | not the same type as message {}", view.pinned_index + 1)
+                    });
+                }
+                if let Some(summary) = self
+                    .message_matches
+                    .as_ref()
+                    .and_then(|matches| matches.summary(self.current_index))
+                {
+                    message.push_str(&format!(" | {summary}"));
+                }
+                self.show_info(message);
             }
             Err(error) => self.show_error(error.to_string()),
         }
     }
 }
 
+/// "Message 3" or "Messages 1, 4, 5" for 0-based message indices.
+fn message_numbers(indices: &[usize]) -> String {
+    let numbers = indices
+        .iter()
+        .map(|index| (index + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if indices.len() == 1 {
+        format!("Message {numbers}")
+    } else {
+        format!("Messages {numbers}")
+    }
+}
+
+/// " TypeName" for a pane title when `--message auto` picked the message type, so the detected
+/// type is always on screen.
+fn detected_type_suffix(inspector: &Inspector) -> String {
+    inspector
+        .detected_type()
+        .map_or_else(String::new, |name| format!(" {name}"))
+}
+
+fn pane_inner(area: Rect) -> Rect {
+    area.inner(Margin::new(1, 1))
+}
+
+/// Moves a bordered pane's scroll offset by `delta`, keeping its last line at the bottom at most.
+fn scrolled(offset: u16, delta: i16, lines: usize, area_height: u16) -> u16 {
+    let visible_lines = usize::from(area_height.saturating_sub(2));
+    let last_top = lines.saturating_sub(visible_lines).min(u16::MAX as usize) as u16;
+
+    offset.saturating_add_signed(delta).min(last_top)
+}
+
+fn is_message_list_key(ev: &KeyEvent) -> bool {
+    ev.modifiers.is_empty()
+        && matches!(
+            ev.code,
+            KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::PageUp
+                | KeyCode::PageDown
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::Enter
+                | KeyCode::Esc
+        )
+}
+
+fn first_line_with_style(text: &Text<'_>, style: Style) -> Option<usize> {
+    text.lines.iter().position(|line| {
+        line.spans
+            .iter()
+            .any(|span| span.style.bg == style.bg && span.style.fg == style.fg)
+    })
+}
+
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let popup_width = width.min(area.width.saturating_sub(2)).max(1);
     let popup_height = height.min(area.height.saturating_sub(2)).max(1);
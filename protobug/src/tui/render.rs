@@ -3,9 +3,20 @@ use ratatui::prelude::*;
 pub(super) const COMPACT_COLUMNS: usize = 16;
 pub(super) const WIDE_COLUMNS: usize = 24;
 
+/// The hex editor's cursor as drawn in a byte pane.
+pub(super) struct ByteCursor {
+    pub(super) index: usize,
+    /// Drawn in place of the byte under the cursor, such as a half-typed hex byte.
+    pub(super) text: Option<String>,
+}
+
+/// Lays `bytes` out `width` to a row. A cursor one past the last byte gets a blank cell of its
+/// own.
 pub(super) fn render_byte_lines<F>(
     bytes: &[u8],
     highlighted_bytes: &std::collections::BTreeSet<usize>,
+    differing_bytes: &std::collections::BTreeSet<usize>,
+    cursor: Option<&ByteCursor>,
     width: usize,
     separator: &str,
     render: F,
@@ -14,24 +25,40 @@ where
     F: Fn(u8) -> String,
 {
     let width = width.max(1);
+    let cells = bytes.len() + usize::from(cursor.is_some_and(|cursor| cursor.index == bytes.len()));
 
-    bytes
-        .chunks(width)
-        .enumerate()
-        .map(|(chunk_index, chunk)| {
+    (0..cells)
+        .step_by(width)
+        .map(|row_start| {
+            let row_end = (row_start + width).min(cells);
             let mut spans = Vec::new();
 
-            for (index_in_chunk, byte) in chunk.iter().enumerate() {
-                let index = chunk_index * width + index_in_chunk;
-                let style = if highlighted_bytes.contains(&index) {
-                    highlight_style()
-                } else {
-                    Style::default()
+            for index in row_start..row_end {
+                let span = match cursor.filter(|cursor| cursor.index == index) {
+                    Some(cursor) => {
+                        let text = cursor
+                            .text
+                            .clone()
+                            .unwrap_or_else(|| match bytes.get(index) {
+                                Some(&byte) => render(byte),
+                                None => " ".repeat(render(0).chars().count()),
+                            });
+                        Span::styled(text, byte_cursor_style())
+                    }
+                    None => {
+                        let style = if highlighted_bytes.contains(&index) {
+                            highlight_style()
+                        } else if differing_bytes.contains(&index) {
+                            diff_style()
+                        } else {
+                            Style::default()
+                        };
+                        Span::styled(render(bytes[index]), style)
+                    }
                 };
+                spans.push(span);
 
-                spans.push(Span::styled(render(*byte), style));
-
-                if index_in_chunk + 1 < chunk.len() {
+                if index + 1 < row_end {
                     spans.push(Span::raw(separator.to_owned()));
                 }
             }
@@ -85,6 +112,55 @@ pub(super) fn highlight_style() -> Style {
         .add_modifier(Modifier::BOLD)
 }
 
+/// Marks the line under the cursor of the focused Protobuf tree.
+pub(super) fn tree_cursor_style() -> Style {
+    highlight_style().add_modifier(Modifier::REVERSED)
+}
+
+/// Marks fields and bytes that differ from the pinned message in diff mode.
+pub(super) fn diff_style() -> Style {
+    Style::default().bg(Color::Yellow).fg(Color::Black)
+}
+
+/// Marks the byte under the hex editor's cursor.
+pub(super) fn byte_cursor_style() -> Style {
+    Style::default()
+        .bg(Color::White)
+        .fg(Color::Black)
+        .add_modifier(Modifier::BOLD)
+}
+
+pub(super) fn search_match_style() -> Style {
+    Style::default().bg(Color::Magenta).fg(Color::White)
+}
+
+/// Draws `text` in `style` with the matches of `pattern` marked as search matches.
+pub(super) fn search_match_spans(
+    text: String,
+    pattern: &regex::Regex,
+    style: Style,
+) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for found in pattern.find_iter(&text) {
+        if found.as_str().is_empty() {
+            continue;
+        }
+        if found.start() > end {
+            spans.push(Span::styled(text[end..found.start()].to_owned(), style));
+        }
+        spans.push(Span::styled(
+            found.as_str().to_owned(),
+            search_match_style(),
+        ));
+        end = found.end();
+    }
+    if end < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[end..].to_owned(), style));
+    }
+    Line::from(spans)
+}
+
 pub(super) fn enum_hint_style() -> Style {
     Style::default()
         .fg(Color::Yellow)
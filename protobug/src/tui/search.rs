@@ -0,0 +1,238 @@
+use error_stack::Report;
+use regex::Regex;
+
+use super::tree::TreeLine;
+use crate::{error::Inspect, json, message::Inspector};
+
+/// Search modes that carry over from one search to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct SearchOptions {
+    pub(super) ignore_case: bool,
+    pub(super) regex: bool,
+    /// Search the lines of the Protobuf pane instead of the JSON editor.
+    pub(super) protobuf: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            ignore_case: true,
+            regex: false,
+            protobuf: false,
+        }
+    }
+}
+
+/// The last search of the Protobuf pane. The JSON editor keeps its own search pattern.
+#[derive(Debug, Clone)]
+pub(super) struct ProtobufSearch {
+    pub(super) pattern: Regex,
+    /// Protobuf pane line of the current match, once one was found.
+    pub(super) line: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct SearchPrompt {
+    pub(super) query: String,
+    /// JSON editor cursor when the prompt was opened; matches are searched for from here and it
+    /// is restored when the search is cancelled.
+    pub(super) origin: (usize, usize),
+    pub(super) error: Option<String>,
+}
+
+impl SearchPrompt {
+    pub(super) fn new(origin: (usize, usize)) -> Self {
+        Self {
+            query: String::new(),
+            origin,
+            error: None,
+        }
+    }
+}
+
+/// Messages that matched the last cross-message query, in message order. They are found when the
+/// query is submitted and not updated by later edits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct MessageMatches {
+    pub(super) filter: String,
+    pub(super) indices: Vec<usize>,
+}
+
+impl MessageMatches {
+    /// The first match after `current` (or before it, going backwards), wrapping around.
+    pub(super) fn next_after(&self, current: usize, forward: bool) -> Option<usize> {
+        if forward {
+            self.indices
+                .iter()
+                .find(|&&index| index > current)
+                .or_else(|| self.indices.first())
+                .copied()
+        } else {
+            self.indices
+                .iter()
+                .rev()
+                .find(|&&index| index < current)
+                .or_else(|| self.indices.last())
+                .copied()
+        }
+    }
+
+    /// Describes where `current` falls among the matches, listing the first few message numbers.
+    pub(super) fn summary(&self, current: usize) -> Option<String> {
+        const LISTED: usize = 8;
+
+        let position = self.indices.iter().position(|&index| index == current)?;
+        let mut listed = self
+            .indices
+            .iter()
+            .take(LISTED)
+            .map(|index| (index + 1).to_string())
+            .collect::<Vec<_>>();
+        if self.indices.len() > LISTED {
+            listed.push(format!("+{} more", self.indices.len() - LISTED));
+        }
+
+        Some(format!(
+            "match {} of {} (messages {})",
+            position + 1,
+            self.indices.len(),
+            listed.join(", ")
+        ))
+    }
+}
+
+/// Runs the jaq `filter` against the canonical JSON of every message and returns the indices of
+/// those it yields a truthy value for, as with `select`. Only a filter that does not compile is an
+/// error; a message the filter yields nothing for, or fails on, is not a match.
+pub(super) fn matching_messages(
+    inspectors: &[Inspector],
+    filter: &str,
+) -> std::result::Result<Vec<usize>, String> {
+    let filter = json::compile_json_filter(filter).map_err(|error| report_message(&error))?;
+
+    Ok(inspectors
+        .iter()
+        .enumerate()
+        .filter(|(_, inspector)| {
+            inspector
+                .canonical_json()
+                .is_ok_and(|json| json::json_filter_matches(&filter, &json))
+        })
+        .map(|(index, _)| index)
+        .collect())
+}
+
+/// The most specific message attached to `error`, which is more useful in the footer than the
+/// generic context.
+pub(super) fn report_message(error: &Report<Inspect>) -> String {
+    error
+        .frames()
+        .find_map(|frame| {
+            frame.downcast_ref::<String>().cloned().or_else(|| {
+                frame
+                    .downcast_ref::<&str>()
+                    .map(|message| (*message).to_owned())
+            })
+        })
+        .unwrap_or_else(|| error.to_string())
+}
+
+/// Builds the pattern handed to the JSON editor; an empty pattern clears the search.
+pub(super) fn search_pattern(query: &str, options: SearchOptions) -> String {
+    if query.is_empty() {
+        return String::new();
+    }
+
+    let pattern = if options.regex {
+        query.to_owned()
+    } else {
+        regex::escape(query)
+    };
+
+    if options.ignore_case {
+        format!("(?i){pattern}")
+    } else {
+        pattern
+    }
+}
+
+/// Counts the matches in `lines` and returns the 1-based number of the match starting at
+/// `cursor`, which is a `(row, column)` pair counted in characters.
+pub(super) fn match_position(
+    pattern: &Regex,
+    lines: &[String],
+    cursor: (usize, usize),
+) -> (Option<usize>, usize) {
+    let mut current = None;
+    let mut total = 0;
+
+    for (row, line) in lines.iter().enumerate() {
+        for found in pattern.find_iter(line) {
+            if found.as_str().is_empty() {
+                continue;
+            }
+
+            total += 1;
+            if (row, line[..found.start()].chars().count()) == cursor {
+                current = Some(total);
+            }
+        }
+    }
+
+    (current, total)
+}
+
+/// Indices of the Protobuf pane lines with a non-empty match of `pattern`.
+pub(super) fn matching_lines(pattern: &Regex, lines: &[TreeLine]) -> Vec<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            pattern
+                .find_iter(&line.text)
+                .any(|found| !found.as_str().is_empty())
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The first of `matches`, which are in line order, after `from` (or at it when `inclusive`) in
+/// the direction of the search, wrapping around.
+pub(super) fn next_match_line(
+    matches: &[usize],
+    from: usize,
+    forward: bool,
+    inclusive: bool,
+) -> Option<usize> {
+    if forward {
+        matches
+            .iter()
+            .find(|&&line| line > from || (inclusive && line == from))
+            .or(matches.first())
+    } else {
+        matches
+            .iter()
+            .rev()
+            .find(|&&line| line < from || (inclusive && line == from))
+            .or(matches.last())
+    }
+    .copied()
+}
+
+/// Whether `column` (in characters) falls inside a string literal on a line of JSON, where `/` is
+/// ordinary text rather than the search key.
+pub(super) fn cursor_in_json_string(line: &str, column: usize) -> bool {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for ch in line.chars().take(column) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ => {}
+        }
+    }
+
+    in_string
+}
@@ -13,13 +13,19 @@ use tempfile::tempdir;
 use tui_textarea::CursorMove;
 
 use super::*;
-use crate::{DisplayOptions, InputFormat, schema::load_inspector};
+use crate::{
+    DisplayOptions, Framing, InputFormat, SchemaSource,
+    schema::{load_inspector, load_message_selection},
+};
 
-fn schema_path() -> Utf8PathBuf {
-    Utf8PathBuf::from(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/../protogen/proto/system-event.proto"
-    ))
+fn schema() -> SchemaSource {
+    SchemaSource::Proto {
+        paths: vec![Utf8PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../protogen/proto/system-event.proto"
+        ))],
+        includes: Vec::new(),
+    }
 }
 
 fn sample_bytes() -> Vec<u8> {
@@ -70,8 +76,9 @@ fn snapshot_text(app: &mut App<'_>) -> String {
 #[test]
 fn render_matches_default_layout() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -88,11 +95,63 @@ fn render_matches_default_layout() {
     assert_snapshot!(rendered);
 }
 
+#[test]
+fn help_overlay_lists_every_key_and_closes_on_any_key() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    let json = app.current_json();
+    app.show_help();
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    app.handle_help_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
+    assert!(!app.help_visible);
+    assert_eq!(app.current_json(), json);
+}
+
+#[test]
+fn footers_fit_the_default_layout_width() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5); 120]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.set_current_message(110);
+    app.toggle_diff_mode();
+    app.set_current_message(119);
+    move_cursor_to(&mut app, "\"Left\"");
+    app.cycle_selected_enum(1);
+    app.last_status = None;
+
+    let footer = app.status_line();
+    assert_eq!(
+        footer,
+        "Modified | F1 help | Ctrl-C quit | Ctrl-S save | / search | Ctrl-J/K msg 120/120 | Ctrl-D unpin 111"
+    );
+    assert!(footer.chars().count() <= 100);
+
+    app.focus_protobuf_tree();
+    app.last_status = None;
+    assert!(app.status_line().chars().count() <= 100);
+    app.handle_tree_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+
+    app.toggle_hex_editor();
+    app.last_status = None;
+    assert!(app.status_line().chars().count() <= 100);
+}
+
 #[test]
 fn json_panel_shows_line_numbers() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -113,8 +172,9 @@ fn json_panel_shows_line_numbers() {
 #[test]
 fn render_matches_error_layout() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -139,8 +199,9 @@ fn render_matches_error_layout() {
 fn save_action_updates_status_message() {
     let dir = tempdir().unwrap();
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -173,8 +234,9 @@ fn save_action_updates_status_message() {
 #[test]
 fn render_highlights_related_panes_for_selected_json_field() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -196,10 +258,10 @@ fn render_highlights_related_panes_for_selected_json_field() {
             selection::FieldPathSegment::Field("seconds".to_owned()),
         ])
     );
-    let protobuf = app.protobuf_text(selected_path.as_ref());
+    let protobuf = app.protobuf_text(selected_path.as_ref(), &[]);
     assert!(protobuf.lines.iter().any(|line| {
         line.spans.iter().any(|span| {
-            span.content.as_ref().contains("seconds: 1234567")
+            span.content.as_ref() == "timestamp: 1970-01-15T06:56:07.000000123Z"
                 && span.style.bg == Some(Color::Blue)
                 && span.style.fg == Some(Color::White)
         })
@@ -209,8 +271,8 @@ fn render_highlights_related_panes_for_selected_json_field() {
         .highlighted_byte_indices(selected_path.as_ref().unwrap())
         .unwrap();
     let columns = app.effective_columns_for_pane_width(48);
-    let hex = app.hex_text(&highlighted_bytes, columns);
-    let ascii = app.ascii_text(&highlighted_bytes, columns);
+    let hex = app.hex_text(&highlighted_bytes, &Default::default(), columns);
+    let ascii = app.ascii_text(&highlighted_bytes, &Default::default(), columns);
 
     let highlighted_hex = highlighted_span_contents(&hex);
     assert!(
@@ -227,8 +289,9 @@ fn render_highlights_related_panes_for_selected_json_field() {
 #[test]
 fn render_shows_hint_for_omitted_default_enum_variant() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -262,8 +325,9 @@ fn render_shows_hint_for_omitted_default_enum_variant() {
 #[test]
 fn cycling_selected_enum_updates_json_and_status() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -290,8 +354,9 @@ fn cycling_selected_enum_updates_json_and_status() {
 #[test]
 fn selected_content_scrolls_into_view() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -310,7 +375,7 @@ fn selected_content_scrolls_into_view() {
 
     let json = app.current_json();
     let selected_path = app.current_selected_path(&json).unwrap();
-    let protobuf_text = app.protobuf_text(Some(&selected_path));
+    let protobuf_text = app.protobuf_text(Some(&selected_path), &[]);
     let highlighted_bytes = app
         .current_inspector()
         .highlighted_byte_indices(&selected_path)
@@ -323,8 +388,9 @@ fn selected_content_scrolls_into_view() {
 #[test]
 fn render_respects_shared_display_columns() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -339,8 +405,8 @@ fn render_respects_shared_display_columns() {
     )
     .unwrap();
 
-    let hex = app.hex_text(&Default::default(), 8);
-    let ascii = app.ascii_text(&Default::default(), 8);
+    let hex = app.hex_text(&Default::default(), &Default::default(), 8);
+    let ascii = app.ascii_text(&Default::default(), &Default::default(), 8);
 
     assert_eq!(hex.lines.len(), 4);
     assert_eq!(ascii.lines.len(), 4);
@@ -349,8 +415,9 @@ fn render_respects_shared_display_columns() {
 #[test]
 fn adjusting_columns_updates_status_message() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -374,8 +441,9 @@ fn adjusting_columns_updates_status_message() {
 #[test]
 fn expired_column_status_returns_to_footer_help() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -395,7 +463,7 @@ fn expired_column_status_returns_to_footer_help() {
 
     assert_eq!(
         app.status_line(),
-        "Ctrl-C quit | Ctrl-S save | Ctrl-X hex | Ctrl-A ascii | [ ] columns 8"
+        "F1 help | Ctrl-C quit | Ctrl-S save | / search"
     );
 
     app.clear_expired_status();
@@ -405,8 +473,9 @@ fn expired_column_status_returns_to_footer_help() {
 #[test]
 fn navigating_messages_switches_visible_payload() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -416,8 +485,9 @@ fn navigating_messages_switches_visible_payload() {
     second_json["click"]["x"] = serde_json::Value::from(100);
     second_json["click"]["y"] = serde_json::Value::from(42);
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         serde_json::to_string_pretty(&second_json)
             .unwrap()
             .as_bytes(),
@@ -435,7 +505,7 @@ fn navigating_messages_switches_visible_payload() {
     assert_eq!(app.message_suffix(), " (1/2)");
     assert_eq!(
         app.status_line(),
-        "Ctrl-C quit | Ctrl-S save | Ctrl-G picker | Ctrl-J/K msg 1/2 | Ctrl-X hex | Ctrl-A ascii | [ ] columns 16"
+        "F1 help | Ctrl-C quit | Ctrl-S save | / search | Ctrl-J/K msg 1/2"
     );
 
     app.navigate_message(1);
@@ -447,15 +517,16 @@ fn navigating_messages_switches_visible_payload() {
     app.last_status = None;
     assert_eq!(
         app.status_line(),
-        "Ctrl-C quit | Ctrl-S save | Ctrl-G picker | Ctrl-J/K msg 2/2 | Ctrl-X hex | Ctrl-A ascii | [ ] columns 16"
+        "F1 help | Ctrl-C quit | Ctrl-S save | / search | Ctrl-J/K msg 2/2"
     );
 }
 
 #[test]
 fn toggling_hex_pane_hides_it_and_updates_status() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -481,8 +552,9 @@ fn toggling_hex_pane_hides_it_and_updates_status() {
 #[test]
 fn hiding_both_byte_panes_removes_them_from_layout() {
     let inspector = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -511,15 +583,17 @@ fn hiding_both_byte_panes_removes_them_from_layout() {
 #[test]
 fn opening_message_selector_renders_modal() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
     .unwrap();
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -544,15 +618,17 @@ fn opening_message_selector_renders_modal() {
 #[test]
 fn message_selector_help_lines_use_distinct_key_colors() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
     .unwrap();
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -586,8 +662,9 @@ fn message_selector_help_lines_use_distinct_key_colors() {
 #[test]
 fn message_selector_submit_jumps_to_requested_message() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -596,8 +673,9 @@ fn message_selector_submit_jumps_to_requested_message() {
         serde_json::from_str::<serde_json::Value>(&first.canonical_json().unwrap()).unwrap();
     second_json["click"]["x"] = serde_json::Value::from(100);
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         serde_json::to_string_pretty(&second_json)
             .unwrap()
             .as_bytes(),
@@ -623,15 +701,17 @@ fn message_selector_submit_jumps_to_requested_message() {
 #[test]
 fn message_selector_rejects_out_of_range_message() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
     .unwrap();
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -654,8 +734,9 @@ fn message_selector_rejects_out_of_range_message() {
 #[test]
 fn message_selector_last_message_shortcut_jumps_to_end() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -664,8 +745,9 @@ fn message_selector_last_message_shortcut_jumps_to_end() {
         serde_json::from_str::<serde_json::Value>(&first.canonical_json().unwrap()).unwrap();
     second_json["click"]["x"] = serde_json::Value::from(100);
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         serde_json::to_string_pretty(&second_json)
             .unwrap()
             .as_bytes(),
@@ -691,8 +773,9 @@ fn message_selector_last_message_shortcut_jumps_to_end() {
 #[test]
 fn message_selector_first_message_shortcut_jumps_to_start() {
     let first = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         &sample_bytes(),
         InputFormat::Binary,
     )
@@ -701,8 +784,9 @@ fn message_selector_first_message_shortcut_jumps_to_start() {
         serde_json::from_str::<serde_json::Value>(&first.canonical_json().unwrap()).unwrap();
     second_json["click"]["x"] = serde_json::Value::from(100);
     let second = load_inspector(
-        schema_path().as_ref(),
+        &schema(),
         Some("SystemEvent"),
+        &[],
         serde_json::to_string_pretty(&second_json)
             .unwrap()
             .as_bytes(),
@@ -761,3 +845,1241 @@ fn highlighted_span_contents(text: &Text<'_>) -> Vec<String> {
         .map(|span| span.content.to_string())
         .collect()
 }
+
+#[test]
+fn diff_mode_highlights_fields_that_differ_from_pinned_message() {
+    let mut app = App::new(
+        click_inspectors(&[(42, 100), (42, 7)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.toggle_diff_mode();
+    assert_eq!(app.diff_pin, Some(0));
+    assert_eq!(
+        app.status_line(),
+        "Pinned message 1; Ctrl-J/K picks a message to compare"
+    );
+
+    app.navigate_message(1);
+    assert_eq!(
+        app.status_line(),
+        "Message 2 of 2 | 1 difference from message 1"
+    );
+
+    let diff_view = app.diff_view().unwrap();
+    let pinned = app.pinned_protobuf_text(&diff_view);
+    let current = app.protobuf_text(None, &diff_view.changes);
+    assert_eq!(diff_span_contents(&pinned), ["click {", "  y: 100", "}"]);
+    assert_eq!(diff_span_contents(&current), ["click {", "  y: 7", "}"]);
+
+    let hex = app.hex_text(&Default::default(), &diff_view.differing_bytes, 16);
+    assert_eq!(diff_span_contents(&hex), ["18", "07"]);
+
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    app.toggle_diff_mode();
+    assert_eq!(app.diff_pin, None);
+    assert_eq!(app.status_line(), "Unpinned message 1");
+}
+
+#[test]
+fn diff_view_is_kept_between_frames_and_rebuilt_on_edit_or_navigation() {
+    let mut app = App::new(
+        click_inspectors(&[(42, 100), (42, 7), (1, 100)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.toggle_diff_mode();
+    app.navigate_message(1);
+    let first = app.diff_view().unwrap();
+    snapshot_text(&mut app);
+    assert!(Rc::ptr_eq(&first, &app.diff_view().unwrap()));
+
+    app.edit_current(|inspector| inspector.apply_json(r#"{"click": {"x": 42, "y": 100}}"#))
+        .unwrap();
+    let edited = app.diff_view().unwrap();
+    assert!(!Rc::ptr_eq(&first, &edited));
+    assert!(edited.changes.is_empty());
+    assert!(edited.differing_bytes.is_empty());
+
+    app.undo_edit();
+    let undone = app.diff_view().unwrap();
+    assert_eq!(undone.changes.len(), 1);
+    assert_eq!(undone.differing_bytes, first.differing_bytes);
+
+    app.navigate_message(1);
+    let navigated = app.diff_view().unwrap();
+    assert_eq!(navigated.changes.len(), 1);
+    assert_ne!(navigated.differing_bytes, first.differing_bytes);
+
+    app.toggle_diff_mode();
+    assert!(app.diff_view().is_none());
+}
+
+#[test]
+fn detected_message_types_show_in_titles_and_message_list() {
+    let mut app = App::new(
+        vec![
+            trace_sample_inspector("trace-bundle-http.bin", "auto"),
+            trace_sample_inspector("trace-replay-request.bin", "auto"),
+        ],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    assert!(
+        app.message_list
+            .row(0, &app.inspectors[0])
+            .starts_with(r#"1    974 B  TraceBundle  {"alerts":"#)
+    );
+    assert!(
+        app.message_list
+            .row(1, &app.inspectors[1])
+            .contains(" B  TraceReplayRequest  {")
+    );
+    assert!(render_text(&mut app).contains("Protobuf TraceBundle"));
+
+    app.toggle_diff_mode();
+    app.navigate_message(1);
+    let rendered = render_text(&mut app);
+    assert!(rendered.contains("Pinned TraceBundle (1/2)"));
+    assert!(rendered.contains("Protobuf TraceReplayRequest"));
+}
+
+#[test]
+fn diff_mode_requires_multiple_messages() {
+    let mut app = App::new(
+        click_inspectors(&[(42, 100)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.toggle_diff_mode();
+
+    assert_eq!(app.diff_pin, None);
+    assert_eq!(app.status_line(), "Only one message is loaded");
+}
+
+/// Parses click events through one schema load so they share a message descriptor, as the
+/// messages of a multi-message inspector do.
+fn click_inspectors(clicks: &[(u32, u32)]) -> Vec<Inspector> {
+    let selection = load_message_selection(&schema(), Some("SystemEvent"), &[]).unwrap();
+
+    clicks
+        .iter()
+        .map(|&(x, y)| {
+            selection
+                .parse(&click_bytes(x, y), InputFormat::Binary)
+                .unwrap()
+        })
+        .collect()
+}
+
+fn click_bytes(x: u32, y: u32) -> Vec<u8> {
+    SystemEvent {
+        event: Some(SystemEventVariant::Click(MouseDown {
+            button: EnumOrUnknown::new(MouseButton::Left),
+            x,
+            y,
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .write_to_bytes()
+    .unwrap()
+}
+
+fn diff_span_contents(text: &Text<'_>) -> Vec<String> {
+    text.lines
+        .iter()
+        .flat_map(|line| line.spans.iter())
+        .filter(|span| span.style == diff_style())
+        .map(|span| span.content.to_string())
+        .collect()
+}
+
+#[test]
+fn search_jumps_cursor_to_match_and_selects_field() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_search();
+    type_search(&mut app, "NANOS");
+
+    assert_eq!(app.json_editor.cursor(), (8, 5));
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 1 of 1"));
+    let json = app.current_json();
+    assert_eq!(
+        app.current_selected_path(&json),
+        Some(vec![
+            selection::FieldPathSegment::Field("timestamp".to_owned()),
+            selection::FieldPathSegment::Field("nanos".to_owned()),
+        ])
+    );
+
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert!(app.search_prompt.is_none());
+    assert_eq!(app.status_line(), "Match 1 of 1");
+}
+
+#[test]
+fn search_steps_through_matches_and_wraps() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_search();
+    type_search(&mut app, "\"[xy]\"");
+    assert_eq!(app.search_prompt.as_ref().unwrap().error, None);
+    assert_eq!(app.search_result_summary().as_deref(), Some("No matches"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    assert!(app.search_options.regex);
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 1 of 2"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 2 of 2"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 1 of 2"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 2 of 2"));
+}
+
+#[test]
+fn search_respects_case_and_reports_invalid_regex() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_search();
+    type_search(&mut app, "LEFT");
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 1 of 1"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL));
+    assert!(!app.search_options.ignore_case);
+    assert_eq!(app.search_result_summary().as_deref(), Some("No matches"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    type_search(&mut app, "(");
+    assert!(
+        app.search_prompt
+            .as_ref()
+            .unwrap()
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("Invalid regex")
+    );
+}
+
+#[test]
+fn cancelling_search_restores_cursor() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_search();
+    type_search(&mut app, "clicked");
+    assert_ne!(app.json_editor.cursor(), (0, 0));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+
+    assert!(app.search_prompt.is_none());
+    assert_eq!(app.json_editor.cursor(), (0, 0));
+    assert!(app.json_editor.search_pattern().is_none());
+    assert_eq!(app.status_line(), "Search cancelled");
+}
+
+#[test]
+fn search_in_protobuf_pane_selects_the_fields_of_matching_lines() {
+    let mut app = App::new(
+        vec![trace_bundle_inspector("trace-bundle-http.bin")],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    let cursor_line = |app: &App<'_>| app.json_editor.lines()[app.json_editor.cursor().0].clone();
+
+    app.open_search();
+    type_search(&mut app, "duration_ms");
+    assert_eq!(app.search_result_summary().as_deref(), Some("No matches"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+    assert!(app.search_options.protobuf);
+    assert!(app.json_editor.search_pattern().is_none());
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 1 of 3"));
+    assert!(cursor_line(&app).contains("\"durationMs\": 184"));
+    let protobuf = app.protobuf_text(None, &[]);
+    assert_eq!(protobuf.lines[8].spans[1].content, "duration_ms");
+    assert_eq!(protobuf.lines[8].spans[1].style, search_match_style());
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 2 of 3"));
+    assert!(cursor_line(&app).contains("\"durationMs\": 97"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    app.handle_search_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(app.search_result_summary().as_deref(), Some("Match 3 of 3"));
+
+    app.handle_search_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    app.repeat_search(true);
+    assert_eq!(app.status_line(), "Match 1 of 3");
+
+    app.open_search();
+    type_search(&mut app, "durationMs");
+    assert_eq!(app.search_result_summary().as_deref(), Some("No matches"));
+    app.handle_search_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert!(app.protobuf_search.is_none());
+}
+
+#[test]
+fn slash_inside_json_string_is_not_a_search_key() {
+    assert!(!search::cursor_in_json_string(r#"  "reason": "a/b""#, 2));
+    assert!(search::cursor_in_json_string(r#"  "reason": "a/b""#, 14));
+    assert!(search::cursor_in_json_string(r#"  "reason": "a\"/b""#, 16));
+    assert!(!search::cursor_in_json_string(r#"  "reason": "a\\","#, 18));
+}
+
+#[test]
+fn find_messages_jumps_between_messages_matching_a_filter() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 50), (3, 5), (4, 50), (5, 50)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.set_current_message(2);
+
+    app.open_message_query();
+    type_message_query(&mut app, ".click.y > 10");
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    app.handle_message_query_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert!(app.message_query.is_none());
+    assert_eq!(app.current_index, 3);
+    assert_eq!(
+        app.status_line(),
+        "Message 4 of 5 | match 2 of 3 (messages 2, 4, 5)"
+    );
+
+    app.step_message_match(true);
+    assert_eq!(app.current_index, 4);
+    app.step_message_match(true);
+    assert_eq!(app.current_index, 1);
+    app.step_message_match(false);
+    assert_eq!(app.current_index, 4);
+
+    app.open_message_query();
+    assert_eq!(app.message_query.as_deref(), Some(".click.y > 10"));
+}
+
+#[test]
+fn find_messages_reports_filter_errors_and_empty_results() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.step_message_match(true);
+    assert_eq!(
+        app.status_line(),
+        "No message matches; press Ctrl-F to find messages"
+    );
+
+    app.open_message_query();
+    type_message_query(&mut app, ".click.y >");
+    app.handle_message_query_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert!(app.message_query.is_some());
+    assert!(app.status_line().starts_with("Invalid filter"));
+
+    app.message_query = Some(".click.x == 9".to_owned());
+    app.handle_message_query_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert!(app.message_query.is_none());
+    assert!(app.message_matches.is_none());
+    assert_eq!(app.status_line(), "No messages match .click.x == 9");
+}
+
+#[test]
+fn find_messages_skips_messages_a_select_filter_yields_nothing_for() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 50), (3, 5)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_message_query();
+    type_message_query(&mut app, "select(.click.y > 10)");
+    app.handle_message_query_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    assert_eq!(app.current_index, 1);
+    assert_eq!(
+        app.message_matches
+            .as_ref()
+            .map(|matches| matches.indices.clone()),
+        Some(vec![1])
+    );
+}
+
+#[test]
+fn find_messages_matches_when_any_output_of_an_iterating_filter_is_true() {
+    let mut app = App::new(
+        vec![
+            trace_bundle_inspector("trace-bundle-queue.bin"),
+            trace_bundle_inspector("trace-bundle-http.bin"),
+            trace_bundle_inspector("trace-bundle-queue.bin"),
+            trace_bundle_inspector("trace-bundle-http.bin"),
+        ],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_message_query();
+    type_message_query(
+        &mut app,
+        r#".services[].spans[] | .spanId == "span-worker""#,
+    );
+    app.handle_message_query_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    assert_eq!(app.current_index, 1);
+    assert_eq!(
+        app.message_matches
+            .as_ref()
+            .map(|matches| matches.indices.clone()),
+        Some(vec![1, 3])
+    );
+}
+
+#[test]
+fn find_messages_requires_multiple_messages() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.open_message_query();
+
+    assert!(app.message_query.is_none());
+    assert_eq!(app.status_line(), "Only one message is loaded");
+}
+
+#[test]
+fn message_list_shows_sizes_and_summaries_and_selects_with_arrows() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (200, 50), (3, 5)]),
+        SaveTargets::default(),
+        DisplayOptions {
+            message_summary: Some(r#""x=\(.click.x)""#.to_owned()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    app.toggle_message_list();
+    assert!(app.message_list.visible && app.message_list.focused);
+    app.handle_message_list_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+    assert_eq!(app.current_index, 1);
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    app.handle_message_list_key(KeyEvent::new(KeyCode::End, KeyModifiers::NONE));
+    assert_eq!(app.current_index, 2);
+    app.handle_message_list_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert!(app.message_list.visible && !app.message_list.focused);
+    assert_eq!(app.status_line(), "Editing message 3");
+
+    app.toggle_message_list();
+    assert!(app.message_list.focused);
+    app.handle_message_list_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert!(!app.message_list.visible && !app.message_list.focused);
+}
+
+#[test]
+fn message_list_rows_follow_edits_and_default_to_compact_json() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        app.message_list.row(0, &app.inspectors[0]),
+        r#"1      6 B  {"click":{"button":"Left","x":1,"y":5}}"#
+    );
+
+    app.current_inspector_mut()
+        .apply_json(r#"{"reason": "edited"}"#)
+        .unwrap();
+
+    assert_eq!(
+        app.message_list.row(0, &app.inspectors[0]),
+        r#"1      8 B  {"reason":"edited"}"#
+    );
+}
+
+#[test]
+fn undo_and_redo_step_through_json_and_enum_edits() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    let original_json = app.current_json();
+
+    move_cursor_to(&mut app, "\"x\": 42");
+    app.json_editor.move_cursor(CursorMove::End);
+    app.json_editor.move_cursor(CursorMove::Back);
+    app.json_editor.insert_char('7');
+    let json = app.current_json();
+    app.edit_current(|inspector| inspector.apply_json(&json))
+        .unwrap();
+    move_cursor_to(&mut app, "\"Left\"");
+    app.cycle_selected_enum(1);
+    assert!(app.current_json().contains("\"x\": 427"));
+    assert!(app.current_json().contains("\"Right\""));
+
+    app.undo_edit();
+    assert!(app.current_json().contains("\"Left\""));
+    assert_eq!(app.status_line(), "Undid edit | modified");
+
+    app.undo_edit();
+    assert_eq!(app.current_json(), original_json);
+    assert_eq!(app.status_line(), "Undid edit | unmodified");
+
+    app.undo_edit();
+    assert_eq!(app.status_line(), "Nothing to undo");
+
+    app.redo_edit();
+    assert!(app.current_json().contains("\"x\": 427"));
+    assert_eq!(app.status_line(), "Redid edit | modified");
+
+    app.last_status = None;
+    assert!(app.status_line().starts_with("Modified | F1 help"));
+}
+
+#[test]
+fn reverting_restores_the_original_message_and_can_be_undone() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    let original_json = app.current_json();
+
+    app.revert_message();
+    assert_eq!(app.status_line(), "Message 1 has no edits to revert");
+
+    app.edit_current(|inspector| inspector.apply_json(r#"{"reason": "edited"}"#))
+        .unwrap();
+    app.refresh_json_editor();
+    app.set_current_message(1);
+    app.set_current_message(0);
+    assert!(app.current_json().contains("edited"));
+
+    app.revert_message();
+    assert_eq!(app.current_json(), original_json);
+    assert_eq!(
+        app.status_line(),
+        "Reverted message 1 to its original bytes; Ctrl-Z undoes the revert"
+    );
+    assert!(!app.is_current_modified());
+
+    app.undo_edit();
+    assert!(app.current_json().contains("edited"));
+    assert!(app.is_current_modified());
+}
+
+#[test]
+fn quitting_without_edits_exits_immediately() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    app.request_exit();
+
+    assert!(app.exit);
+    assert!(!app.quit_prompt);
+}
+
+#[test]
+fn quitting_with_unsaved_edits_prompts_to_save_discard_or_cancel() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5), (3, 5)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.set_current_message(1);
+    app.edit_current(|inspector| inspector.apply_json(r#"{"reason": "edited"}"#))
+        .unwrap();
+    app.refresh_json_editor();
+    app.last_status = None;
+
+    app.request_exit();
+    assert!(!app.exit);
+    assert!(app.quit_prompt);
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    app.handle_quit_prompt_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert!(!app.quit_prompt && !app.exit);
+    assert_eq!(app.status_line(), "Quit cancelled");
+
+    app.request_exit();
+    app.handle_quit_prompt_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+    assert!(app.exit);
+}
+
+#[test]
+fn saving_from_the_quit_prompt_exits_once_every_edit_is_saved() {
+    let dir = tempdir().unwrap();
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets {
+            json: Some(Utf8PathBuf::from_path_buf(dir.path().join("message.json")).unwrap()),
+            ..SaveTargets::default()
+        },
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    for index in [1, 0] {
+        app.set_current_message(index);
+        app.edit_current(|inspector| inspector.apply_json(r#"{"reason": "edited"}"#))
+            .unwrap();
+    }
+
+    app.request_exit();
+    app.handle_quit_prompt_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE));
+    assert!(!app.exit && !app.quit_prompt);
+    assert_eq!(
+        app.status_line(),
+        "Saved message 1; Message 2 still unsaved"
+    );
+
+    app.set_current_message(1);
+    app.save_outputs();
+    app.request_exit();
+    assert!(app.exit);
+}
+
+#[test]
+fn save_all_writes_every_message_in_the_input_framing() {
+    let dir = tempdir().unwrap();
+    let path = Utf8PathBuf::from_path_buf(dir.path().join("messages.hex")).unwrap();
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets {
+            all: Some(path.clone()),
+            ..SaveTargets::default()
+        },
+        DisplayOptions::default(),
+    )
+    .unwrap()
+    .with_stream_layout(StreamLayout {
+        framing: Framing::Lines,
+        payload_format: InputFormat::Hex,
+        stream_format: InputFormat::Hex,
+        had_trailing_newline: true,
+        trailer: Vec::new(),
+    });
+    app.set_current_message(1);
+    app.edit_current(|inspector| inspector.apply_json(r#"{"click": {"x": 7}}"#))
+        .unwrap();
+
+    app.save_all_messages();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        format!("{}\n{}\n", hex::encode(click_bytes(1, 5)), "52021007")
+    );
+    assert_eq!(app.status_line(), format!("Saved 2 messages to {path}"));
+    app.request_exit();
+    assert!(app.exit);
+}
+
+#[test]
+fn save_all_can_change_the_encoding_and_keeps_length_prefixes() {
+    let dir = tempdir().unwrap();
+    let json_path = Utf8PathBuf::from_path_buf(dir.path().join("messages.jsonl")).unwrap();
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets {
+            all: Some(json_path.clone()),
+            all_format: Some(InputFormat::Json),
+            ..SaveTargets::default()
+        },
+        DisplayOptions::default(),
+    )
+    .unwrap()
+    .with_stream_layout(StreamLayout {
+        framing: Framing::Lines,
+        payload_format: InputFormat::Base64,
+        stream_format: InputFormat::Base64,
+        had_trailing_newline: false,
+        trailer: Vec::new(),
+    });
+
+    app.save_all_messages();
+    assert_eq!(
+        std::fs::read_to_string(&json_path).unwrap(),
+        concat!(
+            r#"{"click":{"button":"Left","x":1,"y":5}}"#,
+            "\n",
+            r#"{"click":{"button":"Left","x":2,"y":5}}"#
+        )
+    );
+
+    let stream_path = Utf8PathBuf::from_path_buf(dir.path().join("messages.bin")).unwrap();
+    app.save_targets = SaveTargets {
+        all: Some(stream_path.clone()),
+        ..SaveTargets::default()
+    };
+    app.stream_layout = Some(StreamLayout {
+        framing: Framing::Delimited,
+        payload_format: InputFormat::Binary,
+        stream_format: InputFormat::Binary,
+        had_trailing_newline: false,
+        trailer: Vec::new(),
+    });
+
+    app.save_all_messages();
+    let mut expected = vec![6];
+    expected.extend(click_bytes(1, 5));
+    expected.push(6);
+    expected.extend(click_bytes(2, 5));
+    assert_eq!(std::fs::read(&stream_path).unwrap(), expected);
+}
+
+#[test]
+fn save_all_requires_a_target() {
+    let mut app = App::new(
+        click_inspectors(&[(1, 5), (2, 5)]),
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    assert!(!app.save_all_messages());
+    assert_eq!(
+        app.status_line(),
+        "No save-all target was configured. Pass --save-all or --save-all-in-place."
+    );
+}
+
+fn sample_inspector() -> Inspector {
+    load_inspector(
+        &schema(),
+        Some("SystemEvent"),
+        &[],
+        &sample_bytes(),
+        InputFormat::Binary,
+    )
+    .unwrap()
+}
+
+fn type_search(app: &mut App<'_>, text: &str) {
+    for ch in text.chars() {
+        app.handle_search_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+    }
+}
+
+fn type_message_query(app: &mut App<'_>, text: &str) {
+    for ch in text.chars() {
+        app.handle_message_query_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+    }
+}
+
+#[test]
+fn protobuf_pane_shows_timestamps_in_utc_until_local_time_is_toggled() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    let rendered = render_text(&mut app);
+    assert!(rendered.contains("timestamp: 1970-01-15T06:56:07.000000123Z"));
+    assert!(!rendered.contains("seconds:"));
+
+    app.toggle_local_time();
+    assert!(app.display_options.local_time);
+    assert_eq!(app.status_line(), "Timestamps shown in local time");
+
+    app.toggle_local_time();
+    assert_eq!(app.status_line(), "Timestamps shown in UTC");
+}
+
+#[test]
+fn details_pane_shows_how_the_selected_field_is_encoded() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.toggle_details_pane();
+    assert_eq!(app.status_line(), "Encoding pane shown");
+    app.last_status = None;
+
+    move_cursor_to(&mut app, "\"x\"");
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    move_cursor_to(&mut app, "\"reason\"");
+    let details = detail_text(&app);
+    assert!(details.contains(&"Field   reason = 2".to_owned()));
+    assert!(details.contains(&"Type    string".to_owned()));
+    assert!(details.contains(&"Wire    2 LEN".to_owned()));
+    assert!(details.contains(&"Length  0c @ 9..10 = 12".to_owned()));
+    assert!(!details.iter().any(|line| line.starts_with("As")));
+
+    app.toggle_details_pane();
+    assert_eq!(app.status_line(), "Encoding pane hidden");
+}
+
+#[test]
+fn details_pane_reads_varints_as_each_integer_type_and_notes_omitted_defaults() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+
+    move_cursor_to(&mut app, "\"y\"");
+    let details = detail_text(&app);
+    assert_eq!(details[0], "Field   y = 3");
+    assert_eq!(
+        details.last().unwrap(),
+        "As      uint64 100 | int64 100 | sint64 50 | int32 100"
+    );
+
+    move_cursor_to(&mut app, "\"button\"");
+    assert_eq!(
+        detail_text(&app),
+        ["Not encoded: the field holds its default value"]
+    );
+}
+
+fn detail_text(app: &App<'_>) -> Vec<String> {
+    let json = app.current_json();
+    let selected_path = app.current_selected_path(&json);
+    app.detail_lines(selected_path.as_deref())
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn clicking_a_byte_selects_the_innermost_field_encoding_it() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    render_text(&mut app);
+    let bytes = sample_bytes();
+    let x_value = bytes
+        .windows(2)
+        .position(|pair| pair == [0x10, 0x2a])
+        .unwrap()
+        + 1;
+
+    let position = byte_position(&app, app.pane_areas.hex.unwrap(), x_value, 3);
+    click(&mut app, position);
+    assert_eq!(app.status_line(), format!("Byte {x_value} is in click.x"));
+    assert_cursor_on(&app, "\"x\"");
+
+    let reason_tag = bytes.iter().position(|&byte| byte == 0x12).unwrap();
+    let position = byte_position(&app, app.pane_areas.ascii.unwrap(), reason_tag, 1);
+    click(&mut app, position);
+    assert_eq!(app.status_line(), format!("Byte {reason_tag} is in reason"));
+    assert_cursor_on(&app, "\"reason\"");
+}
+
+#[test]
+fn clicking_a_protobuf_line_selects_its_field() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    render_text(&mut app);
+    let protobuf = app.pane_areas.protobuf;
+
+    click(&mut app, Position::new(protobuf.x + 3, protobuf.y + 1));
+    assert_cursor_on(&app, "\"timestamp\"");
+
+    click(&mut app, Position::new(protobuf.x + 3, protobuf.y + 2));
+    assert_cursor_on(&app, "\"reason\"");
+    assert_eq!(app.pane_areas.protobuf_scroll, 0);
+}
+
+#[test]
+fn mouse_wheel_scrolls_the_byte_pane_within_its_bounds() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions {
+            columns: Some(2),
+            ..DisplayOptions::default()
+        },
+    )
+    .unwrap();
+    render_text(&mut app);
+    let hex = app.pane_areas.hex.unwrap();
+    let inside_hex = Position::new(hex.x + 1, hex.y + 1);
+
+    scroll(&mut app, inside_hex, MouseEventKind::ScrollDown);
+    render_text(&mut app);
+    assert_eq!(app.pane_areas.byte_scroll, 3);
+
+    for _ in 0..10 {
+        scroll(&mut app, inside_hex, MouseEventKind::ScrollDown);
+    }
+    render_text(&mut app);
+    let rows = sample_bytes().len().div_ceil(2);
+    assert_eq!(
+        usize::from(app.pane_areas.byte_scroll),
+        rows - usize::from(hex.height - 2)
+    );
+
+    scroll(&mut app, inside_hex, MouseEventKind::ScrollUp);
+    render_text(&mut app);
+    assert_eq!(
+        usize::from(app.pane_areas.byte_scroll),
+        rows - usize::from(hex.height - 2) - 3
+    );
+}
+
+fn click(app: &mut App<'_>, position: Position) {
+    app.handle_mouse(MouseEvent {
+        kind: MouseEventKind::Down(event::MouseButton::Left),
+        column: position.x,
+        row: position.y,
+        modifiers: KeyModifiers::NONE,
+    });
+}
+
+fn scroll(app: &mut App<'_>, position: Position, kind: MouseEventKind) {
+    app.handle_mouse(MouseEvent {
+        kind,
+        column: position.x,
+        row: position.y,
+        modifiers: KeyModifiers::NONE,
+    });
+}
+
+/// Where `byte_index` is drawn in a byte pane whose bytes are `cell_width` characters apart.
+fn byte_position(app: &App<'_>, area: Rect, byte_index: usize, cell_width: u16) -> Position {
+    let columns = app.pane_areas.byte_columns;
+    let row = (byte_index / columns) as u16 - app.pane_areas.byte_scroll;
+    let column = (byte_index % columns) as u16;
+
+    Position::new(area.x + 1 + column * cell_width, area.y + 1 + row)
+}
+
+fn assert_cursor_on(app: &App<'_>, needle: &str) {
+    let (row, column) = app.json_editor.cursor();
+    assert_eq!(
+        app.json_editor.lines()[row].find(needle),
+        Some(column),
+        "cursor at {row}:{column}"
+    );
+}
+
+#[test]
+fn hex_editing_overwrites_bytes_and_redecodes_the_message() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    move_cursor_to(&mut app, "\"x\"");
+    app.toggle_hex_editor();
+    let x_field = sample_bytes()
+        .windows(2)
+        .position(|pair| pair == [0x10, 0x2a])
+        .unwrap();
+    assert_eq!(app.hex_editor.unwrap().cursor, x_field);
+
+    type_hex(&mut app, &[KeyCode::Right, KeyCode::Char('2')]);
+    assert!(render_text(&mut app).contains("10 2_"));
+    type_hex(&mut app, &[KeyCode::Char('B')]);
+
+    assert!(
+        app.json_editor
+            .lines()
+            .iter()
+            .any(|line| line.contains("\"x\": 43"))
+    );
+    assert_eq!(
+        app.status_line(),
+        format!("Byte {} is in click.y", x_field + 2)
+    );
+    assert!(app.is_current_modified());
+
+    app.undo_edit();
+    assert!(
+        app.json_editor
+            .lines()
+            .iter()
+            .any(|line| line.contains("\"x\": 42"))
+    );
+}
+
+#[test]
+fn hex_editing_keeps_bytes_that_do_not_decode() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.toggle_hex_editor();
+    type_hex(
+        &mut app,
+        &[KeyCode::Insert, KeyCode::Char('f'), KeyCode::Char('f')],
+    );
+
+    assert!(
+        app.status_line()
+            .starts_with("Bytes do not decode at offset 0: "),
+        "{}",
+        app.status_line()
+    );
+    app.last_status = None;
+    let rendered = snapshot_text(&mut app);
+    assert_snapshot!(rendered);
+
+    type_hex(&mut app, &[KeyCode::Backspace]);
+    assert_eq!(app.current_inspector().decode_error(), None);
+    assert_eq!(app.current_inspector().bytes().unwrap(), sample_bytes());
+    assert!(!app.is_current_modified());
+
+    type_hex(
+        &mut app,
+        &[KeyCode::End, KeyCode::Char('1'), KeyCode::Char('2')],
+    );
+    assert!(app.status_line().starts_with(&format!(
+        "Bytes do not decode at offset {}: ",
+        sample_bytes().len()
+    )));
+    type_hex(&mut app, &[KeyCode::Char('0'), KeyCode::Char('0')]);
+    assert_eq!(app.current_inspector().decode_error(), None);
+    assert_eq!(
+        app.status_line(),
+        "End of the message; typed bytes are appended"
+    );
+}
+
+fn type_hex(app: &mut App<'_>, keys: &[KeyCode]) {
+    for &key in keys {
+        app.handle_hex_key(KeyEvent::new(key, KeyModifiers::NONE));
+    }
+}
+
+fn trace_bundle_inspector(sample: &str) -> Inspector {
+    trace_sample_inspector(sample, "TraceBundle")
+}
+
+/// Parses a sample payload with the trace bundle schema as `message`, which may be `auto`.
+fn trace_sample_inspector(sample: &str, message: &str) -> Inspector {
+    let schema = SchemaSource::Proto {
+        paths: vec![Utf8PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../protogen/proto/trace-bundle.proto"
+        ))],
+        includes: Vec::new(),
+    };
+    let bytes = std::fs::read(format!(
+        "{}/../protogen/samples/{sample}",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+
+    load_inspector(&schema, Some(message), &[], &bytes, InputFormat::Binary).unwrap()
+}
+
+fn tree_texts(app: &App<'_>) -> Vec<String> {
+    app.tree_lines().into_iter().map(|line| line.text).collect()
+}
+
+fn press_tree(app: &mut App<'_>, keys: &[KeyCode]) {
+    for key in keys {
+        app.handle_tree_key(KeyEvent::new(*key, KeyModifiers::NONE));
+    }
+}
+
+#[test]
+fn protobuf_tree_folds_messages_repeated_fields_and_maps_with_counts() {
+    let mut app = App::new(
+        vec![trace_bundle_inspector("trace-bundle-http.bin")],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.focus_protobuf_tree();
+    assert!(app.protobuf_tree.focused);
+    assert_eq!(app.protobuf_tree.cursor, 0);
+
+    // Down to the first span, then fold it, the spans around it, and the service around those.
+    press_tree(&mut app, &[KeyCode::Down; 5]);
+    assert_eq!(tree_texts(&app)[5], "  spans {");
+    press_tree(&mut app, &[KeyCode::Left]);
+    assert_eq!(tree_texts(&app)[5], "  spans { … } (6 fields)");
+    press_tree(&mut app, &[KeyCode::Left]);
+    assert_eq!(tree_texts(&app)[5], "  spans [ … ] (2 elements)");
+    assert_cursor_on(&app, "\"spans\"");
+    press_tree(&mut app, &[KeyCode::Left]);
+    assert_eq!(app.protobuf_tree.cursor, 2);
+    assert_eq!(tree_texts(&app)[2], "services { … } (4 fields)");
+
+    // Unfolding the service keeps the spans folded inside it.
+    press_tree(&mut app, &[KeyCode::Right]);
+    let texts = tree_texts(&app);
+    assert_eq!(texts[2], "services {");
+    assert_eq!(texts[5], "  spans [ … ] (2 elements)");
+
+    press_tree(&mut app, &[KeyCode::Char('-')]);
+    let texts = tree_texts(&app);
+    assert_eq!(
+        texts,
+        [
+            "export_id: \"exp-http-20260326\"",
+            "captured_at: 2024-03-26T15:06:40.987654321Z",
+            "services [ … ] (2 elements)",
+            "labels { … } (2 entries)",
+            "user { … } (3 fields)",
+            "alerts [ … ] (2 elements)",
+            "http { … } (4 fields)",
+            "raw_envelope: \"trace-http-envelope\"",
+        ]
+    );
+    assert_eq!(app.protobuf_tree.cursor, 2);
+
+    press_tree(&mut app, &[KeyCode::Char('+')]);
+    assert_eq!(tree_texts(&app).len(), 141);
+}
+
+#[test]
+fn protobuf_tree_folds_map_entries_one_at_a_time() {
+    let mut app = App::new(
+        vec![trace_bundle_inspector("trace-bundle-http.bin")],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    app.focus_protobuf_tree();
+
+    let first_label = tree_texts(&app)
+        .iter()
+        .position(|text| text == "labels {")
+        .unwrap();
+    press_tree(&mut app, &vec![KeyCode::Down; first_label]);
+    press_tree(&mut app, &[KeyCode::Left]);
+
+    let texts = tree_texts(&app);
+    assert_eq!(texts[first_label], "labels { … } (2 fields)");
+    assert_eq!(texts[first_label + 1], "labels {");
+    assert_eq!(texts.len(), 138);
+}
+
+#[test]
+fn protobuf_tree_follows_edits_undo_and_local_time() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    let has_line =
+        |app: &App<'_>, text: &str| tree_texts(app).iter().any(|line| line.trim() == text);
+    assert!(has_line(&app, "x: 42"));
+
+    move_cursor_to(&mut app, "\"x\": 42");
+    app.json_editor.move_cursor(CursorMove::End);
+    app.json_editor.move_cursor(CursorMove::Back);
+    app.json_editor.insert_char('7');
+    let json = app.current_json();
+    app.edit_current(|inspector| inspector.apply_json(&json))
+        .unwrap();
+    assert!(has_line(&app, "x: 427"));
+
+    app.undo_edit();
+    assert!(has_line(&app, "x: 42"));
+
+    // Timestamps are rendered into the nodes, so they are rebuilt in the other time zone.
+    app.toggle_local_time();
+    assert!(app.tree_nodes.borrow().is_none());
+}
+
+#[test]
+fn protobuf_tree_takes_focus_from_the_json_editor_and_gives_it_back() {
+    let mut app = App::new(
+        vec![sample_inspector()],
+        SaveTargets::default(),
+        DisplayOptions::default(),
+    )
+    .unwrap();
+    move_cursor_to(&mut app, "\"reason\"");
+
+    app.focus_protobuf_tree();
+    assert_eq!(app.protobuf_tree.cursor, 1);
+    assert!(app.status_line().starts_with("Protobuf tree:"));
+    app.last_status = None;
+    assert!(app.status_line().contains("Tab/Esc JSON"));
+
+    press_tree(&mut app, &[KeyCode::Down, KeyCode::Enter]);
+    assert_eq!(tree_texts(&app)[2], "click { … } (2 fields)");
+    assert_cursor_on(&app, "\"click\"");
+    let protobuf = app.protobuf_text(None, &[]);
+    assert_eq!(protobuf.lines[2].spans[0].style, tree_cursor_style());
+
+    press_tree(&mut app, &[KeyCode::Tab]);
+    assert!(!app.protobuf_tree.focused);
+    assert_eq!(app.status_line(), "JSON editor focused");
+    // Folds stay until they are unfolded.
+    assert_eq!(tree_texts(&app)[2], "click { … } (2 fields)");
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::selection::{FieldPath, ProtobufNode};
+
+/// Lines moved by `PageUp` and `PageDown` in the tree.
+pub(super) const TREE_PAGE: usize = 10;
+
+/// A run of Protobuf pane lines that can be folded into one: a message, or the elements of a
+/// repeated or map field.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Fold {
+    path: FieldPath,
+    elements: bool,
+    /// How many runs enclose this one, which tells a map entry from the message value it holds.
+    depth: usize,
+}
+
+/// A line of the Protobuf pane as drawn, with folded runs shown as a single line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct TreeLine {
+    pub(super) path: FieldPath,
+    pub(super) text: String,
+    /// Unfolded runs the line belongs to, outermost first.
+    folds: Vec<Fold>,
+    /// The run this line stands for while it is folded.
+    folded: Option<Fold>,
+}
+
+/// Folded runs and cursor of the Protobuf pane, which takes focus from the JSON editor to be
+/// browsed as a tree. Runs are folded by path, so folds stay in place while the message is edited.
+#[derive(Debug, Default)]
+pub(super) struct ProtobufTree {
+    pub(super) focused: bool,
+    /// Index of the line under the cursor while the tree has focus.
+    pub(super) cursor: usize,
+    folded: HashSet<Fold>,
+}
+
+impl ProtobufTree {
+    pub(super) fn lines(&self, nodes: &[ProtobufNode]) -> Vec<TreeLine> {
+        let mut lines = Vec::new();
+        for node in nodes {
+            self.push_lines(node, &mut Vec::new(), &mut lines);
+        }
+        lines
+    }
+
+    fn push_lines(&self, node: &ProtobufNode, folds: &mut Vec<Fold>, lines: &mut Vec<TreeLine>) {
+        match node {
+            ProtobufNode::Line(line) => lines.push(TreeLine {
+                path: line.path.clone(),
+                text: line.text.clone(),
+                folds: folds.clone(),
+                folded: None,
+            }),
+            ProtobufNode::Message {
+                open,
+                children,
+                close,
+            } => {
+                let fold = Fold {
+                    path: open.path.clone(),
+                    elements: false,
+                    depth: folds.len(),
+                };
+                if self.folded.contains(&fold) {
+                    lines.push(TreeLine {
+                        path: open.path.clone(),
+                        text: format!(
+                            "{} … }} ({})",
+                            open.text,
+                            count(children.len(), "field", "fields")
+                        ),
+                        folds: folds.clone(),
+                        folded: Some(fold),
+                    });
+                    return;
+                }
+
+                folds.push(fold);
+                lines.push(TreeLine {
+                    path: open.path.clone(),
+                    text: open.text.clone(),
+                    folds: folds.clone(),
+                    folded: None,
+                });
+                for child in children {
+                    self.push_lines(child, folds, lines);
+                }
+                lines.push(TreeLine {
+                    path: close.path.clone(),
+                    text: close.text.clone(),
+                    folds: folds.clone(),
+                    folded: None,
+                });
+                folds.pop();
+            }
+            ProtobufNode::Elements {
+                path,
+                text,
+                map,
+                elements,
+            } => {
+                let fold = Fold {
+                    path: path.clone(),
+                    elements: true,
+                    depth: folds.len(),
+                };
+                if self.folded.contains(&fold) {
+                    let text = if *map {
+                        format!(
+                            "{text} {{ … }} ({})",
+                            count(elements.len(), "entry", "entries")
+                        )
+                    } else {
+                        format!(
+                            "{text} [ … ] ({})",
+                            count(elements.len(), "element", "elements")
+                        )
+                    };
+                    lines.push(TreeLine {
+                        path: path.clone(),
+                        text,
+                        folds: folds.clone(),
+                        folded: Some(fold),
+                    });
+                    return;
+                }
+
+                folds.push(fold);
+                for element in elements {
+                    self.push_lines(element, folds, lines);
+                }
+                folds.pop();
+            }
+        }
+    }
+
+    /// Folds the innermost run around the cursor and moves the cursor onto it.
+    pub(super) fn fold(&mut self, lines: &[TreeLine]) -> bool {
+        let Some(fold) = lines
+            .get(self.cursor)
+            .and_then(|line| line.folds.last())
+            .cloned()
+        else {
+            return false;
+        };
+
+        if let Some(first) = lines.iter().position(|line| line.folds.contains(&fold)) {
+            self.cursor = first;
+        }
+        self.folded.insert(fold)
+    }
+
+    /// Unfolds the run under the cursor.
+    pub(super) fn unfold(&mut self, lines: &[TreeLine]) -> bool {
+        lines
+            .get(self.cursor)
+            .and_then(|line| line.folded.as_ref())
+            .is_some_and(|fold| self.folded.remove(fold))
+    }
+
+    pub(super) fn toggle(&mut self, lines: &[TreeLine]) -> bool {
+        self.unfold(lines) || self.fold(lines)
+    }
+
+    /// Folds every run of `nodes`, nested ones included.
+    pub(super) fn fold_all(&mut self, nodes: &[ProtobufNode]) {
+        self.fold_all_at(nodes, 0);
+    }
+
+    fn fold_all_at(&mut self, nodes: &[ProtobufNode], depth: usize) {
+        for node in nodes {
+            match node {
+                ProtobufNode::Line(_) => {}
+                ProtobufNode::Message { open, children, .. } => {
+                    self.folded.insert(Fold {
+                        path: open.path.clone(),
+                        elements: false,
+                        depth,
+                    });
+                    self.fold_all_at(children, depth + 1);
+                }
+                ProtobufNode::Elements { path, elements, .. } => {
+                    self.folded.insert(Fold {
+                        path: path.clone(),
+                        elements: true,
+                        depth,
+                    });
+                    self.fold_all_at(elements, depth + 1);
+                }
+            }
+        }
+    }
+
+    pub(super) fn unfold_all(&mut self) {
+        self.folded.clear();
+    }
+
+    /// Moves the cursor by `delta` lines, staying within `len` lines.
+    pub(super) fn move_cursor(&mut self, delta: isize, len: usize) {
+        self.cursor = self
+            .cursor
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+}
+
+/// Keys the focused tree handles itself; everything else keeps its usual meaning.
+pub(super) fn is_tree_key(key: &KeyEvent) -> bool {
+    if key
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    {
+        return false;
+    }
+
+    matches!(
+        key.code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Enter
+            | KeyCode::Char(' ' | '+' | '=' | '-')
+            | KeyCode::Tab
+            | KeyCode::Esc
+    )
+}
+
+fn count(len: usize, singular: &str, plural: &str) -> String {
+    format!("{len} {}", if len == 1 { singular } else { plural })
+}
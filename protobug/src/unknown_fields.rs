@@ -0,0 +1,140 @@
+use protobuf::{
+    MessageDyn,
+    reflect::{ReflectValueBox, ReflectValueRef, RuntimeFieldType, RuntimeType},
+};
+
+use crate::json;
+
+/// Copies unknown fields from `original` onto `edited`, which is assumed to be a re-parse of an
+/// edited copy of the same message. Nested messages are matched by field and map values by key.
+/// A repeated element is matched to the edited element with the same known fields, so it follows
+/// the element when others are removed or reordered; an element that was itself edited only keeps
+/// its unknown fields while the field's length is unchanged. Unknown fields inside anything the
+/// edit removed are dropped.
+pub(crate) fn restore_unknown_fields(original: &dyn MessageDyn, edited: &mut dyn MessageDyn) {
+    *edited.mut_unknown_fields_dyn() = original.unknown_fields_dyn().clone();
+
+    for field in original.descriptor_dyn().fields() {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(_)) => {
+                if !field.has_field(original) || !field.has_field(edited) {
+                    continue;
+                }
+
+                let original_child = field.get_message(original);
+                restore_unknown_fields(&*original_child, field.mut_message(edited));
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(_)) => {
+                let original_items = field.get_repeated(original);
+                let mut edited_items = field.mut_repeated(edited);
+                if !(0..original_items.len()).any(|index| {
+                    matches!(
+                        original_items.get(index),
+                        ReflectValueRef::Message(item) if contains_unknown_fields(&*item)
+                    )
+                }) {
+                    continue;
+                }
+
+                let same_length = original_items.len() == edited_items.len();
+                let edited_json = (0..edited_items.len())
+                    .map(|index| match edited_items.get(index) {
+                        ReflectValueRef::Message(item) => known_fields_json(&*item),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                let mut restored = vec![false; edited_json.len()];
+
+                for index in 0..original_items.len() {
+                    let ReflectValueRef::Message(original_item) = original_items.get(index) else {
+                        continue;
+                    };
+                    if !contains_unknown_fields(&*original_item) {
+                        continue;
+                    }
+
+                    let original_json = known_fields_json(&*original_item);
+                    let target = std::iter::once(index)
+                        .chain(0..edited_json.len())
+                        .filter(|&candidate| candidate < edited_json.len() && !restored[candidate])
+                        .find(|&candidate| {
+                            original_json.is_some() && edited_json[candidate] == original_json
+                        })
+                        .or_else(|| (same_length && !restored[index]).then_some(index));
+                    let Some(target) = target else {
+                        continue;
+                    };
+                    let ReflectValueRef::Message(edited_item) = edited_items.get(target) else {
+                        continue;
+                    };
+
+                    let mut merged = edited_item.clone_box();
+                    restore_unknown_fields(&*original_item, &mut *merged);
+                    edited_items.set(target, ReflectValueBox::Message(merged));
+                    restored[target] = true;
+                }
+            }
+            RuntimeFieldType::Map(_, RuntimeType::Message(_)) => {
+                let original_entries = field.get_map(original);
+                let mut merged_entries = Vec::new();
+
+                {
+                    let edited_entries = field.get_map(&*edited);
+                    for (key, original_value) in &original_entries {
+                        let ReflectValueRef::Message(original_value) = original_value else {
+                            continue;
+                        };
+                        if !contains_unknown_fields(&*original_value) {
+                            continue;
+                        }
+                        let Some(ReflectValueRef::Message(edited_value)) =
+                            edited_entries.get(key.clone())
+                        else {
+                            continue;
+                        };
+
+                        let mut merged = edited_value.clone_box();
+                        restore_unknown_fields(&*original_value, &mut *merged);
+                        merged_entries.push((key.to_box(), merged));
+                    }
+                }
+
+                let mut edited_entries = field.mut_map(edited);
+                for (key, merged) in merged_entries {
+                    edited_entries.insert(key, ReflectValueBox::Message(merged));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The known fields of `message` as canonical JSON, the form the edited message was parsed from.
+fn known_fields_json(message: &dyn MessageDyn) -> Option<String> {
+    protobuf_json_mapping::print_to_string_with_options(message, &json::print_options()).ok()
+}
+
+fn contains_unknown_fields(message: &dyn MessageDyn) -> bool {
+    if message.unknown_fields_dyn().iter().next().is_some() {
+        return true;
+    }
+
+    message
+        .descriptor_dyn()
+        .fields()
+        .any(|field| match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(_)) => {
+                field.has_field(message) && contains_unknown_fields(&*field.get_message(message))
+            }
+            RuntimeFieldType::Repeated(RuntimeType::Message(_)) => field
+                .get_repeated(message)
+                .into_iter()
+                .any(|item| matches!(item, ReflectValueRef::Message(item) if contains_unknown_fields(&*item))),
+            RuntimeFieldType::Map(_, RuntimeType::Message(_)) => {
+                field.get_map(message).into_iter().any(|(_, value)| {
+                    matches!(value, ReflectValueRef::Message(value) if contains_unknown_fields(&*value))
+                })
+            }
+            _ => false,
+        })
+}
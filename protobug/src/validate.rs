@@ -3,8 +3,9 @@ use error_stack::Report;
 
 pub fn validate_schema(
     schema_path: Utf8PathBuf,
+    includes: &[Utf8PathBuf],
 ) -> std::result::Result<String, Report<anyhow::Error>> {
-    crate::schema::validate_schema(schema_path)
+    crate::schema::validate_schema(schema_path, includes)
 }
 
 #[cfg(test)]
@@ -22,7 +23,7 @@ mod tests {
 
     #[test]
     fn validate_schema_returns_the_requested_schema_descriptor() {
-        let descriptor = validate_schema(schema_path()).unwrap();
+        let descriptor = validate_schema(schema_path(), &[]).unwrap();
 
         assert!(descriptor.contains("name: \"SystemEvent\""));
         assert!(!descriptor.contains("name: \"Timestamp\""));
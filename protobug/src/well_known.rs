@@ -0,0 +1,159 @@
+use jiff::{Timestamp, tz::TimeZone};
+use protobuf::{
+    MessageDyn,
+    reflect::{ReflectFieldRef, ReflectValueRef},
+};
+use serde_json::Value;
+
+use crate::selection;
+
+const TIMESTAMP_MESSAGE: &str = "google.protobuf.Timestamp";
+const DURATION_MESSAGE: &str = "google.protobuf.Duration";
+const STRUCT_MESSAGE: &str = "google.protobuf.Struct";
+const VALUE_MESSAGE: &str = "google.protobuf.Value";
+const LIST_VALUE_MESSAGE: &str = "google.protobuf.ListValue";
+
+const WRAPPER_MESSAGES: &[&str] = &[
+    "google.protobuf.DoubleValue",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int64Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Int32Value",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.BoolValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.BytesValue",
+];
+
+/// `message` on one line when it is a well-known type that reads better that way than as its
+/// fields: timestamps in RFC 3339, in UTC or with the local offset, durations in seconds such as
+/// `1.5s`, wrappers as the value they wrap, and `Struct`, `Value`, and `ListValue` as the JSON
+/// they stand for.
+pub(crate) fn format(message: &dyn MessageDyn, local_time: bool) -> Option<String> {
+    let descriptor = message.descriptor_dyn();
+
+    match descriptor.full_name() {
+        TIMESTAMP_MESSAGE => {
+            let (seconds, nanos) = seconds_and_nanos(message)?;
+            let timestamp = Timestamp::new(seconds, nanos).ok()?;
+            Some(if local_time {
+                timestamp
+                    .display_with_offset(TimeZone::system().to_offset(timestamp))
+                    .to_string()
+            } else {
+                timestamp.to_string()
+            })
+        }
+        DURATION_MESSAGE => {
+            let (seconds, nanos) = seconds_and_nanos(message)?;
+            Some(format_duration(seconds, nanos))
+        }
+        STRUCT_MESSAGE | VALUE_MESSAGE | LIST_VALUE_MESSAGE => Some(to_json(message).to_string()),
+        name if WRAPPER_MESSAGES.contains(&name) => Some(selection::format_value(
+            descriptor
+                .field_by_number(1)?
+                .get_singular_field_or_default(message),
+        )),
+        _ => None,
+    }
+}
+
+fn seconds_and_nanos(message: &dyn MessageDyn) -> Option<(i64, i32)> {
+    let descriptor = message.descriptor_dyn();
+
+    let ReflectValueRef::I64(seconds) = descriptor
+        .field_by_number(1)?
+        .get_singular_field_or_default(message)
+    else {
+        return None;
+    };
+    let ReflectValueRef::I32(nanos) = descriptor
+        .field_by_number(2)?
+        .get_singular_field_or_default(message)
+    else {
+        return None;
+    };
+
+    Some((seconds, nanos))
+}
+
+/// A duration in seconds with as many fractional digits as it needs, e.g. `1.5s` or `-0.000001s`.
+fn format_duration(seconds: i64, nanos: i32) -> String {
+    let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+    let mut text = format!("{sign}{}", seconds.unsigned_abs());
+
+    if nanos != 0 {
+        let fraction = format!("{:09}", nanos.unsigned_abs());
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+
+    text.push('s');
+    text
+}
+
+/// The JSON a `Struct`, `Value`, or `ListValue` stands for.
+fn to_json(message: &dyn MessageDyn) -> Value {
+    let descriptor = message.descriptor_dyn();
+
+    match descriptor.full_name() {
+        STRUCT_MESSAGE => {
+            let mut object = serde_json::Map::new();
+            for field in descriptor.fields() {
+                if let ReflectFieldRef::Map(map) = field.get_reflect(message) {
+                    for (key, value) in &map {
+                        if let Some(key) = key.to_str() {
+                            object.insert(key.to_owned(), value_to_json(value));
+                        }
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        LIST_VALUE_MESSAGE => {
+            let mut items = Vec::new();
+            for field in descriptor.fields() {
+                if let ReflectFieldRef::Repeated(repeated) = field.get_reflect(message) {
+                    items.extend(
+                        (0..repeated.len()).map(|index| value_to_json(repeated.get(index))),
+                    );
+                }
+            }
+            Value::Array(items)
+        }
+        // A `Value` holds at most one of its `kind` fields.
+        _ => descriptor
+            .fields()
+            .find_map(|field| match field.get_reflect(message) {
+                ReflectFieldRef::Optional(optional) => optional.value().map(value_to_json),
+                _ => None,
+            })
+            .unwrap_or(Value::Null),
+    }
+}
+
+fn value_to_json(value: ReflectValueRef<'_>) -> Value {
+    match value {
+        ReflectValueRef::Message(message) => to_json(&*message),
+        ReflectValueRef::F64(number) => serde_json::Number::from_f64(number)
+            .map_or_else(|| Value::String(number.to_string()), Value::Number),
+        ReflectValueRef::String(text) => Value::String(text.to_owned()),
+        ReflectValueRef::Bool(flag) => Value::Bool(flag),
+        // `null_value`, the only enum a `Value` holds.
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_show_only_the_fractional_digits_they_need() {
+        assert_eq!(format_duration(1, 500_000_000), "1.5s");
+        assert_eq!(format_duration(3, 0), "3s");
+        assert_eq!(format_duration(0, 1_000), "0.000001s");
+        assert_eq!(format_duration(-2, -250_000_000), "-2.25s");
+        assert_eq!(format_duration(0, -250_000_000), "-0.25s");
+    }
+}
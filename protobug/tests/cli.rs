@@ -0,0 +1,97 @@
+use std::process::{Command, Output};
+
+use protobuf::{EnumOrUnknown, Message as _};
+use protogen::system_event::{
+    SystemEvent,
+    system_event::{Event as SystemEventVariant, MouseButton, MouseDown},
+};
+use tempfile::tempdir;
+
+const SCHEMA: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../protogen/proto/system-event.proto"
+);
+
+/// A gRPC-Web trailer frame: the trailer flag, a 4-byte length, and the trailers as HTTP headers.
+const TRAILER: &[u8] = b"\x80\x00\x00\x00\x0fgrpc-status:0\r\n";
+
+fn click(x: u32) -> Vec<u8> {
+    SystemEvent {
+        event: Some(SystemEventVariant::Click(MouseDown {
+            button: EnumOrUnknown::new(MouseButton::Left),
+            x,
+            y: 5,
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .write_to_bytes()
+    .unwrap()
+}
+
+fn grpc_frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn protobug(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_protobug"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn single_grpc_web_frame_keeps_its_trailers() {
+    let dir = tempdir().unwrap();
+    let body_path = dir.path().join("body.bin");
+    let mut body = grpc_frame(&click(1));
+    body.extend_from_slice(TRAILER);
+    std::fs::write(&body_path, &body).unwrap();
+    let body_path = body_path.to_str().unwrap();
+
+    let edited = protobug(&[
+        "edit",
+        "--schema",
+        SCHEMA,
+        "--message",
+        "SystemEvent",
+        "--file",
+        body_path,
+        "--framing",
+        "grpc",
+        "--filter",
+        ".click.x = 7",
+        "--print-format",
+        "hex",
+    ]);
+    let mut expected = grpc_frame(&click(7));
+    expected.extend_from_slice(TRAILER);
+    assert_eq!(
+        String::from_utf8(edited.stdout).unwrap(),
+        format!("{}\n", hex::encode(expected))
+    );
+
+    let inspected = protobug(&[
+        "inspect",
+        "--schema",
+        SCHEMA,
+        "--message",
+        "SystemEvent",
+        "--file",
+        body_path,
+        "--framing",
+        "grpc",
+        "--print-format",
+        "binary",
+    ]);
+    assert_eq!(inspected.stdout, body);
+}